@@ -0,0 +1,85 @@
+use windows::Win32::{
+    Foundation::BOOL, System::WinRT::EventRegistrationToken, UI::Input::KeyboardAndMouse::*,
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{event::Subscription, hotkey::Hotkey, Result, WebView};
+
+// A key press the browser would treat as a shortcut: anything with Ctrl or Alt, function keys,
+// Esc and the like. Plain typing isn't reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AcceleratorKey {
+    // Compares equal to e.g. `"Ctrl+S".parse::<Hotkey>()`.
+    pub key: Hotkey,
+    // Held down and repeating.
+    pub is_repeat: bool,
+}
+
+fn is_down(vk: VIRTUAL_KEY) -> bool {
+    unsafe { GetKeyState(vk as i32) < 0 }
+}
+
+fn modifiers() -> HOT_KEY_MODIFIERS {
+    let mut modifiers = HOT_KEY_MODIFIERS::default();
+    if is_down(VK_CONTROL) {
+        modifiers |= MOD_CONTROL;
+    }
+    if is_down(VK_MENU) {
+        modifiers |= MOD_ALT;
+    }
+    if is_down(VK_SHIFT) {
+        modifiers |= MOD_SHIFT;
+    }
+    if is_down(VK_LWIN) || is_down(VK_RWIN) {
+        modifiers |= MOD_WIN;
+    }
+    modifiers
+}
+
+impl WebView {
+    // Sees shortcuts such as Ctrl+S, F5 or Ctrl+P before the browser does. Returning true
+    // consumes the key, so neither the browser nor the page gets it.
+    pub fn on_accelerator_key(
+        &self,
+        mut f: impl FnMut(&AcceleratorKey) -> bool + 'static,
+    ) -> Result<Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.controller.AcceleratorKeyPressed(
+                AcceleratorKeyPressedEventHandler::create(Box::new(
+                    move |_controller, args: Option<ICoreWebView2AcceleratorKeyPressedEventArgs>| {
+                        if let Some(args) = args {
+                            let mut kind = COREWEBVIEW2_KEY_EVENT_KIND::default();
+                            args.KeyEventKind(&mut kind)?;
+                            // Key ups follow whatever happened to the key down.
+                            if kind != COREWEBVIEW2_KEY_EVENT_KIND_KEY_DOWN
+                                && kind != COREWEBVIEW2_KEY_EVENT_KIND_SYSTEM_KEY_DOWN
+                            {
+                                return Ok(());
+                            }
+                            let mut vk = 0;
+                            let mut status = COREWEBVIEW2_PHYSICAL_KEY_STATUS::default();
+                            args.VirtualKey(&mut vk)?;
+                            args.PhysicalKeyStatus(&mut status)?;
+                            let key = AcceleratorKey {
+                                key: Hotkey {
+                                    modifiers: modifiers(),
+                                    vk,
+                                },
+                                is_repeat: status.WasKeyDown.as_bool(),
+                            };
+                            if f(&key) {
+                                args.SetHandled(BOOL::from(true))?;
+                            }
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut token,
+            )?;
+        }
+        let controller = self.controller.clone();
+        Ok(self.track(move || unsafe { controller.RemoveAcceleratorKeyPressed(token) }))
+    }
+}
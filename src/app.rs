@@ -0,0 +1,324 @@
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::{
+    command::{Command, CommandSource, Commands},
+    hotkey::{self, Hotkey},
+    menu::{self, MenuItem, COMMAND_ID_BASE},
+    pool::WebViewPool,
+    shutdown::Shutdown,
+    tray::{self, TrayIcon, WM_TRAYICON},
+    window::WindowRunner,
+    Error, Result, WebView, WebViewBuilder,
+};
+
+const TRAY_ID: u32 = 2;
+
+// Declarative app configuration, usually loaded from a JSON file. Every menu item,
+// tray click and hotkey names a command, with an optional payload, which is run by its
+// `App::register_command` handler or else passed to `App::on_command`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub tray: Option<TrayConfig>,
+    pub menu: Vec<MenuItem>,
+    pub hotkeys: Vec<HotkeyConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TrayConfig {
+    pub icon: Option<String>,
+    pub tooltip: String,
+    // Run on a left click. A right click shows `menu`.
+    pub command: Option<String>,
+    pub payload: Value,
+    pub menu: Vec<MenuItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HotkeyConfig {
+    pub keys: String,
+    pub command: String,
+    #[serde(default)]
+    pub payload: Value,
+}
+
+// Gets the label of the invoking window before the JS arguments.
+type GlobalBinding = Rc<RefCell<dyn FnMut(&str, Vec<Value>) -> std::result::Result<Value, String>>>;
+
+pub struct App {
+    config: AppConfig,
+    commands: Commands,
+    shutdown: Shutdown,
+    windows: Rc<RefCell<Vec<(String, WebView)>>>,
+    global_bindings: RefCell<Vec<(String, GlobalBinding)>>,
+}
+
+impl App {
+    pub fn new(config: AppConfig) -> Self {
+        Self {
+            config,
+            commands: Commands::new(),
+            shutdown: Shutdown::new(),
+            windows: Rc::new(RefCell::new(Vec::new())),
+            global_bindings: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn from_config_file(path: impl AsRef<Path>) -> Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(Self::new(serde_json::from_str(&json)?))
+    }
+
+    pub fn config(&self) -> &AppConfig {
+        &self.config
+    }
+
+    // Windows registered with `app.shutdown().manage(..)` quit together; see `Shutdown`.
+    pub fn shutdown(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
+    // `n` warm, hidden windows for popups, with the default builder; see `WebViewPool`.
+    pub fn webview_pool(&self, n: usize) -> Result<WebViewPool> {
+        self.webview_pool_with(n, WebViewBuilder::default)
+    }
+
+    pub fn webview_pool_with(
+        &self,
+        n: usize,
+        make: impl Fn() -> WebViewBuilder<'static> + 'static,
+    ) -> Result<WebViewPool> {
+        WebViewPool::new(n, make)
+    }
+
+    // Makes the window addressable by `label` and gives it every global binding, plus
+    // `taco.windows.postMessage(label, data)` and `taco.windows.onMessage(f)` for talking to
+    // the other windows and `taco.invokeCommand(id, payload)` for running app commands. Call
+    // it before navigating so the page sees them.
+    pub fn add_window(&self, label: &str, webview: &WebView) -> Result<()> {
+        let windows = self.windows.clone();
+        let from = label.to_string();
+        webview.bind_unsafe("_taco_postWindowMessage", move |params| {
+            if let [Value::String(to), data] = &params[..] {
+                let target = windows
+                    .borrow()
+                    .iter()
+                    .find(|(l, _)| l == to)
+                    .map(|(_, webview)| webview.clone());
+                let target = target.ok_or_else(|| format!("No window labelled {}", to))?;
+                target
+                    .emit("window-message", json!({ "from": from, "data": data }))
+                    .map_err(|err| format!("{:?}", err))?;
+                return Ok(Value::Null);
+            }
+            Err("Usage: _taco_postWindowMessage(label, data)".into())
+        });
+        webview.init(include_str!("windows.js"))?;
+
+        let commands = self.commands.clone();
+        let source = CommandSource::Script(label.to_string());
+        webview.bind_unsafe("_taco_invokeCommand", move |params| {
+            if let [Value::String(id), payload] = &params[..] {
+                let command = Command {
+                    id: id.clone(),
+                    payload: payload.clone(),
+                    source: source.clone(),
+                };
+                return commands.dispatch(&command).map(|_| Value::Null);
+            }
+            Err("Usage: _taco_invokeCommand(id, payload)".into())
+        });
+        webview.init(include_str!("command.js"))?;
+
+        for (name, f) in self.global_bindings.borrow().iter() {
+            bind_labelled(webview, label, name, f.clone());
+        }
+        self.windows
+            .borrow_mut()
+            .push((label.to_string(), webview.clone()));
+        Ok(())
+    }
+
+    pub fn window(&self, label: &str) -> Option<WebView> {
+        self.windows
+            .borrow()
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|(_, webview)| webview.clone())
+    }
+
+    pub fn labels(&self) -> Vec<String> {
+        self.windows
+            .borrow()
+            .iter()
+            .map(|(l, _)| l.clone())
+            .collect()
+    }
+
+    pub fn emit_to(&self, label: &str, event: &str, payload: impl Serialize) -> Result<()> {
+        self.labelled(label)?.emit(event, payload)?;
+        Ok(())
+    }
+
+    pub fn eval_in(&self, label: &str, js: &str) -> Result<()> {
+        self.labelled(label)?.eval(js)?;
+        Ok(())
+    }
+
+    pub fn broadcast(&self, event: &str, payload: impl Serialize) -> Result<()> {
+        let payload = serde_json::to_value(payload)?;
+        for webview in self.webviews() {
+            webview.emit(event, &payload)?;
+        }
+        Ok(())
+    }
+
+    pub fn eval_all(&self, js: &str) -> Result<()> {
+        for webview in self.webviews() {
+            webview.eval(js)?;
+        }
+        Ok(())
+    }
+
+    fn labelled(&self, label: &str) -> Result<WebView> {
+        self.window(label)
+            .ok_or_else(|| Error::UnknownWindow(label.to_string()))
+    }
+
+    // Cloned out, so that callbacks run by eval can add windows.
+    fn webviews(&self) -> Vec<WebView> {
+        self.windows
+            .borrow()
+            .iter()
+            .map(|(_, w)| w.clone())
+            .collect()
+    }
+
+    // Binds `name` on every window added with `add_window`, now and later.
+    pub fn bind_global<F>(&self, name: &str, f: F)
+    where
+        F: FnMut(&str, Vec<Value>) -> std::result::Result<Value, String> + 'static,
+    {
+        let f: GlobalBinding = Rc::new(RefCell::new(f));
+        for (label, webview) in self.windows.borrow().iter() {
+            bind_labelled(webview, label, name, f.clone());
+        }
+        self.global_bindings
+            .borrow_mut()
+            .push((name.to_string(), f));
+    }
+
+    // Runs `f` for the command `id` from any menu, the tray, a hotkey or script, with the
+    // payload deserialized as `P`; use `Value` to take it as is. Replaces an earlier handler.
+    pub fn register_command<P: DeserializeOwned>(
+        &self,
+        id: &str,
+        f: impl FnMut(P, &CommandSource) + 'static,
+    ) {
+        self.commands.register(id, f);
+    }
+
+    // Gets the ids of the commands without a `register_command` handler.
+    pub fn on_command(&self, mut f: impl FnMut(&str) + 'static) {
+        self.commands.set_fallback(move |command| f(&command.id));
+    }
+
+    // Creates the menu bar, tray icon and hotkeys declared in the config on the given window.
+    pub fn attach<T: 'static>(&self, wrun: &mut WindowRunner<T>) -> Result<()> {
+        let hwnd = wrun.hwnd();
+        let mut commands = Vec::new();
+        // Before anything is created, so that a typo doesn't leave half of it behind.
+        let hotkeys = self
+            .config
+            .hotkeys
+            .iter()
+            .map(|config| config.keys.parse())
+            .collect::<Result<Vec<Hotkey>>>()?;
+
+        if !self.config.menu.is_empty() {
+            let menu = menu::build_menu(
+                &self.config.menu,
+                false,
+                &CommandSource::Menu,
+                &mut commands,
+            );
+            unsafe { SetMenu(hwnd, menu) };
+        }
+
+        if let Some(config) = &self.config.tray {
+            let icon = tray::load_icon(config.icon.as_deref());
+            let tray = TrayIcon::new(hwnd, TRAY_ID, icon, &config.tooltip)?;
+            let popup = (!config.menu.is_empty())
+                .then(|| menu::build_menu(&config.menu, true, &CommandSource::Tray, &mut commands));
+            let command = config.command.clone().map(|id| Command {
+                id,
+                payload: config.payload.clone(),
+                source: CommandSource::Tray,
+            });
+            let dispatcher = self.commands.clone();
+            wrun.add_event_listener(WM_TRAYICON, move |wparam, lparam, _| {
+                if wparam.0 as u32 != tray.id() {
+                    return;
+                }
+                match (lparam.0 as u32, &command, popup) {
+                    (WM_LBUTTONUP, Some(command), _) => {
+                        // Only script callers get to hear about bad payloads.
+                        let _ = dispatcher.dispatch(command);
+                    }
+                    (WM_RBUTTONUP, _, Some(popup)) => menu::show_popup_menu(hwnd, popup),
+                    _ => {}
+                }
+            });
+        }
+
+        let mut hotkey_ids = Vec::new();
+        for (config, hotkey) in self.config.hotkeys.iter().zip(&hotkeys) {
+            let id = (COMMAND_ID_BASE + commands.len()) as i32;
+            // Another app may hold the same keys.
+            if let Err(err) = hotkey::register(hwnd, id, hotkey) {
+                for id in &hotkey_ids {
+                    hotkey::unregister(hwnd, *id);
+                }
+                return Err(err);
+            }
+            hotkey_ids.push(id);
+            commands.push(Command {
+                id: config.command.clone(),
+                payload: config.payload.clone(),
+                source: CommandSource::Hotkey,
+            });
+        }
+        wrun.add_event_listener(WM_DESTROY, move |_, _, _| {
+            for id in &hotkey_ids {
+                hotkey::unregister(hwnd, *id);
+            }
+        });
+
+        let commands = Rc::new(commands);
+        let dispatcher = self.commands.clone();
+        let dispatch = move |id: usize| {
+            let command = id
+                .checked_sub(COMMAND_ID_BASE)
+                .and_then(|i| commands.get(i));
+            if let Some(command) = command {
+                let _ = dispatcher.dispatch(command);
+            }
+        };
+        // The low word of WPARAM is the menu item id.
+        let on_menu = dispatch.clone();
+        wrun.add_event_listener(WM_COMMAND, move |wparam, _, _| on_menu(wparam.0 & 0xFFFF));
+        wrun.add_event_listener(WM_HOTKEY, move |wparam, _, _| dispatch(wparam.0));
+
+        Ok(())
+    }
+}
+
+fn bind_labelled(webview: &WebView, label: &str, name: &str, f: GlobalBinding) {
+    let label = label.to_string();
+    webview.bind_unsafe(name, move |params| (f.borrow_mut())(&label, params));
+}
@@ -0,0 +1,78 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{
+    protocol::{Request, Response, Route},
+    resource::mime_type,
+};
+
+pub const ASSET_ORIGIN: &str = "https://app.taco.localhost";
+// How asset requests appear to protocol handlers, and what the builder url may start with.
+pub const ASSET_URL: &str = "taco://app/";
+
+// In-memory files, e.g. from `include_str!`, include_dir or rust-embed, keyed by their path
+// relative to the app root (`index.html`, `js/main.js`, ...).
+#[derive(Clone, Debug, Default)]
+pub struct Assets {
+    files: HashMap<String, Cow<'static, [u8]>>,
+}
+
+impl Assets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, path: impl AsRef<str>, bytes: impl Into<Cow<'static, [u8]>>) {
+        let path = path.as_ref().replace('\\', "/");
+        self.files
+            .insert(path.trim_start_matches('/').to_string(), bytes.into());
+    }
+
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path).map(|bytes| bytes.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    fn respond(&self, request: &Request) -> Response {
+        let path = request
+            .uri
+            .trim_start_matches(ASSET_URL)
+            .split(&['?', '#'][..])
+            .next()
+            .unwrap_or_default();
+        let path = if path.is_empty() || path.ends_with('/') {
+            format!("{}index.html", path)
+        } else {
+            path.to_string()
+        };
+
+        match self.get(&path) {
+            Some(bytes) => Response::ok(bytes).with_header("Content-Type", mime_type(&path)),
+            None => Response::not_found(),
+        }
+    }
+
+    pub(crate) fn into_route(self) -> Route {
+        Route {
+            scheme: "taco".to_string(),
+            host: Some("app".to_string()),
+            handler: Box::new(move |request| self.respond(request)),
+        }
+    }
+}
+
+impl<K: AsRef<str>, V: Into<Cow<'static, [u8]>>> FromIterator<(K, V)> for Assets {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut assets = Self::new();
+        for (path, bytes) in iter {
+            assets.insert(path, bytes);
+        }
+        assets
+    }
+}
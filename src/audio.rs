@@ -0,0 +1,192 @@
+use std::{cell::Cell, ptr, rc::Rc};
+
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::{BOOL, E_POINTER},
+        Media::Audio::*,
+        System::{Com::*, Power::*, WinRT::EventRegistrationToken},
+    },
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{event::Subscription, Result, WebView};
+
+// Audio from the page is rendered by the WebView2 browser processes, not by the app itself,
+// so the app-level session is the set of WASAPI sessions owned by any of those processes.
+// A session only exists after its process started playing, so an empty one is not an error.
+pub struct AudioSession {
+    volumes: Vec<ISimpleAudioVolume>,
+}
+
+impl AudioSession {
+    pub fn for_processes(pids: &[u32]) -> Result<Self> {
+        let mut volumes = Vec::new();
+        unsafe {
+            let enumerator: IMMDeviceEnumerator =
+                CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+            let device = enumerator.GetDefaultAudioEndpoint(eRender, eConsole)?;
+
+            let mut manager: Option<IAudioSessionManager2> = None;
+            device.Activate(
+                &IAudioSessionManager2::IID,
+                CLSCTX_ALL,
+                ptr::null(),
+                &mut manager as *mut _ as _,
+            )?;
+            let manager = manager.ok_or_else(|| windows::core::Error::fast_error(E_POINTER))?;
+
+            let sessions = manager.GetSessionEnumerator()?;
+            for i in 0..sessions.GetCount()? {
+                let control: IAudioSessionControl2 = sessions.GetSession(i)?.cast()?;
+                if let Ok(pid) = control.GetProcessId() {
+                    if pids.contains(&pid) {
+                        volumes.push(control.cast()?);
+                    }
+                }
+            }
+        }
+        Ok(Self { volumes })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.volumes.is_empty()
+    }
+
+    pub fn volume(&self) -> Result<f32> {
+        match self.volumes.first() {
+            Some(volume) => Ok(unsafe { volume.GetMasterVolume()? }),
+            None => Ok(1.),
+        }
+    }
+
+    // `level` ranges from 0.0 to 1.0.
+    pub fn set_volume(&self, level: f32) -> Result<()> {
+        for volume in &self.volumes {
+            unsafe { volume.SetMasterVolume(level.clamp(0., 1.), ptr::null())? };
+        }
+        Ok(())
+    }
+
+    pub fn is_muted(&self) -> Result<bool> {
+        match self.volumes.first() {
+            Some(volume) => Ok(unsafe { volume.GetMute()? }.as_bool()),
+            None => Ok(false),
+        }
+    }
+
+    pub fn set_muted(&self, muted: bool) -> Result<()> {
+        for volume in &self.volumes {
+            unsafe { volume.SetMute(muted, ptr::null())? };
+        }
+        Ok(())
+    }
+}
+
+impl WebView {
+    pub fn set_muted(&self, muted: bool) -> Result<&Self> {
+        let core: ICoreWebView2_8 = self.core.cast()?;
+        unsafe { core.SetIsMuted(muted)? };
+        Ok(self)
+    }
+
+    pub fn is_muted(&self) -> Result<bool> {
+        let core: ICoreWebView2_8 = self.core.cast()?;
+        let mut muted = BOOL::default();
+        unsafe { core.IsMuted(&mut muted)? };
+        Ok(muted.as_bool())
+    }
+
+    pub fn is_playing_audio(&self) -> Result<bool> {
+        let core: ICoreWebView2_8 = self.core.cast()?;
+        let mut playing = BOOL::default();
+        unsafe { core.IsDocumentPlayingAudio(&mut playing)? };
+        Ok(playing.as_bool())
+    }
+
+    // Keeps the display on and the screensaver off while the page plays audio or shows a
+    // fullscreen element (e.g. a video), for media players.
+    pub fn keep_awake_while_playing(&self) -> Result<Subscription> {
+        // (playing audio, fullscreen)
+        let state = Rc::new(Cell::new((false, false)));
+        let update = move |state: (bool, bool)| unsafe {
+            let flags = match state {
+                (false, false) => ES_CONTINUOUS,
+                _ => ES_CONTINUOUS | ES_DISPLAY_REQUIRED | ES_SYSTEM_REQUIRED,
+            };
+            SetThreadExecutionState(flags);
+        };
+
+        let core: ICoreWebView2_8 = self.core.cast()?;
+        let mut audio_token = EventRegistrationToken::default();
+        let mut fullscreen_token = EventRegistrationToken::default();
+        unsafe {
+            let s = state.clone();
+            core.IsDocumentPlayingAudioChanged(
+                IsDocumentPlayingAudioChangedEventHandler::create(Box::new(move |core, _args| {
+                    if let Some(core) = core {
+                        let mut playing = BOOL::default();
+                        core.cast::<ICoreWebView2_8>()?
+                            .IsDocumentPlayingAudio(&mut playing)?;
+                        s.set((playing.as_bool(), s.get().1));
+                        update(s.get());
+                    }
+                    Ok(())
+                })),
+                &mut audio_token,
+            )?;
+
+            self.core.ContainsFullScreenElementChanged(
+                ContainsFullScreenElementChangedEventHandler::create(Box::new(
+                    move |core, _args| {
+                        if let Some(core) = core {
+                            let mut fullscreen = BOOL::default();
+                            core.ContainsFullScreenElement(&mut fullscreen)?;
+                            state.set((state.get().0, fullscreen.as_bool()));
+                            update(state.get());
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut fullscreen_token,
+            )?;
+        }
+        let c = self.core.clone();
+        Ok(self.track(move || unsafe {
+            update((false, false));
+            core.RemoveIsDocumentPlayingAudioChanged(audio_token)?;
+            c.RemoveContainsFullScreenElementChanged(fullscreen_token)
+        }))
+    }
+
+    pub fn browser_process_ids(&self) -> Result<Vec<u32>> {
+        let mut pids = Vec::new();
+        unsafe {
+            let mut pid = 0;
+            self.core.BrowserProcessId(&mut pid)?;
+            pids.push(pid);
+
+            if let Ok(environment) = self.environment.cast::<ICoreWebView2Environment8>() {
+                let infos = environment.GetProcessInfos()?;
+                let mut count = 0;
+                infos.Count(&mut count)?;
+                for i in 0..count {
+                    let mut pid = 0;
+                    infos.GetValueAtIndex(i)?.ProcessId(&mut pid)?;
+                    if !pids.contains(&(pid as u32)) {
+                        pids.push(pid as u32);
+                    }
+                }
+            }
+        }
+        Ok(pids)
+    }
+
+    // The audio sessions of this app, for an app-level volume slider.
+    pub fn audio_session(&self) -> Result<AudioSession> {
+        let mut pids = self.browser_process_ids()?;
+        pids.push(std::process::id());
+        AudioSession::for_processes(&pids)
+    }
+}
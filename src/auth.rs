@@ -0,0 +1,77 @@
+use serde_json::{json, Value};
+
+use crate::{event::Subscription, send_devtools_method, Result, WebView};
+
+#[derive(Clone, Debug)]
+pub struct AuthChallenge {
+    // `https://example.com` for a server, the proxy's address for a proxy.
+    pub origin: String,
+    // `basic`, `digest`, `ntlm` or `negotiate`.
+    pub scheme: String,
+    pub realm: String,
+    pub is_proxy: bool,
+}
+
+impl AuthChallenge {
+    fn from_params(params: &Value) -> Self {
+        let challenge = &params["authChallenge"];
+        let text = |key: &str| challenge[key].as_str().unwrap_or_default().to_string();
+        Self {
+            origin: text("origin"),
+            scheme: text("scheme").to_ascii_lowercase(),
+            realm: text("realm"),
+            is_proxy: challenge["source"] == "Proxy",
+        }
+    }
+}
+
+impl WebView {
+    // Answers HTTP authentication challenges (401 and 407) from Rust, e.g.
+    // `|uri, challenge| Some(("user".into(), "pass".into()))`. None shows WebView2's own dialog.
+    //
+    // This WebView2 SDK has no BasicAuthenticationRequested event, so it goes through the CDP
+    // Fetch domain, which pauses every request until it's continued.
+    pub fn on_basic_auth(
+        &self,
+        mut f: impl FnMut(&str, &AuthChallenge) -> Option<(String, String)> + 'static,
+    ) -> Result<Subscription> {
+        self.call_devtools_method(
+            "Fetch.enable",
+            json!({ "patterns": [{ "urlPattern": "*" }], "handleAuthRequests": true }),
+        )?;
+
+        let core = self.core.clone();
+        let paused = self.on_devtools_event("Fetch.requestPaused", move |params| {
+            send_devtools_method(
+                &core,
+                "Fetch.continueRequest",
+                json!({ "requestId": params["requestId"] }),
+            );
+        })?;
+
+        let core = self.core.clone();
+        let auth = self.on_devtools_event("Fetch.authRequired", move |params| {
+            let uri = params["request"]["url"].as_str().unwrap_or_default();
+            let response = match f(uri, &AuthChallenge::from_params(&params)) {
+                Some((username, password)) => json!({
+                    "response": "ProvideCredentials",
+                    "username": username,
+                    "password": password,
+                }),
+                None => json!({ "response": "Default" }),
+            };
+            send_devtools_method(
+                &core,
+                "Fetch.continueWithAuth",
+                json!({ "requestId": params["requestId"], "authChallengeResponse": response }),
+            );
+        })?;
+
+        let core = self.core.clone();
+        let fetch = self.track(move || {
+            send_devtools_method(&core, "Fetch.disable", json!({}));
+            Ok(())
+        });
+        Ok(self.merge(&[paused, auth, fetch]))
+    }
+}
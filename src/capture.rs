@@ -0,0 +1,40 @@
+use std::ptr;
+
+use windows::Win32::{System::Com::STREAM_SEEK_SET, UI::Shell::SHCreateMemStream};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{protocol::read_stream, Error, Result, WebView};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+}
+
+impl WebView {
+    // The visible part of the page, encoded as `format`.
+    pub fn capture(&self, format: ImageFormat) -> Result<Vec<u8>> {
+        let stream = unsafe { SHCreateMemStream(ptr::null(), 0) }
+            .ok_or(Error::WindowsError(windows::core::Error::from_win32()))?;
+        let format = match format {
+            ImageFormat::Png => COREWEBVIEW2_CAPTURE_PREVIEW_IMAGE_FORMAT_PNG,
+            ImageFormat::Jpeg => COREWEBVIEW2_CAPTURE_PREVIEW_IMAGE_FORMAT_JPEG,
+        };
+
+        let core = self.core.clone();
+        let s = stream.clone();
+        CapturePreviewCompletedHandler::wait_for_async_operation(
+            Box::new(move |handler| unsafe {
+                core.CapturePreview(format, s, handler)
+                    .map_err(webview2_com::Error::WindowsError)
+            }),
+            Box::new(|error_code| error_code),
+        )?;
+
+        unsafe {
+            stream.Seek(0, STREAM_SEEK_SET)?;
+            read_stream(&stream)
+        }
+    }
+}
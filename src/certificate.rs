@@ -0,0 +1,330 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use serde_json::json;
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::{BOOL, PWSTR},
+        Security::Cryptography::{
+            CertCreateCertificateContext, CertFreeCertificateContext,
+            CertGetCertificateContextProperty, CryptStringToBinaryW, CERT_SHA1_HASH_PROP_ID,
+            CRYPT_STRING_BASE64HEADER, PKCS_7_ASN_ENCODING, X509_ASN_ENCODING,
+        },
+        System::WinRT::EventRegistrationToken,
+    },
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{event::Subscription, send_devtools_method, Result, WebView};
+
+// One of the certificates in the user's store that the server accepts.
+#[derive(Clone, Debug)]
+pub struct ClientCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub display_name: String,
+    // Uppercase hex SHA-1 of the certificate, as certmgr.msc shows it (without the spaces).
+    pub thumbprint: String,
+    // Seconds since the Unix epoch.
+    pub valid_from: f64,
+    pub valid_to: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct ClientCertificateRequest {
+    pub host: String,
+    pub port: u16,
+    pub is_proxy: bool,
+    // Distinguished names of the CAs the server trusts.
+    pub allowed_authorities: Vec<String>,
+    pub certificates: Vec<ClientCertificate>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CertificateChoice {
+    // Lets WebView2 show its own certificate picker.
+    Default,
+    // An index into `ClientCertificateRequest::certificates`.
+    Select(usize),
+    // Goes on without a certificate and lets the server decide.
+    None,
+    Cancel,
+}
+
+// A server certificate the page's TLS connection rejected, e.g. a self-signed dev server's.
+#[derive(Clone, Debug)]
+pub struct ServerCertificateError {
+    pub uri: String,
+    pub host: String,
+    // Chromium's net error, e.g. `net::ERR_CERT_AUTHORITY_INVALID`.
+    pub error: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CertificateErrorAction {
+    // Blocks the request with WebView2's error page.
+    Default,
+    // Blocks the request too; CDP can't tell the two apart.
+    Cancel,
+    // Goes ahead, and trusts the host for the rest of this WebView's life without asking again.
+    AlwaysAllow,
+}
+
+// `https://example.com:8443/path` -> `example.com`.
+fn host(uri: &str) -> &str {
+    let start = uri.find("://").map_or(0, |i| i + 3);
+    let authority = match uri[start..].find(['/', '?', '#']) {
+        Some(end) => &uri[start..start + end],
+        None => &uri[start..],
+    };
+    let authority = authority.rsplit('@').next().unwrap_or_default();
+    match authority.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    }
+}
+
+// `a1 b2 ...` or `A1B2...`, the way thumbprints get copied out of certmgr.msc.
+fn normalize_thumbprint(thumbprint: &str) -> String {
+    thumbprint
+        .chars()
+        .filter(char::is_ascii_hexdigit)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+// WebView2 only gives out the PEM encoding, so hash it the way the certificate store does.
+fn thumbprint(pem: &str) -> Option<String> {
+    unsafe {
+        let mut len = 0;
+        let decode = |der: *mut u8, len: &mut u32| {
+            CryptStringToBinaryW(
+                pem,
+                0,
+                CRYPT_STRING_BASE64HEADER,
+                der,
+                len,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+            .as_bool()
+        };
+        if !decode(std::ptr::null_mut(), &mut len) {
+            return None;
+        }
+        let mut der = vec![0u8; len as usize];
+        if !decode(der.as_mut_ptr(), &mut len) {
+            return None;
+        }
+
+        let context = CertCreateCertificateContext(
+            X509_ASN_ENCODING | PKCS_7_ASN_ENCODING,
+            der.as_ptr(),
+            len,
+        );
+        if context.is_null() {
+            return None;
+        }
+        let mut hash = [0u8; 20];
+        let mut size = hash.len() as u32;
+        let hashed = CertGetCertificateContextProperty(
+            context,
+            CERT_SHA1_HASH_PROP_ID,
+            hash.as_mut_ptr() as *mut _,
+            &mut size,
+        )
+        .as_bool();
+        CertFreeCertificateContext(context);
+        hashed.then(|| {
+            hash[..size as usize]
+                .iter()
+                .map(|b| format!("{:02X}", b))
+                .collect()
+        })
+    }
+}
+
+unsafe fn read_certificate(
+    certificate: &ICoreWebView2ClientCertificate,
+) -> Result<ClientCertificate> {
+    let (mut subject, mut issuer, mut display_name, mut pem) = (
+        PWSTR::default(),
+        PWSTR::default(),
+        PWSTR::default(),
+        PWSTR::default(),
+    );
+    let (mut valid_from, mut valid_to) = (0., 0.);
+    certificate.Subject(&mut subject)?;
+    certificate.Issuer(&mut issuer)?;
+    certificate.DisplayName(&mut display_name)?;
+    certificate.ToPemEncoding(&mut pem)?;
+    certificate.ValidFrom(&mut valid_from)?;
+    certificate.ValidTo(&mut valid_to)?;
+    Ok(ClientCertificate {
+        subject: take_pwstr(subject),
+        issuer: take_pwstr(issuer),
+        display_name: take_pwstr(display_name),
+        thumbprint: thumbprint(&take_pwstr(pem)).unwrap_or_default(),
+        valid_from,
+        valid_to,
+    })
+}
+
+unsafe fn read_request(
+    args: &ICoreWebView2ClientCertificateRequestedEventArgs,
+) -> Result<(
+    ClientCertificateRequest,
+    Vec<ICoreWebView2ClientCertificate>,
+)> {
+    let mut host = PWSTR::default();
+    let mut port = 0;
+    let mut is_proxy = BOOL::default();
+    args.Host(&mut host)?;
+    args.Port(&mut port)?;
+    args.IsProxy(&mut is_proxy)?;
+
+    let authorities = args.AllowedCertificateAuthorities()?;
+    let mut count = 0;
+    authorities.Count(&mut count)?;
+    let mut allowed_authorities = Vec::new();
+    for i in 0..count {
+        let mut authority = PWSTR::default();
+        authorities.GetValueAtIndex(i, &mut authority)?;
+        allowed_authorities.push(take_pwstr(authority));
+    }
+
+    let collection = args.MutuallyTrustedCertificates()?;
+    collection.Count(&mut count)?;
+    let mut certificates = Vec::new();
+    let mut raw = Vec::new();
+    for i in 0..count {
+        let certificate = collection.GetValueAtIndex(i)?;
+        certificates.push(read_certificate(&certificate)?);
+        raw.push(certificate);
+    }
+
+    let request = ClientCertificateRequest {
+        host: take_pwstr(host),
+        port: port as u16,
+        is_proxy: is_proxy.as_bool(),
+        allowed_authorities,
+        certificates,
+    };
+    Ok((request, raw))
+}
+
+impl WebView {
+    // Called when a server (or proxy) asks for a TLS client certificate. The request lists the
+    // certificates from the user's store that the server would accept.
+    pub fn on_client_certificate(
+        &self,
+        mut f: impl FnMut(&ClientCertificateRequest) -> CertificateChoice + 'static,
+    ) -> Result<Subscription> {
+        let core = self.core.cast::<ICoreWebView2_5>()?;
+        let handler =
+            move |_core, args: Option<ICoreWebView2ClientCertificateRequestedEventArgs>| {
+                let args = match args {
+                    Some(args) => args,
+                    None => return Ok(()),
+                };
+                let (request, raw) = match unsafe { read_request(&args) } {
+                    Ok(request) => request,
+                    Err(_) => return Ok(()),
+                };
+                unsafe {
+                    match f(&request) {
+                        CertificateChoice::Default => {}
+                        CertificateChoice::Select(index) => {
+                            if let Some(certificate) = raw.get(index) {
+                                args.SetSelectedCertificate(certificate)?;
+                                args.SetHandled(true)?;
+                            }
+                        }
+                        CertificateChoice::None => args.SetHandled(true)?,
+                        CertificateChoice::Cancel => args.SetCancel(true)?,
+                    }
+                }
+                Ok(())
+            };
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            core.ClientCertificateRequested(
+                ClientCertificateRequestedEventHandler::create(Box::new(handler)),
+                &mut token,
+            )?;
+        }
+        Ok(self.track(move || unsafe { core.RemoveClientCertificateRequested(token) }))
+    }
+
+    // Answers every request with the certificate whose thumbprint matches, and falls back to
+    // WebView2's picker when the server doesn't accept it.
+    pub fn select_client_certificate(&self, thumbprint: &str) -> Result<Subscription> {
+        let thumbprint = normalize_thumbprint(thumbprint);
+        self.on_client_certificate(move |request| {
+            match request
+                .certificates
+                .iter()
+                .position(|certificate| certificate.thumbprint == thumbprint)
+            {
+                Some(index) => CertificateChoice::Select(index),
+                None => CertificateChoice::Default,
+            }
+        })
+    }
+
+    // Decides what happens when a server certificate doesn't validate, e.g. to let internal tools
+    // talk to a self-signed dev server: `|error| if error.host == "localhost" { AlwaysAllow }
+    // else { Default }`.
+    //
+    // This WebView2 SDK has no ServerCertificateErrorDetected event, so it goes through CDP's
+    // (deprecated but still supported) certificate error override.
+    pub fn on_server_certificate_error(
+        &self,
+        mut f: impl FnMut(&ServerCertificateError) -> CertificateErrorAction + 'static,
+    ) -> Result<Subscription> {
+        self.call_devtools_method("Security.enable", json!({}))?;
+        self.call_devtools_method(
+            "Security.setOverrideCertificateErrors",
+            json!({ "override": true }),
+        )?;
+
+        let allowed = Rc::new(RefCell::new(HashSet::new()));
+        let core = self.core.clone();
+        let errors = self.on_devtools_event("Security.certificateError", move |params| {
+            let uri = params["requestURL"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            let error = ServerCertificateError {
+                host: host(&uri).to_ascii_lowercase(),
+                error: params["errorType"].as_str().unwrap_or_default().to_string(),
+                uri,
+            };
+            let allow = allowed.borrow().contains(&error.host)
+                || match f(&error) {
+                    CertificateErrorAction::AlwaysAllow => {
+                        allowed.borrow_mut().insert(error.host.clone());
+                        true
+                    }
+                    CertificateErrorAction::Default | CertificateErrorAction::Cancel => false,
+                };
+            send_devtools_method(
+                &core,
+                "Security.handleCertificateError",
+                json!({
+                    "eventId": params["eventId"],
+                    "action": if allow { "continue" } else { "cancel" },
+                }),
+            );
+        })?;
+
+        let core = self.core.clone();
+        let overriding = self.track(move || {
+            let params = json!({ "override": false });
+            send_devtools_method(&core, "Security.setOverrideCertificateErrors", params);
+            Ok(())
+        });
+        Ok(self.merge(&[errors, overriding]))
+    }
+}
@@ -0,0 +1,188 @@
+use std::time::{Duration, Instant};
+
+use windows::Win32::{
+    Foundation::{HWND, RECT},
+    Graphics::Gdi::*,
+    UI::WindowsAndMessaging::*,
+};
+
+use crate::{dpi_ratio, Error, GetWindowLong, Result, SetWindowLong, WebView};
+
+const RETRY_INTERVAL: Duration = Duration::from_millis(20);
+
+// The windows Chromium creates inside the host window. They appear some time after the
+// controller does (the D3D one only with GPU compositing), so look them up when needed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChildWindows {
+    pub host: HWND,
+    pub widget0: HWND,
+    pub widget1: HWND,
+    pub widget_host: HWND,
+    // Zero if it doesn't exist (yet).
+    pub d3d: HWND,
+}
+
+fn find_child(parent: HWND, class: &str) -> HWND {
+    if parent.0 == 0 {
+        return HWND(0);
+    }
+    unsafe { FindWindowExA(parent, None, class, None) }
+}
+
+impl ChildWindows {
+    // A single lookup; any of the windows may still be missing.
+    pub fn find(host: HWND) -> Self {
+        let widget0 = find_child(host, "Chrome_WidgetWin_0");
+        let widget1 = find_child(widget0, "Chrome_WidgetWin_1");
+        Self {
+            host,
+            widget0,
+            widget1,
+            widget_host: find_child(widget1, "Chrome_RenderWidgetHostHWND"),
+            d3d: find_child(widget1, "Intermediate D3D Window"),
+        }
+    }
+
+    // Retries until the render widget exists, pumping messages meanwhile. The D3D window is
+    // waited for too, but only until `timeout`, as it may never be created.
+    pub fn wait(host: HWND, timeout: Duration) -> Result<Self> {
+        let start = Instant::now();
+        loop {
+            let children = Self::find(host);
+            if children.d3d.0 != 0 {
+                return Ok(children);
+            }
+            if start.elapsed() >= timeout {
+                return match children.widget_host.0 {
+                    0 => Err(Error::ChildWindowNotFound),
+                    _ => Ok(children),
+                };
+            }
+            crate::pump_for(RETRY_INTERVAL)?;
+        }
+    }
+
+    fn content(&self) -> Result<HWND> {
+        match self.widget0.0 {
+            0 => Err(Error::ChildWindowNotFound),
+            _ => Ok(self.widget0),
+        }
+    }
+
+    // Clips the web content to the union of `rects` (client coordinates). Empty clears the clip.
+    pub fn set_region(&self, rects: &[RECT]) -> Result<()> {
+        let hwnd = self.content()?;
+        unsafe {
+            let region = match rects.split_first() {
+                None => HRGN(0),
+                Some((first, rest)) => {
+                    let region = CreateRectRgn(first.left, first.top, first.right, first.bottom);
+                    for rect in rest {
+                        let other = CreateRectRgn(rect.left, rect.top, rect.right, rect.bottom);
+                        CombineRgn(region, region, other, RGN_OR);
+                        DeleteObject(other);
+                    }
+                    region
+                }
+            };
+            // The system owns the region from here on.
+            SetWindowRgn(hwnd, region, true);
+        }
+        Ok(())
+    }
+
+    // Makes the web content layered, with `color_key` (0x00BBGGRR) transparent and/or `alpha`.
+    pub fn set_layered(&self, color_key: Option<u32>, alpha: u8) -> Result<()> {
+        let hwnd = self.content()?;
+        unsafe {
+            let exstyle = GetWindowLong(hwnd, GWL_EXSTYLE);
+            SetWindowLong(hwnd, GWL_EXSTYLE, exstyle | WS_EX_LAYERED as isize);
+            let flags = match color_key {
+                Some(_) => LWA_COLORKEY | LWA_ALPHA,
+                None => LWA_ALPHA,
+            };
+            SetLayeredWindowAttributes(hwnd, color_key.unwrap_or_default(), alpha, flags).ok()?;
+        }
+        Ok(())
+    }
+
+    // Hides the window from screenshots and screen sharing. Only top-level windows support
+    // this, so it applies to the host window as a whole.
+    pub fn exclude_from_capture(&self, exclude: bool) -> Result<()> {
+        let affinity = if exclude {
+            WDA_EXCLUDEFROMCAPTURE
+        } else {
+            WDA_NONE
+        };
+        unsafe { SetWindowDisplayAffinity(self.host, affinity).ok()? };
+        Ok(())
+    }
+}
+
+pub enum WindowRegion {
+    // Corner radius in DIPs, for the current window size.
+    RoundedRect(f64),
+    // In window coordinates, i.e. relative to the top left of the frame. Owned by the window
+    // from here on.
+    Custom(HRGN),
+    // Back to the plain window rectangle.
+    None,
+}
+
+impl WebView {
+    // Shapes the host window and clips Chromium's child windows to the same region, e.g. for
+    // rounded frameless popups where DWM corner preferences aren't available (Windows 10).
+    // Call it again after resizing. WebViews built with `visual_hosting` have no child windows
+    // to clip; `set_corner_radius` rounds them.
+    pub fn set_window_region(&self, region: WindowRegion) -> Result<()> {
+        let host = self.hwnd;
+        unsafe {
+            let mut window = RECT::default();
+            GetWindowRect(host, &mut window).ok()?;
+            let region = match region {
+                WindowRegion::RoundedRect(radius) => {
+                    let diameter = (radius * 2. * dpi_ratio(host)) as i32;
+                    let (width, height) = (window.right - window.left, window.bottom - window.top);
+                    CreateRoundRectRgn(0, 0, width + 1, height + 1, diameter, diameter)
+                }
+                WindowRegion::Custom(region) => region,
+                WindowRegion::None => HRGN(0),
+            };
+
+            let children = self.child_windows();
+            for child in [
+                children.widget0,
+                children.widget1,
+                children.widget_host,
+                children.d3d,
+            ] {
+                if child.0 == 0 {
+                    continue;
+                }
+                let child_region = match region.0 {
+                    0 => HRGN(0),
+                    _ => {
+                        // Each window needs its own copy, moved into its own coordinates.
+                        let mut rect = RECT::default();
+                        GetWindowRect(child, &mut rect);
+                        let copy = CreateRectRgn(0, 0, 0, 0);
+                        CombineRgn(copy, region, HRGN(0), RGN_COPY);
+                        OffsetRgn(copy, window.left - rect.left, window.top - rect.top);
+                        copy
+                    }
+                };
+                SetWindowRgn(child, child_region, true);
+            }
+            SetWindowRgn(host, region, true);
+        }
+        Ok(())
+    }
+
+    pub fn child_windows(&self) -> ChildWindows {
+        ChildWindows::find(self.hwnd)
+    }
+
+    pub fn wait_for_child_windows(&self, timeout: Duration) -> Result<ChildWindows> {
+        ChildWindows::wait(self.hwnd, timeout)
+    }
+}
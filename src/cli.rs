@@ -0,0 +1,64 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    OnceLock,
+};
+
+// The builder values a shipped binary's command line can override, e.g. for QA pointing it at
+// a staging frontend: `--taco-url=https://staging.example.com`, `--taco-devtools` and
+// `--taco-user-data-dir=C:\tmp\profile`. The prefix is `WebViewBuilder::cli_prefix`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CliOverrides {
+    // Only for the first window built; see `WebViewBuilder::cli_prefix`.
+    pub url: Option<String>,
+    // Enables the DevTools, even in builds with `debug` off, and opens them in the first window.
+    pub devtools: bool,
+    pub user_data_dir: Option<String>,
+}
+
+// Whether the first window has been built, and the url it took, kept for the whole process so
+// that builders of any lifetime can borrow it.
+static CLAIMED: AtomicBool = AtomicBool::new(false);
+static URL: OnceLock<Option<String>> = OnceLock::new();
+
+impl CliOverrides {
+    // From this process's command line. Later switches win; unknown ones are left alone.
+    // Arguments that aren't valid Unicode are read lossily.
+    pub fn from_args(prefix: &str) -> Self {
+        let args = std::env::args_os()
+            .skip(1)
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        Self::parse(prefix, &args)
+    }
+
+    pub fn parse(prefix: &str, args: &[impl AsRef<str>]) -> Self {
+        let mut overrides = Self::default();
+        for arg in args {
+            let rest = match arg
+                .as_ref()
+                .strip_prefix("--")
+                .and_then(|arg| arg.strip_prefix(prefix))
+                .and_then(|arg| arg.strip_prefix('-'))
+            {
+                Some(rest) => rest,
+                None => continue,
+            };
+            match rest.split_once('=') {
+                Some(("url", url)) => overrides.url = Some(url.to_string()),
+                Some(("user-data-dir", dir)) => overrides.user_data_dir = Some(dir.to_string()),
+                None if rest == "devtools" => overrides.devtools = true,
+                _ => {}
+            }
+        }
+        overrides
+    }
+
+    // Some, with the url to open instead of the builder's, for the first window of the process
+    // only.
+    pub(crate) fn claim_first_window(&self) -> Option<Option<&'static str>> {
+        if CLAIMED.swap(true, Ordering::Relaxed) {
+            return None;
+        }
+        Some(URL.get_or_init(|| self.url.clone()).as_deref())
+    }
+}
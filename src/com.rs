@@ -0,0 +1,60 @@
+use std::{marker::PhantomData, ptr};
+
+use windows::Win32::{
+    Foundation::RPC_E_CHANGED_MODE,
+    System::{Com::*, Threading::GetCurrentThreadId},
+};
+
+use crate::{Error, Result};
+
+// Keeps the calling thread in a single-threaded apartment while alive.
+// Every successful CoInitializeEx (including on an already initialized STA) is balanced
+// by one CoUninitialize on drop, so nesting inside host code that owns the apartment is fine.
+pub struct Apartment {
+    thread: u32,
+    // COM apartments belong to a thread.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Apartment {
+    // Panics in debug builds unless called on the thread that joined the apartment.
+    #[track_caller]
+    pub(crate) fn assert_thread(&self, what: &str) {
+        assert_thread(self.thread, what);
+    }
+}
+
+// Calling into COM objects from another thread deadlocks or corrupts them rather than failing,
+// so catch it early in debug builds.
+#[track_caller]
+pub(crate) fn assert_thread(owner: u32, what: &str) {
+    if cfg!(debug_assertions) {
+        let current = unsafe { GetCurrentThreadId() };
+        assert!(
+            current == owner,
+            "{} called on thread {}, but it belongs to thread {}; \
+             send a WebViewHandle or WindowHandle across threads instead",
+            what,
+            current,
+            owner
+        );
+    }
+}
+
+impl Drop for Apartment {
+    fn drop(&mut self) {
+        unsafe { CoUninitialize() };
+    }
+}
+
+// WebView2 needs an STA. Fails with `Error::WrongApartment` if the thread already joined the MTA.
+pub fn ensure_sta() -> Result<Apartment> {
+    match unsafe { CoInitializeEx(ptr::null_mut(), COINIT_APARTMENTTHREADED) } {
+        Ok(()) => Ok(Apartment {
+            thread: unsafe { GetCurrentThreadId() },
+            _not_send: PhantomData,
+        }),
+        Err(err) if err.code() == RPC_E_CHANGED_MODE => Err(Error::WrongApartment),
+        Err(err) => Err(err.into()),
+    }
+}
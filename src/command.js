@@ -0,0 +1,7 @@
+(function () {
+    const taco = window.taco = window.taco || {};
+
+    // Runs the app command `id` like a menu item would. Resolves once its handler has run and
+    // rejects if the handler can't read the payload.
+    taco.invokeCommand = (id, payload) => window._taco_invokeCommand(id, payload === undefined ? null : payload);
+})();
@@ -0,0 +1,73 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+// Where a command came from. `Script` carries the label of the window that called
+// `taco.invokeCommand`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommandSource {
+    Menu,
+    Tray,
+    Hotkey,
+    Script(String),
+}
+
+// A command id plus the payload its menu item, tray entry or hotkey declares (null if none),
+// or the one passed by `taco.invokeCommand(id, payload)`.
+#[derive(Clone, Debug)]
+pub struct Command {
+    pub id: String,
+    pub payload: Value,
+    pub source: CommandSource,
+}
+
+type Handler = Rc<RefCell<dyn FnMut(&Command) -> std::result::Result<(), String>>>;
+type Fallback = Rc<RefCell<Box<dyn FnMut(&Command)>>>;
+
+// Handlers by command id. Commands without one go to `fallback`, i.e. `App::on_command`.
+#[derive(Clone)]
+pub(crate) struct Commands {
+    handlers: Rc<RefCell<HashMap<String, Handler>>>,
+    fallback: Fallback,
+}
+
+impl Commands {
+    pub fn new() -> Self {
+        Self {
+            handlers: Rc::new(RefCell::new(HashMap::new())),
+            fallback: Rc::new(RefCell::new(Box::new(|_| {}))),
+        }
+    }
+
+    pub fn register<P, F>(&self, id: &str, mut f: F)
+    where
+        P: DeserializeOwned,
+        F: FnMut(P, &CommandSource) + 'static,
+    {
+        let handler: Handler = Rc::new(RefCell::new(move |command: &Command| {
+            let payload = serde_json::from_value(command.payload.clone())
+                .map_err(|err| format!("Bad payload for command {}: {}", command.id, err))?;
+            f(payload, &command.source);
+            Ok(())
+        }));
+        self.handlers.borrow_mut().insert(id.to_string(), handler);
+    }
+
+    pub fn set_fallback(&self, f: impl FnMut(&Command) + 'static) {
+        *self.fallback.borrow_mut() = Box::new(f);
+    }
+
+    // Errors only for payloads the handler can't deserialize.
+    pub fn dispatch(&self, command: &Command) -> std::result::Result<(), String> {
+        // Cloned out, so that handlers can register more commands.
+        let handler = self.handlers.borrow().get(&command.id).cloned();
+        match handler {
+            Some(handler) => (handler.borrow_mut())(command),
+            None => {
+                (self.fallback.borrow_mut())(command);
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::{cell::Cell, ptr, rc::Rc, sync::mpsc};
+
+use windows::{
+    core::{Abi, IUnknown, Interface},
+    Win32::{
+        Foundation::{E_POINTER, HWND, LPARAM, POINT, RECT, WPARAM},
+        Graphics::{DirectComposition::*, Gdi::ScreenToClient},
+        System::WinRT::EventRegistrationToken,
+        UI::{
+            Input::KeyboardAndMouse::{
+                ReleaseCapture, SetCapture, TrackMouseEvent, TME_LEAVE, TRACKMOUSEEVENT,
+            },
+            WindowsAndMessaging::*,
+        },
+    },
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{dpi_ratio, window::WindowRunner, Error, Result, WebView};
+
+const WM_MOUSELEAVE: u32 = 0x02A3;
+
+const MOUSE_MESSAGES: [u32; 15] = [
+    WM_MOUSEMOVE,
+    WM_LBUTTONDOWN,
+    WM_LBUTTONUP,
+    WM_LBUTTONDBLCLK,
+    WM_RBUTTONDOWN,
+    WM_RBUTTONUP,
+    WM_RBUTTONDBLCLK,
+    WM_MBUTTONDOWN,
+    WM_MBUTTONUP,
+    WM_MBUTTONDBLCLK,
+    WM_XBUTTONDOWN,
+    WM_XBUTTONUP,
+    WM_XBUTTONDBLCLK,
+    WM_MOUSEWHEEL,
+    WM_MOUSEHWHEEL,
+];
+
+// The DirectComposition tree of a WebView built with `visual_hosting`. WebView2 renders into
+// `webview`, a child of the window's `root`; visuals of the app's own, e.g. a DirectX swap chain
+// under a transparent page, go into `root` too. Call `device.Commit()` after changing them.
+pub struct Composition {
+    pub controller: ICoreWebView2CompositionController,
+    pub device: IDCompositionDevice,
+    pub root: IDCompositionVisual,
+    pub webview: IDCompositionVisual,
+    clip: IDCompositionRectangleClip,
+    _target: IDCompositionTarget,
+    // In client coordinates; WebView2 itself is always told it's at the origin of `webview`.
+    bounds: Cell<RECT>,
+    corner_radius: Cell<f32>,
+}
+
+impl Composition {
+    pub fn bounds(&self) -> RECT {
+        self.bounds.get()
+    }
+
+    pub fn corner_radius(&self) -> f32 {
+        self.corner_radius.get()
+    }
+
+    pub(crate) fn set_bounds(
+        &self,
+        controller: &ICoreWebView2Controller,
+        bounds: RECT,
+    ) -> Result<()> {
+        let (width, height) = (bounds.right - bounds.left, bounds.bottom - bounds.top);
+        unsafe {
+            controller.SetBounds(RECT {
+                left: 0,
+                top: 0,
+                right: width,
+                bottom: height,
+            })?;
+            self.webview.SetOffsetX2(bounds.left as f32)?;
+            self.webview.SetOffsetY2(bounds.top as f32)?;
+            self.clip.SetRight2(width as f32)?;
+            self.clip.SetBottom2(height as f32)?;
+        }
+        self.bounds.set(bounds);
+        self.commit()
+    }
+
+    pub(crate) fn set_corner_radius(&self, radius: f32) -> Result<()> {
+        unsafe {
+            self.clip.SetTopLeftRadiusX2(radius)?;
+            self.clip.SetTopLeftRadiusY2(radius)?;
+            self.clip.SetTopRightRadiusX2(radius)?;
+            self.clip.SetTopRightRadiusY2(radius)?;
+            self.clip.SetBottomLeftRadiusX2(radius)?;
+            self.clip.SetBottomLeftRadiusY2(radius)?;
+            self.clip.SetBottomRightRadiusX2(radius)?;
+            self.clip.SetBottomRightRadiusY2(radius)?;
+        }
+        self.corner_radius.set(radius);
+        self.commit()
+    }
+
+    fn commit(&self) -> Result<()> {
+        unsafe { self.device.Commit()? };
+        Ok(())
+    }
+
+    // The cursor the page wants, for WM_SETCURSOR in the client area.
+    pub(crate) fn cursor(&self) -> Option<HCURSOR> {
+        let mut cursor = HCURSOR::default();
+        unsafe { self.controller.Cursor(&mut cursor) }.ok()?;
+        (cursor.0 != 0).then_some(cursor)
+    }
+}
+
+// Creates the WebView with a composition controller instead of a windowed one, and the visual
+// tree for it. The window needs no redirection bitmap for per-pixel transparency, see
+// `WebViewBuilder::visual_hosting`.
+pub(crate) fn create_controller(
+    environment: &ICoreWebView2Environment,
+    hwnd: HWND,
+) -> Result<(ICoreWebView2Controller, Composition)> {
+    let environment: ICoreWebView2Environment3 = environment.cast()?;
+    let (tx, rx) = mpsc::channel();
+    CreateCoreWebView2CompositionControllerCompletedHandler::wait_for_async_operation(
+        Box::new(move |handler| unsafe {
+            environment
+                .CreateCoreWebView2CompositionController(hwnd, handler)
+                .map_err(webview2_com::Error::WindowsError)
+        }),
+        Box::new(
+            move |error_code, controller: Option<ICoreWebView2CompositionController>| {
+                error_code?;
+                tx.send(controller.ok_or_else(|| windows::core::Error::fast_error(E_POINTER)))
+                    .expect("send over mpsc channel");
+                Ok(())
+            },
+        ),
+    )?;
+    let composition_controller = rx
+        .recv()
+        .map_err(|_| Error::WebView2Error(webview2_com::Error::SendError))??;
+
+    let composition = unsafe {
+        // No DXGI device: WebView2 brings its own, and so can the app's visuals.
+        let mut device = ptr::null_mut();
+        DCompositionCreateDevice(None, &IDCompositionDevice::IID, &mut device)?;
+        let device = IDCompositionDevice::from_abi(device)?;
+        let target = device.CreateTargetForHwnd(hwnd, true)?;
+        let root = device.CreateVisual()?;
+        let webview = device.CreateVisual()?;
+        let clip = device.CreateRectangleClip()?;
+        webview.SetClip(&clip)?;
+        root.AddVisual(&webview, true, None)?;
+        target.SetRoot(&root)?;
+        composition_controller.SetRootVisualTarget(&webview)?;
+        device.Commit()?;
+        Composition {
+            controller: composition_controller.clone(),
+            device,
+            root,
+            webview,
+            clip,
+            _target: target,
+            bounds: Cell::new(RECT::default()),
+            corner_radius: Cell::new(0.),
+        }
+    };
+    Ok((composition_controller.cast()?, composition))
+}
+
+fn point_of(lparam: LPARAM) -> POINT {
+    POINT {
+        x: (lparam.0 & 0xFFFF) as i16 as i32,
+        y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+    }
+}
+
+fn hiword(wparam: WPARAM) -> u16 {
+    ((wparam.0 >> 16) & 0xFFFF) as u16
+}
+
+// A visually hosted WebView has no window of its own to receive the mouse, so the host window
+// forwards it. Touch and pen arrive as mouse messages too, without their pointer details.
+pub(crate) fn forward_input<T: 'static>(
+    webview: &WebView,
+    composition: &Rc<Composition>,
+    wrun: &mut WindowRunner<T>,
+) -> Result<()> {
+    let hwnd = webview.hwnd;
+    // Whether WM_MOUSELEAVE has been asked for since the mouse entered.
+    let tracking = Rc::new(Cell::new(false));
+
+    for msg in MOUSE_MESSAGES {
+        let c = composition.clone();
+        let tracking = tracking.clone();
+        wrun.add_event_listener(msg, move |wparam, lparam, _| unsafe {
+            let mut point = point_of(lparam);
+            // The wheels come in screen coordinates.
+            if msg == WM_MOUSEWHEEL || msg == WM_MOUSEHWHEEL {
+                ScreenToClient(hwnd, &mut point);
+            }
+            let bounds = c.bounds();
+            point.x -= bounds.left;
+            point.y -= bounds.top;
+
+            let mouse_data = match msg {
+                // Signed deltas.
+                WM_MOUSEWHEEL | WM_MOUSEHWHEEL => hiword(wparam) as i16 as i32 as u32,
+                WM_XBUTTONDOWN | WM_XBUTTONUP | WM_XBUTTONDBLCLK => hiword(wparam) as u32,
+                _ => 0,
+            };
+            match msg {
+                WM_MOUSEMOVE if !tracking.replace(true) => {
+                    let mut event = TRACKMOUSEEVENT {
+                        cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
+                        dwFlags: TME_LEAVE,
+                        hwndTrack: hwnd,
+                        dwHoverTime: 0,
+                    };
+                    TrackMouseEvent(&mut event);
+                }
+                // Keeps drags going outside the window.
+                WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN | WM_XBUTTONDOWN => {
+                    SetCapture(hwnd);
+                }
+                // Once no button is held: MK_LBUTTON | MK_RBUTTON | MK_MBUTTON | MK_XBUTTON1 |
+                // MK_XBUTTON2.
+                WM_LBUTTONUP | WM_RBUTTONUP | WM_MBUTTONUP | WM_XBUTTONUP
+                    if wparam.0 & 0x73 == 0 =>
+                {
+                    ReleaseCapture();
+                }
+                _ => {}
+            }
+            // The message ids and MK_* flags are what WebView2's kinds and virtual keys are.
+            let _ = c.controller.SendMouseInput(
+                msg as COREWEBVIEW2_MOUSE_EVENT_KIND,
+                (wparam.0 & 0xFFFF) as COREWEBVIEW2_MOUSE_EVENT_VIRTUAL_KEYS,
+                mouse_data,
+                point,
+            );
+        });
+    }
+
+    let c = composition.clone();
+    wrun.add_event_listener(WM_MOUSELEAVE, move |_, _, _| unsafe {
+        tracking.set(false);
+        let _ = c.controller.SendMouseInput(
+            COREWEBVIEW2_MOUSE_EVENT_KIND_LEAVE,
+            COREWEBVIEW2_MOUSE_EVENT_VIRTUAL_KEYS_NONE,
+            0,
+            POINT::default(),
+        );
+    });
+
+    // WM_SETCURSOR only comes with the next mouse move, so apply the page's new cursor right
+    // away while the mouse is over the window.
+    let mut token = EventRegistrationToken::default();
+    let c = composition.clone();
+    unsafe {
+        composition.controller.CursorChanged(
+            CursorChangedEventHandler::create(Box::new(move |_, _: Option<IUnknown>| {
+                let mut point = POINT::default();
+                if GetCursorPos(&mut point).as_bool() && WindowFromPoint(point) == hwnd {
+                    if let Some(cursor) = c.cursor() {
+                        SetCursor(cursor);
+                    }
+                }
+                Ok(())
+            })),
+            &mut token,
+        )?;
+    }
+    let controller = composition.controller.clone();
+    webview.track(move || unsafe { controller.RemoveCursorChanged(token) });
+    Ok(())
+}
+
+impl WebView {
+    // Rounds the WebView's corners by `radius` DIPs, with the rest of the window showing
+    // through; per pixel, unlike `set_window_region`. Follows resizes.
+    pub fn set_corner_radius(&self, radius: f64) -> Result<&Self> {
+        let composition = self.composition().ok_or(Error::NotVisuallyHosted)?;
+        composition.set_corner_radius((radius * dpi_ratio(self.hwnd)) as f32)?;
+        Ok(self)
+    }
+}
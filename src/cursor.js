@@ -0,0 +1,20 @@
+(function () {
+    // Elements like `<div data-taco-cursor="ew-resize">` tell the host which cursor to show
+    // where it does the hit-testing, e.g. on the edges and caption of a frameless window.
+    let current = null;
+    const update = e => {
+        const element = e.target instanceof Element && e.target.closest('[data-taco-cursor]');
+        const cursor = element ? element.dataset.tacoCursor : null;
+        if (cursor !== current) {
+            current = cursor;
+            window._taco_setCursorHint(cursor);
+        }
+    };
+    document.addEventListener('mousemove', update, true);
+    document.addEventListener('mouseleave', () => {
+        if (current !== null) {
+            current = null;
+            window._taco_setCursorHint(null);
+        }
+    });
+})();
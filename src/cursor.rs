@@ -0,0 +1,88 @@
+use std::{cell::Cell, rc::Rc};
+
+use serde_json::Value;
+use windows::Win32::{
+    Foundation::{LRESULT, PWSTR},
+    UI::WindowsAndMessaging::*,
+};
+
+use crate::{
+    window::{self, WindowRunner},
+    Result, WebView,
+};
+
+// CSS cursor names -> system cursors.
+fn system_cursor(name: &str) -> Option<PWSTR> {
+    Some(match name {
+        "default" | "auto" => IDC_ARROW,
+        "pointer" => IDC_HAND,
+        "move" | "all-scroll" => IDC_SIZEALL,
+        "text" => IDC_IBEAM,
+        "wait" => IDC_WAIT,
+        "progress" => IDC_APPSTARTING,
+        "not-allowed" | "no-drop" => IDC_NO,
+        "help" => IDC_HELP,
+        "crosshair" => IDC_CROSS,
+        "ew-resize" | "e-resize" | "w-resize" | "col-resize" => IDC_SIZEWE,
+        "ns-resize" | "n-resize" | "s-resize" | "row-resize" => IDC_SIZENS,
+        "nwse-resize" | "nw-resize" | "se-resize" => IDC_SIZENWSE,
+        "nesw-resize" | "ne-resize" | "sw-resize" => IDC_SIZENESW,
+        _ => return None,
+    })
+}
+
+// Shows the cursor declared with `data-taco-cursor` while the mouse is over that element, if
+// `hints` is set, and the page's cursor of a visually hosted WebView.
+pub(crate) fn inject<T: 'static>(
+    webview: &WebView,
+    wrun: &mut WindowRunner<T>,
+    hints: bool,
+) -> Result<()> {
+    let hint = Rc::new(Cell::new(None));
+
+    if hints {
+        let h = hint.clone();
+        webview.bind_unsafe("_taco_setCursorHint", move |params| {
+            match &params[..] {
+                [Value::String(name)] => {
+                    // Rather than keeping the previous element's cursor.
+                    let cursor = system_cursor(name);
+                    h.set(cursor);
+                    let cursor = cursor.ok_or_else(|| format!("Unknown cursor: {}", name))?;
+                    unsafe { SetCursor(LoadCursorW(None, cursor)) };
+                }
+                [Value::Null] => h.set(None),
+                _ => return Err("Usage: _taco_setCursorHint(cursor)".into()),
+            }
+            Ok(Value::Null)
+        });
+        webview.init(include_str!("cursor.js"))?;
+    } else if webview.composition().is_none() {
+        return Ok(());
+    }
+
+    let hwnd = wrun.hwnd();
+    let w = webview.clone();
+    wrun.add_event_listener(WM_SETCURSOR, move |wparam, lparam, _| unsafe {
+        // A visually hosted WebView only tells which cursor the page wants.
+        let page_cursor = match (lparam.0 & 0xFFFF) as u32 {
+            HTCLIENT => w.composition().and_then(|c| c.cursor()),
+            _ => None,
+        };
+        let result = match (hint.get(), page_cursor) {
+            (Some(cursor), _) => {
+                SetCursor(LoadCursorW(None, cursor));
+                LRESULT(1)
+            }
+            (None, Some(cursor)) => {
+                SetCursor(cursor);
+                LRESULT(1)
+            }
+            // Keeps the resize cursors on the window frame, and lets the child windows of
+            // iframes and the like set their own.
+            (None, None) => DefWindowProcA(hwnd, WM_SETCURSOR, wparam, lparam),
+        };
+        window::set_message_result(result);
+    });
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use windows::Win32::{
+    Foundation::{BOOL, HWND, LPARAM, PWSTR, WPARAM},
+    UI::WindowsAndMessaging::*,
+};
+
+use crate::{Result, WebView};
+
+// The DevTools window this WebView opened. The browser process, and so its DevTools windows,
+// are shared by every WebView of the environment.
+#[derive(Default)]
+pub(crate) struct Tracked {
+    // The process's DevTools windows from before `open_devtools`, until a new one shows up.
+    before: Option<Vec<HWND>>,
+    window: Option<HWND>,
+}
+
+struct Search {
+    pid: u32,
+    found: Vec<HWND>,
+}
+
+unsafe extern "system" fn collect_devtools(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let search = &mut *(lparam.0 as *mut Search);
+    let mut pid = 0;
+    GetWindowThreadProcessId(hwnd, &mut pid);
+    if pid == search.pid && IsWindowVisible(hwnd).as_bool() {
+        let mut title = [0u16; 64];
+        let len = GetWindowTextW(hwnd, PWSTR(title.as_mut_ptr()), title.len() as _);
+        if String::from_utf16_lossy(&title[..len.max(0) as usize]).starts_with("DevTools") {
+            search.found.push(hwnd);
+        }
+    }
+    true.into()
+}
+
+impl WebView {
+    pub fn open_devtools(&self) -> Result<&Self> {
+        if self.devtools_window()?.is_none() {
+            let before = self.devtools_windows()?;
+            self.devtools.borrow_mut().before = Some(before);
+        }
+        unsafe { self.core.OpenDevToolsWindow()? };
+        Ok(self)
+    }
+
+    // WebView2 has no API for this, so the window `open_devtools` opened is told apart from
+    // those of other WebViews by appearing after it was called, and closed. DevTools the user
+    // opened with F12 or the context menu aren't known, so they stay.
+    pub fn close_devtools(&self) -> Result<&Self> {
+        if let Some(hwnd) = self.devtools_window()? {
+            unsafe { PostMessageW(hwnd, WM_CLOSE, WPARAM(0), LPARAM(0)) };
+        }
+        Ok(self)
+    }
+
+    // Only the DevTools from `open_devtools`, once their window has shown up.
+    pub fn is_devtools_open(&self) -> Result<bool> {
+        Ok(self.devtools_window()?.is_some())
+    }
+
+    pub fn toggle_devtools(&self) -> Result<&Self> {
+        if self.is_devtools_open()? {
+            self.close_devtools()
+        } else {
+            self.open_devtools()
+        }
+    }
+
+    fn devtools_window(&self) -> Result<Option<HWND>> {
+        let window = self.devtools.borrow().window;
+        if let Some(hwnd) = window {
+            if unsafe { IsWindow(hwnd).as_bool() && IsWindowVisible(hwnd).as_bool() } {
+                return Ok(Some(hwnd));
+            }
+            self.devtools.borrow_mut().window = None;
+        }
+        let before = self.devtools.borrow().before.clone();
+        if let Some(before) = before {
+            let new = self
+                .devtools_windows()?
+                .into_iter()
+                .find(|hwnd| !before.contains(hwnd));
+            if new.is_some() {
+                *self.devtools.borrow_mut() = Tracked {
+                    before: None,
+                    window: new,
+                };
+            }
+            return Ok(new);
+        }
+        Ok(None)
+    }
+
+    fn devtools_windows(&self) -> Result<Vec<HWND>> {
+        let mut search = Search {
+            pid: 0,
+            found: Vec::new(),
+        };
+        unsafe {
+            self.core.BrowserProcessId(&mut search.pid)?;
+            EnumWindows(
+                Some(collect_devtools),
+                LPARAM(&mut search as *mut Search as _),
+            );
+        }
+        Ok(search.found)
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        mpsc, Mutex,
+    },
+    thread,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::{HWND, PWSTR},
+        System::Threading::GetCurrentProcessId,
+        UI::WindowsAndMessaging::SetTimer,
+    },
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{process, Result, WebView, WebViewBuilder};
+
+// How often the UI thread is expected to run the heartbeat; anything later is a stall.
+const HEARTBEAT_MS: u32 = 100;
+const HEARTBEAT_TIMER_ID: usize = 6;
+const IPC_LOG_LEN: usize = 50;
+// Polled by the diagnostics window itself, so left out of the IPC log.
+const BINDING: &str = "_taco_diagnostics";
+
+static PENDING_DISPATCHES: AtomicUsize = AtomicUsize::new(0);
+// The longest stall since the diagnostics window last looked.
+static LONGEST_STALL_US: AtomicU64 = AtomicU64::new(0);
+static IPC_CALLS: Mutex<VecDeque<IpcCall>> = Mutex::new(VecDeque::new());
+
+thread_local! {
+    static LAST_BEAT: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IpcCall {
+    method: String,
+    duration_ms: f64,
+    ok: bool,
+    // Seconds since the Unix epoch.
+    at: f64,
+}
+
+pub(crate) fn dispatch_posted() {
+    PENDING_DISPATCHES.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn dispatch_ran() {
+    PENDING_DISPATCHES.fetch_sub(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_ipc(method: &str, started: Instant, ok: bool) {
+    if method == BINDING {
+        return;
+    }
+    let at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let mut calls = IPC_CALLS.lock().unwrap_or_else(|err| err.into_inner());
+    if calls.len() == IPC_LOG_LEN {
+        calls.pop_front();
+    }
+    calls.push_back(IpcCall {
+        method: method.to_string(),
+        duration_ms: started.elapsed().as_secs_f64() * 1000.,
+        ok,
+        at,
+    });
+}
+
+unsafe extern "system" fn heartbeat(_hwnd: HWND, _msg: u32, _id: usize, _time: u32) {
+    let now = Instant::now();
+    if let Some(last) = LAST_BEAT.with(|beat| beat.replace(Some(now))) {
+        let late = (now - last).as_micros() as u64;
+        let stall = late.saturating_sub(HEARTBEAT_MS as u64 * 1000);
+        LONGEST_STALL_US.fetch_max(stall, Ordering::Relaxed);
+    }
+}
+
+fn process(pid: u32, kind: &str, (working_set, private_bytes): (usize, usize)) -> Value {
+    json!({
+        "pid": pid,
+        "kind": kind,
+        "workingSet": working_set,
+        "privateBytes": private_bytes,
+    })
+}
+
+// The diagnostics window shares the app's data directory, and so its browser process, so its
+// environment lists the app's WebView2 processes too.
+fn snapshot(environment: &ICoreWebView2Environment) -> Result<Value> {
+    let host = unsafe { GetCurrentProcessId() };
+    let mut processes = vec![process(host, "host", process::memory(host))];
+    for p in process::list(environment)? {
+        let memory = (p.working_set, p.private_bytes);
+        processes.push(process(p.pid, p.kind.name(), memory));
+    }
+
+    let ipc = IPC_CALLS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    Ok(json!({
+        "processes": processes,
+        "pendingDispatches": PENDING_DISPATCHES.load(Ordering::Relaxed),
+        "longestStallMs": LONGEST_STALL_US.swap(0, Ordering::Relaxed) as f64 / 1000.,
+        "ipc": ipc,
+    }))
+}
+
+impl WebView {
+    // Opens a window listing the WebView2 processes and their memory, how long this thread's
+    // message loop has stalled, the dispatches waiting to run and the latest binding calls.
+    // It runs on its own thread, so it stays responsive while this one is stuck. It can't open
+    // next to WebViews built with `language` or browser args, whose browser process
+    // it would have to share; that's the error returned then.
+    //
+    // The stalls are measured by a timer on this WebView's window, so they're this thread's
+    // until the window is destroyed, whichever window opened the diagnostics last.
+    pub fn open_diagnostics(&self) -> Result<()> {
+        unsafe { SetTimer(self.hwnd, HEARTBEAT_TIMER_ID, HEARTBEAT_MS, Some(heartbeat)) };
+        LAST_BEAT.with(|beat| beat.set(None));
+
+        // Same folder, same browser process; see `snapshot`.
+        let mut data_directory = PWSTR::default();
+        unsafe {
+            self.environment
+                .cast::<ICoreWebView2Environment7>()?
+                .UserDataFolder(&mut data_directory)?;
+        }
+        let data_directory = take_pwstr(data_directory);
+
+        // Back from the thread once the window is up.
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let open = || -> Result<_> {
+                let window = WebViewBuilder {
+                    title: "taco diagnostics",
+                    data_directory: Some(&data_directory),
+                    width: 720,
+                    height: 540,
+                    ..Default::default()
+                }
+                .build()?;
+                let webview = window.webview().clone();
+                let environment = webview.environment.clone();
+                webview.bind_unsafe(BINDING, move |_| {
+                    snapshot(&environment).map_err(|err| err.to_string())
+                });
+                webview
+                    .set_html(include_str!("diagnostics.html"))?
+                    .set_visible(true)?;
+                Ok(window)
+            };
+            match open() {
+                Ok(window) => {
+                    let _ = tx.send(Ok(()));
+                    let _ = window.run(());
+                }
+                Err(err) => {
+                    let _ = tx.send(Err(err));
+                }
+            }
+        });
+        webview2_com::wait_with_pump(rx)?
+    }
+}
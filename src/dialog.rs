@@ -0,0 +1,133 @@
+use windows::Win32::{
+    Foundation::{HWND, PWSTR},
+    System::WinRT::EventRegistrationToken,
+    UI::WindowsAndMessaging::*,
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{event::Subscription, Result, WebView};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptDialogKind {
+    Alert,
+    Confirm,
+    Prompt,
+    // "Leave site?" when the page handles `beforeunload`.
+    BeforeUnload,
+}
+
+// An open alert/confirm/prompt. The page waits until it is answered, which may be after the
+// callback returns: keep the dialog and call `accept` or `dismiss` later. Dropping it dismisses.
+pub struct ScriptDialog {
+    pub kind: ScriptDialogKind,
+    pub uri: String,
+    pub message: String,
+    // What a prompt shows in its text field initially.
+    pub default_text: String,
+    args: ICoreWebView2ScriptDialogOpeningEventArgs,
+    deferral: Option<ICoreWebView2Deferral>,
+}
+
+impl ScriptDialog {
+    // OK for confirm and beforeunload. A prompt returns the default text; see `accept_with_text`.
+    pub fn accept(self) -> Result<()> {
+        let text = self.default_text.clone();
+        self.accept_with_text(&text)
+    }
+
+    pub fn accept_with_text(mut self, text: &str) -> Result<()> {
+        unsafe {
+            self.args.SetResultText(text)?;
+            self.args.Accept()?;
+        }
+        self.complete()
+    }
+
+    // Cancel for confirm, null for prompt.
+    pub fn dismiss(mut self) -> Result<()> {
+        self.complete()
+    }
+
+    // Answers with a MessageBox owned by `hwnd`. Windows has no native prompt, so prompts are
+    // accepted with their default text.
+    pub fn show_message_box(self, hwnd: HWND, title: &str) -> Result<()> {
+        let style = match self.kind {
+            ScriptDialogKind::Alert => MB_OK,
+            ScriptDialogKind::Confirm => MB_OKCANCEL,
+            ScriptDialogKind::BeforeUnload => MB_OKCANCEL | MB_ICONWARNING,
+            ScriptDialogKind::Prompt => return self.accept(),
+        };
+        match unsafe { MessageBoxW(hwnd, self.message.as_str(), title, style) } {
+            IDOK => self.accept(),
+            _ => self.dismiss(),
+        }
+    }
+
+    fn complete(&mut self) -> Result<()> {
+        if let Some(deferral) = self.deferral.take() {
+            unsafe { deferral.Complete()? };
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ScriptDialog {
+    fn drop(&mut self) {
+        let _ = self.complete();
+    }
+}
+
+impl WebView {
+    // Replaces the default Chromium dialogs with `f`.
+    pub fn on_script_dialog(
+        &self,
+        mut f: impl FnMut(ScriptDialog) + 'static,
+    ) -> Result<Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core
+                .Settings()?
+                .SetAreDefaultScriptDialogsEnabled(false)?;
+
+            self.core.ScriptDialogOpening(
+                ScriptDialogOpeningEventHandler::create(Box::new(
+                    move |_core, args: Option<ICoreWebView2ScriptDialogOpeningEventArgs>| {
+                        if let Some(args) = args {
+                            let mut kind = COREWEBVIEW2_SCRIPT_DIALOG_KIND::default();
+                            let (mut uri, mut message, mut default_text) =
+                                (PWSTR::default(), PWSTR::default(), PWSTR::default());
+                            args.Kind(&mut kind)?;
+                            args.Uri(&mut uri)?;
+                            args.Message(&mut message)?;
+                            args.DefaultText(&mut default_text)?;
+                            f(ScriptDialog {
+                                kind: match kind {
+                                    COREWEBVIEW2_SCRIPT_DIALOG_KIND_CONFIRM => {
+                                        ScriptDialogKind::Confirm
+                                    }
+                                    COREWEBVIEW2_SCRIPT_DIALOG_KIND_PROMPT => {
+                                        ScriptDialogKind::Prompt
+                                    }
+                                    COREWEBVIEW2_SCRIPT_DIALOG_KIND_BEFOREUNLOAD => {
+                                        ScriptDialogKind::BeforeUnload
+                                    }
+                                    _ => ScriptDialogKind::Alert,
+                                },
+                                uri: take_pwstr(uri),
+                                message: take_pwstr(message),
+                                default_text: take_pwstr(default_text),
+                                deferral: Some(args.GetDeferral()?),
+                                args,
+                            });
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut token,
+            )?;
+        }
+        let core = self.core.clone();
+        Ok(self.track(move || unsafe { core.RemoveScriptDialogOpening(token) }))
+    }
+}
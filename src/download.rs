@@ -0,0 +1,192 @@
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::{BOOL, PWSTR},
+        System::WinRT::EventRegistrationToken,
+    },
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{event::Subscription, Result, WebView};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DownloadState {
+    InProgress,
+    // See `Download::interrupt_reason`; `resume` may continue it.
+    Interrupted,
+    Completed,
+}
+
+// A download started by the page. Clones refer to the same download.
+#[derive(Clone)]
+pub struct Download {
+    operation: ICoreWebView2DownloadOperation,
+    // Whose `unsubscribe` removes the handlers, or closing its window does.
+    webview: WebView,
+}
+
+impl Download {
+    pub fn uri(&self) -> Result<String> {
+        let mut uri = PWSTR::default();
+        unsafe { self.operation.Uri(&mut uri)? };
+        Ok(take_pwstr(uri))
+    }
+
+    pub fn mime_type(&self) -> Result<String> {
+        let mut mime_type = PWSTR::default();
+        unsafe { self.operation.MimeType(&mut mime_type)? };
+        Ok(take_pwstr(mime_type))
+    }
+
+    pub fn path(&self) -> Result<String> {
+        let mut path = PWSTR::default();
+        unsafe { self.operation.ResultFilePath(&mut path)? };
+        Ok(take_pwstr(path))
+    }
+
+    pub fn bytes_received(&self) -> Result<u64> {
+        let mut bytes = 0;
+        unsafe { self.operation.BytesReceived(&mut bytes)? };
+        Ok(bytes as u64)
+    }
+
+    // None if the server didn't send a Content-Length.
+    pub fn total_bytes(&self) -> Result<Option<u64>> {
+        let mut bytes = 0;
+        unsafe { self.operation.TotalBytesToReceive(&mut bytes)? };
+        Ok((bytes > 0).then_some(bytes as u64))
+    }
+
+    pub fn state(&self) -> Result<DownloadState> {
+        let mut state = COREWEBVIEW2_DOWNLOAD_STATE::default();
+        unsafe { self.operation.State(&mut state)? };
+        Ok(match state {
+            COREWEBVIEW2_DOWNLOAD_STATE_INTERRUPTED => DownloadState::Interrupted,
+            COREWEBVIEW2_DOWNLOAD_STATE_COMPLETED => DownloadState::Completed,
+            _ => DownloadState::InProgress,
+        })
+    }
+
+    // A COREWEBVIEW2_DOWNLOAD_INTERRUPT_REASON value, 0 if not interrupted.
+    pub fn interrupt_reason(&self) -> Result<i32> {
+        let mut reason = COREWEBVIEW2_DOWNLOAD_INTERRUPT_REASON::default();
+        unsafe { self.operation.InterruptReason(&mut reason)? };
+        Ok(reason)
+    }
+
+    pub fn cancel(&self) -> Result<()> {
+        unsafe { self.operation.Cancel()? };
+        Ok(())
+    }
+
+    pub fn pause(&self) -> Result<()> {
+        unsafe { self.operation.Pause()? };
+        Ok(())
+    }
+
+    pub fn resume(&self) -> Result<()> {
+        unsafe { self.operation.Resume()? };
+        Ok(())
+    }
+
+    pub fn can_resume(&self) -> Result<bool> {
+        let mut can = BOOL::default();
+        unsafe { self.operation.CanResume(&mut can)? };
+        Ok(can.as_bool())
+    }
+
+    pub fn on_progress(&self, mut f: impl FnMut(&Download) + 'static) -> Result<Subscription> {
+        let webview = self.webview.clone();
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.operation.BytesReceivedChanged(
+                BytesReceivedChangedEventHandler::create(Box::new(move |operation, _args| {
+                    if let Some(operation) = operation {
+                        let webview = webview.clone();
+                        f(&Download { operation, webview });
+                    }
+                    Ok(())
+                })),
+                &mut token,
+            )?;
+        }
+        let operation = self.operation.clone();
+        Ok(self
+            .webview
+            .track(move || unsafe { operation.RemoveBytesReceivedChanged(token) }))
+    }
+
+    pub fn on_state_changed(&self, mut f: impl FnMut(&Download) + 'static) -> Result<Subscription> {
+        let webview = self.webview.clone();
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.operation.StateChanged(
+                StateChangedEventHandler::create(Box::new(move |operation, _args| {
+                    if let Some(operation) = operation {
+                        let webview = webview.clone();
+                        f(&Download { operation, webview });
+                    }
+                    Ok(())
+                })),
+                &mut token,
+            )?;
+        }
+        let operation = self.operation.clone();
+        Ok(self
+            .webview
+            .track(move || unsafe { operation.RemoveStateChanged(token) }))
+    }
+}
+
+// Changes to `path`, `cancel` and `suppress_default_ui` are applied when the callback returns.
+pub struct DownloadStarting {
+    pub download: Download,
+    pub path: String,
+    pub cancel: bool,
+    // Hides the default download flyout, e.g. to show progress in the app instead.
+    pub suppress_default_ui: bool,
+}
+
+impl WebView {
+    pub fn on_download_starting(
+        &self,
+        mut f: impl FnMut(&mut DownloadStarting) + 'static,
+    ) -> Result<Subscription> {
+        let webview = self.clone();
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core.cast::<ICoreWebView2_4>()?.DownloadStarting(
+                DownloadStartingEventHandler::create(Box::new(
+                    move |_core, args: Option<ICoreWebView2DownloadStartingEventArgs>| {
+                        if let Some(args) = args {
+                            let mut path = PWSTR::default();
+                            args.ResultFilePath(&mut path)?;
+                            let path = take_pwstr(path);
+                            let mut starting = DownloadStarting {
+                                download: Download {
+                                    operation: args.DownloadOperation()?,
+                                    webview: webview.clone(),
+                                },
+                                path: path.clone(),
+                                cancel: false,
+                                suppress_default_ui: false,
+                            };
+                            f(&mut starting);
+
+                            if starting.path != path {
+                                args.SetResultFilePath(starting.path)?;
+                            }
+                            args.SetCancel(starting.cancel)?;
+                            args.SetHandled(starting.suppress_default_ui)?;
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut token,
+            )?;
+        }
+        let core: ICoreWebView2_4 = self.core.cast()?;
+        Ok(self.track(move || unsafe { core.RemoveDownloadStarting(token) }))
+    }
+}
@@ -0,0 +1,7 @@
+(function () {
+    const taco = window.taco = window.taco || {};
+
+    // Call from a mousedown or dragstart handler while the button is still held.
+    // Resolves to 'copy', 'link' or 'none' once the files are dropped.
+    taco.startFileDrag = paths => window._taco_startFileDrag(Array.isArray(paths) ? paths : [paths]);
+})();
@@ -0,0 +1,155 @@
+use std::{ffi::c_void, path::Path, ptr};
+
+use serde_json::Value;
+use windows::{
+    core::{IUnknown, Interface, GUID, HRESULT},
+    Win32::{
+        Foundation::{
+            BOOL, DRAGDROP_S_CANCEL, DRAGDROP_S_DROP, DRAGDROP_S_USEDEFAULTCURSORS, E_NOINTERFACE,
+            S_OK,
+        },
+        System::{Com::IDataObject, Ole::*},
+        UI::{
+            Shell::{Common::ITEMIDLIST, *},
+            WindowsAndMessaging::MK_LBUTTON,
+        },
+    },
+};
+
+use crate::{Result, WebView};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DropEffect {
+    None,
+    Copy,
+    Link,
+}
+
+impl DropEffect {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Copy => "copy",
+            Self::Link => "link",
+        }
+    }
+}
+
+// The drag source has no state, so a single static object with a no-op reference count is enough.
+#[repr(C)]
+struct DropSource {
+    vtable: &'static IDropSourceVtbl,
+}
+
+static DROP_SOURCE: DropSource = DropSource {
+    vtable: &IDropSourceVtbl(
+        query_interface,
+        add_ref,
+        release,
+        query_continue_drag,
+        give_feedback,
+    ),
+};
+
+unsafe extern "system" fn query_interface(
+    this: *mut c_void,
+    iid: &GUID,
+    interface: *mut *mut c_void,
+) -> HRESULT {
+    if *iid == IUnknown::IID || *iid == IDropSource::IID {
+        *interface = this;
+        S_OK
+    } else {
+        *interface = ptr::null_mut();
+        E_NOINTERFACE
+    }
+}
+
+unsafe extern "system" fn add_ref(_this: *mut c_void) -> u32 {
+    1
+}
+
+unsafe extern "system" fn release(_this: *mut c_void) -> u32 {
+    1
+}
+
+unsafe extern "system" fn query_continue_drag(
+    _this: *mut c_void,
+    escape_pressed: BOOL,
+    key_state: u32,
+) -> HRESULT {
+    if escape_pressed.as_bool() {
+        DRAGDROP_S_CANCEL
+    } else if key_state & MK_LBUTTON == 0 {
+        DRAGDROP_S_DROP
+    } else {
+        S_OK
+    }
+}
+
+unsafe extern "system" fn give_feedback(_this: *mut c_void, _effect: u32) -> HRESULT {
+    DRAGDROP_S_USEDEFAULTCURSORS
+}
+
+// The same data Explorer puts on a drag of these files.
+fn shell_data_object(paths: &[impl AsRef<Path>]) -> Result<IDataObject> {
+    let mut pidls = Vec::new();
+    let result = (|| unsafe {
+        for path in paths {
+            let mut pidl = ptr::null_mut::<ITEMIDLIST>();
+            SHParseDisplayName(
+                path.as_ref().to_string_lossy().as_ref(),
+                None,
+                &mut pidl,
+                0,
+                ptr::null_mut(),
+            )?;
+            pidls.push(pidl as *const ITEMIDLIST);
+        }
+        let items = SHCreateShellItemArrayFromIDLists(pidls.len() as u32, pidls.as_ptr())?;
+        Ok(items.BindToHandler(None, &BHID_DataObject)?)
+    })();
+    for pidl in pidls {
+        unsafe { ILFree(pidl) };
+    }
+    result
+}
+
+// Drags the files out of the app, e.g. onto Explorer or the desktop. Blocks until they are
+// dropped, so call it while the left button is held.
+pub fn start_file_drag(paths: &[impl AsRef<Path>]) -> Result<DropEffect> {
+    let data = shell_data_object(paths)?;
+    let source: IDropSource =
+        unsafe { std::mem::transmute(&DROP_SOURCE as *const DropSource as *mut c_void) };
+
+    // Drag and drop needs OLE on top of the COM apartment. This is reference counted, too.
+    unsafe { OleInitialize(ptr::null_mut())? };
+    let mut effect = DROPEFFECT_NONE;
+    let result =
+        unsafe { DoDragDrop(data, source, DROPEFFECT_COPY | DROPEFFECT_LINK, &mut effect) };
+    unsafe { OleUninitialize() };
+
+    if result != DRAGDROP_S_DROP {
+        result.ok()?;
+        return Ok(DropEffect::None);
+    }
+    Ok(match effect {
+        DROPEFFECT_COPY => DropEffect::Copy,
+        DROPEFFECT_LINK => DropEffect::Link,
+        _ => DropEffect::None,
+    })
+}
+
+// Binds `taco.startFileDrag(path | paths)`.
+pub(crate) fn inject(webview: &WebView) -> Result<()> {
+    webview.bind_unsafe("_taco_startFileDrag", |params| {
+        if let [Value::Array(paths)] = &params[..] {
+            let paths = paths.iter().filter_map(Value::as_str).collect::<Vec<_>>();
+            let effect = start_file_drag(&paths).map_err(|err| err.to_string())?;
+            return Ok(Value::String(effect.as_str().into()));
+        }
+        Err("Usage: _taco_startFileDrag(paths)".into())
+    });
+    webview.init(include_str!("drag.js"))?;
+    Ok(())
+}
@@ -0,0 +1,51 @@
+(function () {
+    const taco = window.taco = window.taco || {};
+
+    // Text, HTML, links and images dragged in from other apps, while there's a
+    // `taco.on('drop', ...)` listener and the page itself didn't take the drop. Other files are
+    // left to `Window::on_file_drop`.
+    const TEXT_TYPES = ['text/plain', 'text/html', 'text/uri-list'];
+    const claims = dataTransfer => Array.from(dataTransfer.items).some(item =>
+        item.kind === 'string' ? TEXT_TYPES.includes(item.type) : item.type.startsWith('image/'));
+    const wanted = e => !e.defaultPrevented && e.dataTransfer && taco._listening('drop') && claims(e.dataTransfer);
+
+    const readImage = file => new Promise((resolve, reject) => {
+        const reader = new FileReader();
+        reader.onload = () => resolve({ type: 'image', name: file.name, mime: file.type, dataUrl: reader.result });
+        reader.onerror = () => reject(reader.error);
+        reader.readAsDataURL(file);
+    });
+
+    document.addEventListener('dragover', e => {
+        if (wanted(e)) {
+            e.preventDefault();
+            e.dataTransfer.dropEffect = 'copy';
+        }
+    });
+
+    document.addEventListener('drop', async e => {
+        if (!wanted(e)) {
+            return;
+        }
+        e.preventDefault();
+        // The data is only readable during the event itself.
+        const data = e.dataTransfer;
+        const items = [];
+        const text = data.getData('text/plain');
+        if (text) {
+            items.push({ type: 'text', text });
+        }
+        const html = data.getData('text/html');
+        if (html) {
+            items.push({ type: 'html', html });
+        }
+        const uris = data.getData('text/uri-list').split(/\r?\n/).filter(line => line && !line.startsWith('#'));
+        if (uris.length) {
+            items.push({ type: 'uri', uris });
+        }
+        const images = Array.from(data.files).filter(file => file.type.startsWith('image/'));
+        const { clientX: x, clientY: y } = e;
+        items.push(...await Promise.all(images.map(readImage)));
+        taco._emit('drop', { x, y, items });
+    });
+})();
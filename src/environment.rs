@@ -0,0 +1,387 @@
+use std::{
+    cell::Cell,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Mutex,
+    },
+};
+
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::{CloseHandle, E_POINTER, HWND, PWSTR},
+        System::{
+            Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION},
+            WinRT::EventRegistrationToken,
+        },
+        UI::{Shell::ShellExecuteW, WindowsAndMessaging::SW_SHOWNORMAL},
+    },
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{com, Error, Result, WebViewBuilder};
+
+const INCOGNITO_PREFIX: &str = "taco-incognito-";
+
+// Numbers the incognito directories of this process.
+static INCOGNITO_COUNT: AtomicUsize = AtomicUsize::new(0);
+// Held while WEBVIEW2_RELEASE_CHANNEL_PREFERENCE is set for one environment; see `with_channel`.
+static CHANNEL_LOCK: Mutex<()> = Mutex::new(());
+
+// Microsoft's permanent link to the Evergreen bootstrapper, MicrosoftEdgeWebview2Setup.exe.
+pub const BOOTSTRAPPER_URL: &str = "https://go.microsoft.com/fwlink/p/?LinkId=2124703";
+
+// Which runtime the loader looks for first. This SDK can only pass it through the
+// process-wide WEBVIEW2_RELEASE_CHANNEL_PREFERENCE variable, so it's set only while the
+// runtime is picked, and environments are created one at a time, whichever thread builds them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReleaseChannelPreference {
+    // The installed WebView2 Runtime, falling back to Edge Beta, Dev and Canary.
+    Stable,
+    // Edge Canary first, then Dev and Beta, then the Runtime, e.g. to test upcoming changes.
+    Canary,
+}
+
+// A browser process with its data directory, for the WebViews built with
+// `WebViewBuilder::build_with_env` on the thread that created it.
+#[derive(Clone)]
+pub struct Environment {
+    pub environment: ICoreWebView2Environment,
+    // Released after the environment.
+    pub(crate) _apartment: Rc<com::Apartment>,
+}
+
+impl Environment {
+    // The default environment: the installed runtime and the data directory next to the
+    // executable.
+    pub fn new() -> Result<Self> {
+        WebViewBuilder::default().create_environment()
+    }
+}
+
+// Curated sets of Chromium switches for `WebViewBuilder::browser_presets`. Their feature lists
+// are merged with each other's and with `additional_browser_args`; see `merge_browser_args`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    // Full-screen signage and terminals: no pinch zooming, swipe navigation or translate bar,
+    // and media plays without a click first.
+    Kiosk,
+    // One renderer process for all sites, and no caches kept around for quick back navigation.
+    LowMemory,
+    // For machines where Chromium misbehaves: no GPU acceleration, and no renderer code
+    // integrity, which blocks the DLLs some antivirus and IME software injects.
+    Compatibility,
+}
+
+impl Preset {
+    pub fn args(&self) -> &'static str {
+        match self {
+            Preset::Kiosk => {
+                "--disable-pinch --overscroll-history-navigation=0 \
+                 --autoplay-policy=no-user-gesture-required \
+                 --disable-features=Translate,OverscrollHistoryNavigation"
+            }
+            Preset::LowMemory => {
+                "--renderer-process-limit=1 --process-per-site \
+                 --disable-features=BackForwardCache,SpareRendererForSitePerProcess"
+            }
+            Preset::Compatibility => "--disable-gpu --disable-features=RendererCodeIntegrity",
+        }
+    }
+}
+
+// Chromium only reads the last `--enable-features` and `--disable-features`, so their lists
+// are combined into one switch each. Other switches appear once per name, with the last value
+// like Chromium would take, so that `additional_browser_args` override the presets.
+pub fn merge_browser_args<'a>(args: impl IntoIterator<Item = &'a str>) -> String {
+    let mut switches: Vec<&str> = Vec::new();
+    let mut enabled: Vec<&str> = Vec::new();
+    let mut disabled: Vec<&str> = Vec::new();
+    for arg in args.into_iter().flat_map(str::split_whitespace) {
+        let (list, features) = match (
+            arg.strip_prefix("--enable-features="),
+            arg.strip_prefix("--disable-features="),
+        ) {
+            (Some(features), _) => (&mut enabled, features),
+            (_, Some(features)) => (&mut disabled, features),
+            _ => {
+                let name = arg.split('=').next();
+                match switches.iter_mut().find(|s| s.split('=').next() == name) {
+                    Some(switch) => *switch = arg,
+                    None => switches.push(arg),
+                }
+                continue;
+            }
+        };
+        for feature in features.split(',').filter(|f| !f.is_empty()) {
+            if !list.contains(&feature) {
+                list.push(feature);
+            }
+        }
+    }
+
+    let mut merged = switches.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    if !enabled.is_empty() {
+        merged.push(format!("--enable-features={}", enabled.join(",")));
+    }
+    if !disabled.is_empty() {
+        merged.push(format!("--disable-features={}", disabled.join(",")));
+    }
+    merged.join(" ")
+}
+
+// What CreateCoreWebView2EnvironmentWithOptions gets. WebViews sharing a data directory share
+// one browser process, and must agree on the rest of these options.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct EnvironmentOptions<'a> {
+    // A fixed-version runtime shipped with the app, instead of the installed one.
+    pub browser_executable_folder: Option<&'a str>,
+    pub data_directory: Option<&'a str>,
+    pub language: Option<&'a str>,
+    pub additional_browser_args: Option<&'a str>,
+    pub release_channel_preference: Option<ReleaseChannelPreference>,
+}
+
+// Null-terminated UTF-16, which has to outlive the PWSTR pointing into it.
+fn wide(s: Option<&str>) -> Option<Vec<u16>> {
+    s.map(|s| s.encode_utf16().chain(Some(0)).collect())
+}
+
+fn as_pwstr(s: &Option<Vec<u16>>) -> PWSTR {
+    match s {
+        Some(s) => PWSTR(s.as_ptr() as *mut u16),
+        None => PWSTR::default(),
+    }
+}
+
+// Puts an environment variable back the way it was when dropped.
+struct RestoreVar {
+    name: &'static str,
+    previous: Option<OsString>,
+}
+
+impl RestoreVar {
+    fn set(name: &'static str, value: &str) -> Self {
+        let previous = std::env::var_os(name);
+        std::env::set_var(name, value);
+        Self { name, previous }
+    }
+}
+
+impl Drop for RestoreVar {
+    fn drop(&mut self) {
+        match &self.previous {
+            Some(value) => std::env::set_var(self.name, value),
+            None => std::env::remove_var(self.name),
+        }
+    }
+}
+
+// Runs `f` with WEBVIEW2_RELEASE_CHANNEL_PREFERENCE set to `preference`, as this SDK predates
+// ICoreWebView2EnvironmentOptions6. The variable is the process's, so other threads creating
+// environments wait until it's restored. `f` mustn't pump messages, or an environment created
+// from one of them on this thread would wait forever.
+fn with_channel<R>(preference: Option<ReleaseChannelPreference>, f: impl FnOnce() -> R) -> R {
+    let _lock = CHANNEL_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let _channel = preference.map(|preference| {
+        let value = match preference {
+            ReleaseChannelPreference::Stable => "0",
+            ReleaseChannelPreference::Canary => "1",
+        };
+        RestoreVar::set("WEBVIEW2_RELEASE_CHANNEL_PREFERENCE", value)
+    });
+    f()
+}
+
+pub(crate) fn create(options: &EnvironmentOptions) -> Result<ICoreWebView2Environment> {
+    let channel = options.release_channel_preference;
+
+    // Otherwise a missing runtime only shows up as an HRESULT from the loader below.
+    with_channel(channel, || {
+        browser_version(options.browser_executable_folder)
+    })?;
+
+    let environment_options: ICoreWebView2EnvironmentOptions =
+        CoreWebView2EnvironmentOptions::default().into();
+    unsafe {
+        if let Some(language) = options.language {
+            environment_options.SetLanguage(language)?;
+        }
+        if let Some(args) = options.additional_browser_args {
+            environment_options.SetAdditionalBrowserArguments(args)?;
+        }
+    }
+    let browser_executable_folder = wide(options.browser_executable_folder);
+    let data_directory = wide(options.data_directory);
+
+    let (tx, rx) = mpsc::channel();
+    CreateCoreWebView2EnvironmentCompletedHandler::wait_for_async_operation(
+        Box::new(move |environmentcreatedhandler| {
+            // The runtime is picked before the call returns, and before the pump starts.
+            // Null means the defaults: the installed runtime, and an `<exe name>.WebView2`
+            // data folder next to the executable.
+            with_channel(channel, || unsafe {
+                CreateCoreWebView2EnvironmentWithOptions(
+                    as_pwstr(&browser_executable_folder),
+                    as_pwstr(&data_directory),
+                    environment_options,
+                    environmentcreatedhandler,
+                )
+            })
+            .map_err(webview2_com::Error::WindowsError)
+        }),
+        Box::new(
+            move |error_code, environment: Option<ICoreWebView2Environment>| {
+                error_code?;
+                tx.send(environment.ok_or_else(|| windows::core::Error::fast_error(E_POINTER)))
+                    .expect("send over mpsc channel");
+                Ok(())
+            },
+        ),
+    )?;
+
+    rx.recv()
+        .map_err(|_| Error::WebView2Error(webview2_com::Error::SendError))?
+        .map_err(Error::from)
+}
+
+// WebView2's default is an `<exe name>.WebView2` folder next to the executable.
+pub(crate) fn data_directory_or_default(data_directory: Option<&str>) -> Result<PathBuf> {
+    match data_directory {
+        Some(directory) => Ok(PathBuf::from(directory)),
+        None => {
+            let exe = std::env::current_exe()?;
+            let stem = exe.file_stem().unwrap_or_default().to_string_lossy();
+            Ok(exe.with_file_name(format!("{}.WebView2", stem)))
+        }
+    }
+}
+
+// The data directory of the profile `name`: a folder of its own under `data_directory`, or
+// under the default `<exe name>.WebView2` folder next to the executable.
+pub(crate) fn profile_directory(data_directory: Option<&str>, name: &str) -> Result<String> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ' ';
+    if name.is_empty() || name.trim() != name || !name.chars().all(valid) {
+        return Err(Error::InvalidProfileName(name.to_string()));
+    }
+    let directory = data_directory_or_default(data_directory)?
+        .join("Profiles")
+        .join(name);
+    Ok(directory.to_string_lossy().into_owned())
+}
+
+fn is_running(pid: u32) -> bool {
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid);
+        if process.is_invalid() {
+            return false;
+        }
+        CloseHandle(process);
+        true
+    }
+}
+
+// A fresh data directory under %TEMP% for a `WebViewBuilder::incognito` window. Leftovers of
+// processes that are gone, i.e. ones that crashed before `remove_when_exited` got to run, are
+// deleted first.
+pub(crate) fn incognito_directory() -> Result<String> {
+    let temp = std::env::temp_dir();
+    for entry in fs::read_dir(&temp)?.flatten() {
+        let name = entry.file_name();
+        let pid = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(INCOGNITO_PREFIX))
+            .and_then(|rest| rest.split('-').next())
+            .and_then(|pid| pid.parse().ok());
+        if let Some(pid) = pid {
+            if !is_running(pid) {
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+    }
+
+    let n = INCOGNITO_COUNT.fetch_add(1, Ordering::Relaxed);
+    let directory = temp.join(format!("{}{}-{}", INCOGNITO_PREFIX, process::id(), n));
+    fs::create_dir_all(&directory)?;
+    Ok(directory.to_string_lossy().into_owned())
+}
+
+// Deletes `directory` once the browser process using it has exited and let go of its files.
+// That's after the windows are gone, so the handler isn't tracked by a WebView; it removes
+// itself instead.
+pub(crate) fn remove_when_exited(
+    environment: &ICoreWebView2Environment,
+    directory: String,
+) -> Result<()> {
+    let environment = environment.cast::<ICoreWebView2Environment5>()?;
+    let token = Rc::new(Cell::new(None));
+    let mut registered = EventRegistrationToken::default();
+    unsafe {
+        let (e, t) = (environment.clone(), token.clone());
+        environment.BrowserProcessExited(
+            BrowserProcessExitedEventHandler::create(Box::new(move |_, _| {
+                let _ = fs::remove_dir_all(&directory);
+                if let Some(token) = t.take() {
+                    e.RemoveBrowserProcessExited(token)?;
+                }
+                Ok(())
+            })),
+            &mut registered,
+        )?;
+    }
+    token.set(Some(registered));
+    Ok(())
+}
+
+// The version of the runtime in `browser_executable_folder`, or of the one new WebViews would
+// pick (see `ReleaseChannelPreference`) if None.
+pub fn browser_version(browser_executable_folder: Option<&str>) -> Result<String> {
+    let folder = wide(browser_executable_folder);
+    let mut version = PWSTR::default();
+    let found = unsafe {
+        GetAvailableCoreWebView2BrowserVersionString(as_pwstr(&folder), &mut version).is_ok()
+    };
+    // The call can also succeed with a null version.
+    if !found || version.is_null() {
+        return Err(Error::RuntimeNotFound);
+    }
+    Ok(take_pwstr(version))
+}
+
+// Installs the Evergreen WebView2 Runtime, e.g. after `build()` failed with
+// `Error::RuntimeNotFound`. Runs `bootstrapper` (a MicrosoftEdgeWebview2Setup.exe shipped with
+// the app) and waits for it to finish. Without one, the browser opens `BOOTSTRAPPER_URL` for the
+// user to download and run it themselves.
+pub fn install_runtime(bootstrapper: Option<&Path>) -> Result<()> {
+    if let Some(bootstrapper) = bootstrapper {
+        let status = process::Command::new(bootstrapper)
+            .args(["/silent", "/install"])
+            .status()?;
+        if !status.success() {
+            return Err(Error::RuntimeNotFound);
+        }
+        return Ok(());
+    }
+
+    let result = unsafe {
+        ShellExecuteW(
+            HWND::default(),
+            "open",
+            BOOTSTRAPPER_URL,
+            PWSTR::default(),
+            PWSTR::default(),
+            SW_SHOWNORMAL as i32,
+        )
+    };
+    // Values up to 32 are errors.
+    if result.0 <= 32 {
+        return Err(windows::core::Error::from_win32().into());
+    }
+    Ok(())
+}
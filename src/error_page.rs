@@ -0,0 +1,152 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use windows::Win32::Foundation::PWSTR;
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{event::Subscription, Result, WebView};
+
+// What kind of failure a navigation ran into, for picking an error page.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    // No network, or the server didn't answer.
+    Offline,
+    // The host name didn't resolve.
+    Dns,
+    Certificate,
+    // A `register_protocol` handler or the assets answered 404 for a page.
+    NotFound,
+    Other,
+}
+
+#[derive(Clone, Debug)]
+pub struct NavigationError {
+    pub uri: String,
+    pub class: ErrorClass,
+    // A COREWEBVIEW2_WEB_ERROR_STATUS value, 0 for `NotFound`.
+    pub web_error_status: i32,
+}
+
+type ErrorPageCallback = Rc<RefCell<dyn FnMut(&NavigationError) -> Option<String>>>;
+
+// The templates and callback of one WebView; the callback goes first.
+#[derive(Default)]
+pub(crate) struct ErrorPages {
+    templates: HashMap<ErrorClass, String>,
+    callback: Option<ErrorPageCallback>,
+    watching: bool,
+    // Set by `retry::watch` until it gives up, so that its fallback page isn't replaced.
+    pub(crate) retrying: bool,
+}
+
+pub(crate) fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Cancellations (the user navigated elsewhere) and unknown statuses, which include HTTP errors
+// from servers that send pages of their own, are left alone.
+fn classify(status: COREWEBVIEW2_WEB_ERROR_STATUS) -> Option<ErrorClass> {
+    let class = match status {
+        COREWEBVIEW2_WEB_ERROR_STATUS_UNKNOWN
+        | COREWEBVIEW2_WEB_ERROR_STATUS_OPERATION_CANCELED => return None,
+        COREWEBVIEW2_WEB_ERROR_STATUS_HOST_NAME_NOT_RESOLVED => ErrorClass::Dns,
+        COREWEBVIEW2_WEB_ERROR_STATUS_DISCONNECTED
+        | COREWEBVIEW2_WEB_ERROR_STATUS_CANNOT_CONNECT
+        | COREWEBVIEW2_WEB_ERROR_STATUS_SERVER_UNREACHABLE
+        | COREWEBVIEW2_WEB_ERROR_STATUS_TIMEOUT
+        | COREWEBVIEW2_WEB_ERROR_STATUS_CONNECTION_ABORTED
+        | COREWEBVIEW2_WEB_ERROR_STATUS_CONNECTION_RESET => ErrorClass::Offline,
+        COREWEBVIEW2_WEB_ERROR_STATUS_CERTIFICATE_COMMON_NAME_IS_INCORRECT
+        | COREWEBVIEW2_WEB_ERROR_STATUS_CERTIFICATE_EXPIRED
+        | COREWEBVIEW2_WEB_ERROR_STATUS_CLIENT_CERTIFICATE_CONTAINS_ERRORS
+        | COREWEBVIEW2_WEB_ERROR_STATUS_CERTIFICATE_REVOKED
+        | COREWEBVIEW2_WEB_ERROR_STATUS_CERTIFICATE_IS_INVALID => ErrorClass::Certificate,
+        _ => ErrorClass::Other,
+    };
+    Some(class)
+}
+
+// The page to show for `error`, if the app registered one.
+pub(crate) fn page(pages: &RefCell<ErrorPages>, error: &NavigationError) -> Option<String> {
+    // Cloned out, so that the callback can register more pages.
+    let callback = pages.borrow().callback.clone();
+    if let Some(html) = callback.and_then(|f| (f.borrow_mut())(error)) {
+        return Some(html);
+    }
+    pages
+        .borrow()
+        .templates
+        .get(&error.class)
+        .map(|html| html.replace("{url}", &escape_html(&error.uri)))
+}
+
+impl WebView {
+    // Shows `html` instead of Edge's error page for navigations failing with `class`, with
+    // `{url}` replaced by the URL that failed. `NotFound` pages are served in place of the
+    // empty 404 responses, so the address stays; the others replace the page like `set_html`.
+    pub fn set_error_page(&self, class: ErrorClass, html: &str) -> Result<&Self> {
+        self.error_pages
+            .borrow_mut()
+            .templates
+            .insert(class, html.to_string());
+        self.watch_navigation_errors()?;
+        Ok(self)
+    }
+
+    // Decides the error page per failure, before the `set_error_page` templates. None falls
+    // back to those, then to Edge's own page. Replaces the previous callback.
+    pub fn on_error_page(
+        &self,
+        f: impl FnMut(&NavigationError) -> Option<String> + 'static,
+    ) -> Result<Subscription> {
+        let callback: ErrorPageCallback = Rc::new(RefCell::new(f));
+        self.error_pages.borrow_mut().callback = Some(callback.clone());
+        self.watch_navigation_errors()?;
+
+        let pages = self.error_pages.clone();
+        Ok(self.track(move || {
+            let mut pages = pages.borrow_mut();
+            if pages
+                .callback
+                .as_ref()
+                .is_some_and(|f| Rc::ptr_eq(f, &callback))
+            {
+                pages.callback = None;
+            }
+            Ok(())
+        }))
+    }
+
+    fn watch_navigation_errors(&self) -> Result<()> {
+        if std::mem::replace(&mut self.error_pages.borrow_mut().watching, true) {
+            return Ok(());
+        }
+        let w = self.clone();
+        // After the handler of `retry::watch`, which the builder registers first.
+        self.on_navigation_completed(move |args| {
+            if args.is_success || w.error_pages.borrow().retrying {
+                return;
+            }
+            let class = match classify(args.web_error_status) {
+                Some(class) => class,
+                None => return,
+            };
+            let mut uri = PWSTR::default();
+            if unsafe { w.core.Source(&mut uri) }.is_err() {
+                return;
+            }
+            let error = NavigationError {
+                uri: take_pwstr(uri),
+                class,
+                web_error_status: args.web_error_status,
+            };
+            if let Some(html) = page(&w.error_pages, &error) {
+                let _ = unsafe { w.core.NavigateToString(html) };
+            }
+        })?;
+        Ok(())
+    }
+}
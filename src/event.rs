@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use crate::{Result, WebView};
+
+// Returned by the `on_*` methods of `WebView`, to remove the callback again with
+// `WebView::unsubscribe`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Subscription(u64);
+
+type Remover = Box<dyn FnOnce() -> windows::core::Result<()>>;
+
+// How to remove each event handler registered on the WebView, the ones taco registers for
+// itself included. Their closures often hold a clone of the WebView, so they'd keep each
+// other alive if they weren't removed when the window closes.
+#[derive(Default)]
+pub(crate) struct Registry {
+    next_id: u64,
+    removers: HashMap<u64, Remover>,
+}
+
+impl WebView {
+    // `remove` is usually the Remove... method matching the registration, with its token.
+    pub(crate) fn track(
+        &self,
+        remove: impl FnOnce() -> windows::core::Result<()> + 'static,
+    ) -> Subscription {
+        let mut events = self.events.borrow_mut();
+        let id = events.next_id;
+        events.next_id += 1;
+        events.removers.insert(id, Box::new(remove));
+        Subscription(id)
+    }
+
+    // One subscription for all of `subscriptions`, for the `on_*` methods that register more
+    // than one handler.
+    pub(crate) fn merge(&self, subscriptions: &[Subscription]) -> Subscription {
+        let removers = {
+            let mut events = self.events.borrow_mut();
+            subscriptions
+                .iter()
+                .filter_map(|subscription| events.removers.remove(&subscription.0))
+                .collect::<Vec<_>>()
+        };
+        // All of them, even after one fails.
+        self.track(move || {
+            let mut result = Ok(());
+            for remove in removers {
+                result = result.and(remove());
+            }
+            result
+        })
+    }
+
+    // For the `on_*` methods that hear from the page through the binding `name`.
+    pub(crate) fn track_binding(&self, name: &str) -> Subscription {
+        let (w, name) = (self.clone(), name.to_string());
+        self.track(move || {
+            w.forget_binding(&name);
+            w.remove_binding_script(&name)
+        })
+    }
+
+    // `init`, removed again with the subscription.
+    pub(crate) fn track_init(&self, js: &str) -> Result<Subscription> {
+        let id = self.add_init_script(js)?;
+        let (w, js) = (self.clone(), js.to_string());
+        Ok(self.track(move || {
+            w.init_scripts.borrow_mut().retain(|script| *script != js);
+            unsafe { w.core.RemoveScriptToExecuteOnDocumentCreated(id) }
+        }))
+    }
+
+    // Does nothing for subscriptions that are already gone.
+    pub fn unsubscribe(&self, subscription: Subscription) -> Result<()> {
+        let remove = self.events.borrow_mut().removers.remove(&subscription.0);
+        if let Some(remove) = remove {
+            remove()?;
+        }
+        Ok(())
+    }
+
+    // Every subscription made from now on has an id from here on.
+    pub(crate) fn subscription_mark(&self) -> u64 {
+        self.events.borrow().next_id
+    }
+
+    // Removes the handlers registered since `mark`, leaving the earlier ones, e.g. taco's own.
+    pub(crate) fn unsubscribe_since(&self, mark: u64) {
+        let removers = {
+            let mut events = self.events.borrow_mut();
+            let ids = events
+                .removers
+                .keys()
+                .filter(|&&id| id >= mark)
+                .copied()
+                .collect::<Vec<_>>();
+            ids.iter()
+                .filter_map(|id| events.removers.remove(id))
+                .collect::<Vec<_>>()
+        };
+        for remove in removers {
+            let _ = remove();
+        }
+    }
+
+    // Removes every handler, including taco's own; the WebView stops responding to the page.
+    // Done when the window is destroyed.
+    pub fn unsubscribe_all(&self) {
+        let removers = std::mem::take(&mut self.events.borrow_mut().removers);
+        for (_, remove) in removers {
+            let _ = remove();
+        }
+    }
+}
@@ -0,0 +1,34 @@
+(function () {
+    const taco = window.taco = window.taco || {};
+    const listeners = {};
+
+    taco.on = (event, callback) => {
+        (listeners[event] = listeners[event] || []).push(callback);
+        return () => taco.off(event, callback);
+    };
+
+    taco.once = (event, callback) => {
+        const off = taco.on(event, payload => {
+            off();
+            callback(payload);
+        });
+        return off;
+    };
+
+    taco.off = (event, callback) => {
+        listeners[event] = (listeners[event] || []).filter(f => f !== callback);
+    };
+
+    taco._listening = event => (listeners[event] || []).length > 0;
+
+    taco._emit = (event, payload) => {
+        (listeners[event] || []).slice().forEach(f => f(payload));
+    };
+
+    window.chrome.webview.addEventListener('message', e => {
+        const message = e.data;
+        if (message && typeof message === 'object' && '_tacoEvent' in message) {
+            taco._emit(message._tacoEvent, message.payload);
+        }
+    });
+})();
@@ -0,0 +1,139 @@
+use std::{cell::RefCell, rc::Rc};
+
+use windows::Win32::{
+    Foundation::{BOOL, PWSTR},
+    System::WinRT::EventRegistrationToken,
+    UI::{Shell::ShellExecuteW, WindowsAndMessaging::SW_SHOWNORMAL},
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{event::Subscription, Result, WebView};
+
+// Schemes the WebView opens itself; anything else is handed to another app.
+const BROWSER_SCHEMES: [&str; 13] = [
+    "http",
+    "https",
+    "file",
+    "about",
+    "data",
+    "blob",
+    "javascript",
+    "ws",
+    "wss",
+    "edge",
+    "devtools",
+    "view-source",
+    "chrome-extension",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LaunchDecision {
+    // Opens the URI with its app right away, without asking the user.
+    Launch,
+    Deny,
+    // Leaves it to WebView2, which asks the user first.
+    Ask,
+}
+
+// e.g. `mailto:someone@example.com`, `ms-settings:display`, `myapp://open?id=1`
+#[derive(Clone, Debug)]
+pub struct ExternalUriRequest {
+    pub uri: String,
+    // Lowercase, without the colon.
+    pub scheme: String,
+    pub is_user_initiated: bool,
+}
+
+fn external_scheme(uri: &str) -> Option<String> {
+    let (scheme, _) = uri.split_once(':')?;
+    let scheme = scheme.to_ascii_lowercase();
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    (valid && !BROWSER_SCHEMES.contains(&scheme.as_str())).then_some(scheme)
+}
+
+fn launch(webview: &WebView, uri: &str) {
+    unsafe {
+        ShellExecuteW(
+            webview.hwnd,
+            "open",
+            uri,
+            PWSTR::default(),
+            PWSTR::default(),
+            SW_SHOWNORMAL as i32,
+        )
+    };
+}
+
+impl WebView {
+    // Decides what happens to links and navigations to other apps' schemes, in the page and its
+    // iframes, instead of WebView2's confirmation dialog. This SDK predates the
+    // LaunchingExternalUriScheme event, so they're recognized by their scheme as they start.
+    pub fn on_external_uri_scheme(
+        &self,
+        f: impl FnMut(&ExternalUriRequest) -> LaunchDecision + 'static,
+    ) -> Result<Subscription> {
+        let f = Rc::new(RefCell::new(f));
+        let handler = move |w: &WebView,
+                            args: Option<ICoreWebView2NavigationStartingEventArgs>|
+              -> windows::core::Result<()> {
+            let args = match args {
+                Some(args) => args,
+                None => return Ok(()),
+            };
+            let mut uri = PWSTR::default();
+            let mut is_user_initiated = BOOL::default();
+            unsafe {
+                args.Uri(&mut uri)?;
+                args.IsUserInitiated(&mut is_user_initiated)?;
+            }
+            let uri = take_pwstr(uri);
+            let scheme = match external_scheme(&uri) {
+                Some(scheme) => scheme,
+                None => return Ok(()),
+            };
+            let request = ExternalUriRequest {
+                uri,
+                scheme,
+                is_user_initiated: is_user_initiated.as_bool(),
+            };
+            match (f.borrow_mut())(&request) {
+                LaunchDecision::Ask => {}
+                decision => {
+                    unsafe { args.SetCancel(true)? };
+                    if decision == LaunchDecision::Launch {
+                        launch(w, &request.uri);
+                    }
+                }
+            }
+            Ok(())
+        };
+        let handler = Rc::new(handler);
+
+        let mut token = EventRegistrationToken::default();
+        let mut frame_token = EventRegistrationToken::default();
+        unsafe {
+            let w = self.clone();
+            let h = handler.clone();
+            self.core.NavigationStarting(
+                NavigationStartingEventHandler::create(Box::new(move |_core, args| h(&w, args))),
+                &mut token,
+            )?;
+            let w = self.clone();
+            self.core.FrameNavigationStarting(
+                NavigationStartingEventHandler::create(Box::new(move |_core, args| {
+                    handler(&w, args)
+                })),
+                &mut frame_token,
+            )?;
+        }
+        let core = self.core.clone();
+        Ok(self.track(move || unsafe {
+            core.RemoveNavigationStarting(token)?;
+            core.RemoveFrameNavigationStarting(frame_token)
+        }))
+    }
+}
@@ -0,0 +1,37 @@
+// Called with the name of the binding that receives the icon as PNG bytes.
+(function (binding) {
+    let last = null;
+
+    const iconUrl = () => {
+        const links = document.querySelectorAll('link[rel~="icon"]');
+        if (links.length) return links[links.length - 1].href;
+        return location.protocol.startsWith('http') ? new URL('/favicon.ico', location.href).href : null;
+    };
+
+    const update = async () => {
+        const url = iconUrl();
+        if (!url || url === last) return;
+        last = url;
+        try {
+            const bitmap = await createImageBitmap(await (await fetch(url)).blob());
+            const canvas = new OffscreenCanvas(bitmap.width, bitmap.height);
+            canvas.getContext('2d').drawImage(bitmap, 0, 0);
+            const png = await canvas.convertToBlob({ type: 'image/png' });
+            window[binding](Array.from(new Uint8Array(await png.arrayBuffer())));
+        } catch (e) {
+            // Cross-origin icons without CORS can't be read; keep the previous one.
+        }
+    };
+
+    const start = () => {
+        update();
+        new MutationObserver(update).observe(document.head, {
+            childList: true,
+            subtree: true,
+            attributes: true,
+            attributeFilter: ['href', 'rel'],
+        });
+    };
+    if (document.readyState === 'loading') document.addEventListener('DOMContentLoaded', start);
+    else start();
+})
@@ -0,0 +1,70 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use serde_json::Value;
+use windows::Win32::{
+    Foundation::{LPARAM, WPARAM},
+    UI::WindowsAndMessaging::*,
+};
+
+use crate::{event::Subscription, Result, WebView};
+
+static NEXT_BINDING: AtomicUsize = AtomicUsize::new(0);
+
+impl WebView {
+    // Calls `f` with the page's favicon as PNG bytes whenever it changes. The page reads it
+    // with fetch(), so cross-origin icons need CORS.
+    pub fn on_favicon(&self, mut f: impl FnMut(&[u8]) + 'static) -> Result<Subscription> {
+        let name = format!(
+            "_taco_favicon{}",
+            NEXT_BINDING.fetch_add(1, Ordering::Relaxed)
+        );
+        self.bind_unsafe(&name, move |params| {
+            if let [png] = &params[..] {
+                let png = serde_json::from_value::<Vec<u8>>(png.clone())
+                    .map_err(|err| err.to_string())?;
+                f(&png);
+                return Ok(Value::Null);
+            }
+            Err("Usage: _taco_favicon(png)".into())
+        });
+        let binding = self.track_binding(&name);
+        let script = self.track_init(&format!(
+            "{}({});",
+            include_str!("favicon.js"),
+            Value::String(name)
+        ))?;
+        Ok(self.merge(&[binding, script]))
+    }
+
+    // Uses the favicon as the window and taskbar icon, like `sync_title` does for the title.
+    pub fn sync_favicon(&self) -> Result<Subscription> {
+        let hwnd = self.hwnd;
+        let icons = Cell::new([HICON(0); 2]);
+        self.on_favicon(move |png| unsafe {
+            let new = [(ICON_SMALL, SM_CXSMICON), (ICON_BIG, SM_CXICON)].map(|(which, metric)| {
+                let size = GetSystemMetrics(metric);
+                let icon = CreateIconFromResourceEx(
+                    png.as_ptr(),
+                    png.len() as u32,
+                    true,
+                    0x00030000,
+                    size,
+                    size,
+                    LR_DEFAULTCOLOR,
+                );
+                if icon.0 != 0 {
+                    SendMessageW(hwnd, WM_SETICON, WPARAM(which as usize), LPARAM(icon.0));
+                }
+                icon
+            });
+            for icon in icons.replace(new) {
+                if icon.0 != 0 {
+                    DestroyIcon(icon);
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,150 @@
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+use windows::Win32::{
+    Foundation::{BOOL, POINT, PWSTR},
+    Graphics::Gdi::ScreenToClient,
+    System::WinRT::EventRegistrationToken,
+    UI::WindowsAndMessaging::{GetCursorPos, KillTimer, SetTimer, WM_DESTROY, WM_TIMER},
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{event::Subscription, navigation::Decision, window::Window, Result, WebView};
+
+const DROP_TIMER_ID: usize = 4;
+// The files of one drop arrive as separate navigations, close together.
+const DROP_SETTLE_MS: u32 = 50;
+
+fn decode_percent(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// `file:///C:/a%20b.txt` -> `C:\a b.txt`, `file://server/share/c.txt` -> `\\server\share\c.txt`
+fn file_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let path = match rest.strip_prefix('/') {
+        Some(local) => decode_percent(local),
+        None => format!("//{}", decode_percent(rest)),
+    };
+    Some(PathBuf::from(path.replace('/', "\\")))
+}
+
+// Files dropped on a page the page doesn't handle itself are opened by navigating to them,
+// the first one in place and the rest in new windows. Pages from elsewhere can't navigate to
+// `file://`, so those navigations are taken for drops and cancelled, handing `f` the paths,
+// unless the host started them with `navigate` and the like.
+fn intercept(webview: &WebView, f: impl FnMut(PathBuf) + 'static) -> Result<Subscription> {
+    let f = Rc::new(RefCell::new(f));
+
+    let w = webview.clone();
+    let g = f.clone();
+    let starting = webview.on_navigation_starting(move |args| {
+        let from_file = w.url().is_ok_and(|url| url.starts_with("file:"));
+        let dropped = args.is_user_initiated && !from_file && !w.host_navigating.get();
+        match file_path(&args.uri) {
+            Some(path) if dropped => {
+                (g.borrow_mut())(path);
+                Decision::Cancel
+            }
+            _ => Decision::Allow,
+        }
+    })?;
+
+    let mut token = EventRegistrationToken::default();
+    unsafe {
+        webview.core.NewWindowRequested(
+            NewWindowRequestedEventHandler::create(Box::new(
+                move |_core, args: Option<ICoreWebView2NewWindowRequestedEventArgs>| {
+                    if let Some(args) = args {
+                        let mut uri = PWSTR::default();
+                        args.Uri(&mut uri)?;
+                        if let Some(path) = file_path(&take_pwstr(uri)) {
+                            (f.borrow_mut())(path);
+                            args.SetHandled(BOOL::from(true))?;
+                        }
+                    }
+                    Ok(())
+                },
+            )),
+            &mut token,
+        )?;
+    }
+    let core = webview.core.clone();
+    let new_window = webview.track(move || unsafe { core.RemoveNewWindowRequested(token) });
+    Ok(webview.merge(&[starting, new_window]))
+}
+
+// Chromium's windows register their own OLE drop target, so the page's drop events are where
+// content from other apps arrives; `drop.js` turns them into `taco.on('drop', ...)` events.
+pub(crate) fn inject(webview: &WebView) -> Result<()> {
+    webview.init(include_str!("drop.js"))?;
+    Ok(())
+}
+
+impl WebView {
+    // Keeps files dropped on the window from replacing the page, without handling them.
+    // Drops the page handles in JS are unaffected.
+    pub fn block_drop_navigation(&self) -> Result<Subscription> {
+        intercept(self, |_| {})
+    }
+}
+
+impl<T: 'static> Window<T> {
+    // Calls `f` with the files dropped on the window, and where they were dropped in client
+    // coordinates, instead of navigating to them. As with `block_drop_navigation`, the page
+    // gets to handle drops first.
+    pub fn on_file_drop(
+        &mut self,
+        mut f: impl FnMut(Vec<PathBuf>, (i32, i32)) + 'static,
+    ) -> Result<Subscription> {
+        let hwnd = self.webview().hwnd;
+        let dropped = Rc::new(RefCell::new((Vec::new(), POINT::default())));
+
+        let d = dropped.clone();
+        let subscription = intercept(self.webview(), move |path| {
+            let mut dropped = d.borrow_mut();
+            if dropped.0.is_empty() {
+                unsafe {
+                    GetCursorPos(&mut dropped.1);
+                    ScreenToClient(hwnd, &mut dropped.1);
+                    SetTimer(hwnd, DROP_TIMER_ID, DROP_SETTLE_MS, None);
+                }
+            }
+            dropped.0.push(path);
+        })?;
+
+        self.add_event_listener(WM_TIMER, move |wparam, _, _| {
+            if wparam.0 != DROP_TIMER_ID {
+                return;
+            }
+            unsafe { KillTimer(hwnd, DROP_TIMER_ID) };
+            let (paths, point) = std::mem::take(&mut *dropped.borrow_mut());
+            if !paths.is_empty() {
+                f(paths, (point.x, point.y));
+            }
+        });
+        self.add_event_listener(WM_DESTROY, move |_, _, _| unsafe {
+            KillTimer(hwnd, DROP_TIMER_ID);
+        });
+        Ok(subscription)
+    }
+}
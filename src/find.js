@@ -0,0 +1,111 @@
+(function () {
+    const taco = window.taco = window.taco || {};
+
+    // Matches are looked for in the text of the top document, across element boundaries, and
+    // shown with CSS highlights so the page's DOM stays as it was.
+    const style = document.createElement('style');
+    style.textContent = '::highlight(taco-find) { background: #ffeb3b; color: #000; } ' +
+        '::highlight(taco-find-active) { background: #ff9632; color: #000; }';
+    const SKIPPED = new Set(['SCRIPT', 'STYLE', 'NOSCRIPT', 'TEMPLATE', 'TEXTAREA', 'INPUT']);
+
+    let ranges = [];
+    let active = -1;
+
+    const textNodes = () => {
+        const nodes = [];
+        const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT, {
+            acceptNode: node => SKIPPED.has(node.parentElement && node.parentElement.tagName)
+                ? NodeFilter.FILTER_REJECT : NodeFilter.FILTER_ACCEPT,
+        });
+        while (walker.nextNode()) {
+            nodes.push(walker.currentNode);
+        }
+        return nodes;
+    };
+
+    const isWordChar = c => c !== undefined && /[\p{L}\p{N}_]/u.test(c);
+
+    const search = (text, options) => {
+        const nodes = textNodes();
+        const starts = [];
+        let full = '';
+        for (const node of nodes) {
+            starts.push(full.length);
+            full += node.data;
+        }
+        const haystack = options.caseSensitive ? full : full.toLowerCase();
+        const needle = options.caseSensitive ? text : text.toLowerCase();
+        // The node containing offset `i` of the concatenated text.
+        const locate = i => {
+            let lo = 0, hi = starts.length - 1;
+            while (lo < hi) {
+                const mid = (lo + hi + 1) >> 1;
+                if (starts[mid] <= i) lo = mid; else hi = mid - 1;
+            }
+            return [nodes[lo], i - starts[lo]];
+        };
+        const found = [];
+        for (let i = haystack.indexOf(needle); needle && i !== -1; i = haystack.indexOf(needle, i + needle.length)) {
+            if (options.wholeWord && (isWordChar(full[i - 1]) || isWordChar(full[i + needle.length]))) {
+                continue;
+            }
+            const range = document.createRange();
+            range.setStart(...locate(i));
+            range.setEnd(...locate(i + needle.length - 1));
+            range.setEnd(range.endContainer, range.endOffset + 1);
+            found.push(range);
+        }
+        return found;
+    };
+
+    const show = () => {
+        if (window.CSS && CSS.highlights) {
+            if (!style.isConnected) {
+                document.head.appendChild(style);
+            }
+            const rest = ranges.filter((_, i) => i !== active);
+            CSS.highlights.set('taco-find', new Highlight(...rest));
+            if (active >= 0) {
+                CSS.highlights.set('taco-find-active', new Highlight(ranges[active]));
+            } else {
+                CSS.highlights.delete('taco-find-active');
+            }
+        } else if (active >= 0) {
+            // Older runtimes without highlights get the active match selected instead.
+            const selection = getSelection();
+            selection.removeAllRanges();
+            selection.addRange(ranges[active]);
+        }
+        if (active >= 0) {
+            const element = ranges[active].startContainer.parentElement;
+            if (element) {
+                element.scrollIntoView({ block: 'center', inline: 'nearest' });
+            }
+        }
+        window._taco_findResult(ranges.length, active);
+    };
+
+    taco._find = {
+        start: (text, options) => {
+            ranges = search(text, options);
+            active = ranges.length ? 0 : -1;
+            show();
+        },
+        step: delta => {
+            if (!ranges.length) {
+                return;
+            }
+            active = (active + delta + ranges.length) % ranges.length;
+            show();
+        },
+        stop: () => {
+            ranges = [];
+            active = -1;
+            if (window.CSS && CSS.highlights) {
+                CSS.highlights.delete('taco-find');
+                CSS.highlights.delete('taco-find-active');
+            }
+            style.remove();
+        },
+    };
+})();
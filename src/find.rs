@@ -0,0 +1,77 @@
+use std::{cell::RefCell, rc::Rc};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{Result, WebView};
+
+pub(crate) type FindCallback = Rc<RefCell<dyn FnMut(&FindResult)>>;
+
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindOptions {
+    pub case_sensitive: bool,
+    // Only matches that aren't part of a longer word.
+    pub whole_word: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FindResult {
+    pub matches: usize,
+    // Zero-based; None without matches.
+    pub active: Option<usize>,
+}
+
+// This SDK predates ICoreWebView2Find, so finding is done by `find.js` in the page: the top
+// document only, without its iframes.
+pub(crate) fn inject(webview: &WebView) -> Result<()> {
+    let w = webview.clone();
+    webview.bind_unsafe("_taco_findResult", move |params| {
+        if let [matches, active] = &params[..] {
+            let result = FindResult {
+                matches: matches.as_u64().unwrap_or_default() as usize,
+                active: active.as_u64().map(|active| active as usize),
+            };
+            // Cloned out, so that the callback can start another search.
+            let callback = w.find_results.borrow().clone();
+            if let Some(f) = callback {
+                (f.borrow_mut())(&result);
+            }
+            return Ok(Value::Null);
+        }
+        Err("Usage: _taco_findResult(matches, active)".into())
+    });
+    webview.init(include_str!("find.js"))?;
+    Ok(())
+}
+
+impl WebView {
+    // Highlights the matches of `text` and scrolls to the first one. An empty `text` clears
+    // them, like `stop_find`.
+    pub fn find(&self, text: &str, options: FindOptions) -> Result<&Self> {
+        let js = format!(
+            "taco._find.start({}, {})",
+            serde_json::to_string(text)?,
+            serde_json::to_string(&options)?
+        );
+        self.eval(&js)
+    }
+
+    // Moves to the next match, wrapping around at the end.
+    pub fn find_next(&self) -> Result<&Self> {
+        self.eval("taco._find.step(1)")
+    }
+
+    pub fn find_previous(&self) -> Result<&Self> {
+        self.eval("taco._find.step(-1)")
+    }
+
+    pub fn stop_find(&self) -> Result<&Self> {
+        self.eval("taco._find.stop()")
+    }
+
+    // Called after every `find`, `find_next` and `find_previous`, e.g. for a "3 of 12" label.
+    pub fn on_find_result(&self, f: impl FnMut(&FindResult) + 'static) {
+        *self.find_results.borrow_mut() = Some(Rc::new(RefCell::new(f)));
+    }
+}
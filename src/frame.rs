@@ -0,0 +1,250 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::{BOOL, PWSTR},
+        System::WinRT::EventRegistrationToken,
+    },
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{
+    binding_shim, com, event::Subscription, resolve_script, BindingsMap, InvokeMessage, Result,
+    WebView,
+};
+
+// An iframe of the page. Init scripts and bindings registered on the WebView already run in
+// iframes, but their calls only reach Rust through a Frame's own `bind_unsafe`.
+#[derive(Clone)]
+pub struct Frame {
+    pub frame: ICoreWebView2Frame2,
+    bindings: Rc<RefCell<BindingsMap>>,
+    // Re-run whenever the iframe starts loading a new document, without waiting for them, so
+    // unlike the WebView's init scripts they may run after the page's own scripts.
+    scripts: Rc<RefCell<Vec<String>>>,
+    // Whose subscriptions the frame's handlers are.
+    webview: WebView,
+    // Last, so the COM objects above are released before the apartment is.
+    _apartment: Rc<com::Apartment>,
+}
+
+impl Frame {
+    fn new(frame: ICoreWebView2Frame2, webview: WebView) -> Result<Self> {
+        let frame = Self {
+            frame,
+            bindings: Rc::new(RefCell::new(HashMap::new())),
+            scripts: Rc::new(RefCell::new(Vec::new())),
+            _apartment: webview._apartment.clone(),
+            webview,
+        };
+
+        let mut message_token = EventRegistrationToken::default();
+        let mut loading_token = EventRegistrationToken::default();
+        let mut destroyed_token = EventRegistrationToken::default();
+        // Removed again once the iframe is gone, rather than with the window.
+        let internal = Rc::new(Cell::new(None));
+        unsafe {
+            let bindings = frame.bindings.clone();
+            frame.frame.WebMessageReceived(
+                FrameWebMessageReceivedEventHandler::create(Box::new(
+                    move |sender, args: Option<ICoreWebView2WebMessageReceivedEventArgs>| {
+                        let (sender, args) = match (sender, args) {
+                            (Some(sender), Some(args)) => (sender, args),
+                            _ => return Ok(()),
+                        };
+                        let mut message = PWSTR::default();
+                        if args.WebMessageAsJson(&mut message).is_err() {
+                            return Ok(());
+                        }
+                        let value =
+                            match serde_json::from_str::<InvokeMessage>(&take_pwstr(message)) {
+                                Ok(value) => value,
+                                Err(_) => return Ok(()),
+                            };
+                        // Cloned out, so that the binding can bind more.
+                        let binding = bindings.borrow().get(&value.method).cloned();
+                        let result = match binding.as_ref().map(|f| f.try_borrow_mut()) {
+                            Some(Ok(mut f)) => f(value.params),
+                            Some(Err(_)) => Err(format!("{} is still running", value.method)),
+                            None => return Ok(()),
+                        };
+                        let js = match result {
+                            Ok(result) => resolve_script(value.id, 0, result),
+                            Err(err) => resolve_script(value.id, 1, Value::String(err)),
+                        };
+                        // Don't wait: this runs inside WebView2's event dispatch.
+                        sender.cast::<ICoreWebView2Frame2>()?.ExecuteScript(
+                            js,
+                            ExecuteScriptCompletedHandler::create(Box::new(|_, _| Ok(()))),
+                        )
+                    },
+                )),
+                &mut message_token,
+            )?;
+
+            let scripts = frame.scripts.clone();
+            frame.frame.ContentLoading(
+                FrameContentLoadingEventHandler::create(Box::new(move |sender, _args| {
+                    let sender = match sender {
+                        Some(sender) => sender.cast::<ICoreWebView2Frame2>()?,
+                        None => return Ok(()),
+                    };
+                    for js in scripts.borrow().iter() {
+                        sender.ExecuteScript(
+                            js.as_str(),
+                            ExecuteScriptCompletedHandler::create(Box::new(|_, _| Ok(()))),
+                        )?;
+                    }
+                    Ok(())
+                })),
+                &mut loading_token,
+            )?;
+
+            let (w, i) = (frame.webview.clone(), internal.clone());
+            frame.frame.Destroyed(
+                FrameDestroyedEventHandler::create(Box::new(move |_sender, _args| {
+                    if let Some(subscription) = i.take() {
+                        w.unsubscribe(subscription).ok();
+                    }
+                    Ok(())
+                })),
+                &mut destroyed_token,
+            )?;
+        }
+
+        let (f, w) = (frame.frame.clone(), &frame.webview);
+        let message = w.track(move || unsafe { f.RemoveWebMessageReceived(message_token) });
+        let f = frame.frame.clone();
+        let loading = w.track(move || unsafe { f.RemoveContentLoading(loading_token) });
+        let f = frame.frame.clone();
+        let destroyed = w.track(move || unsafe { f.RemoveDestroyed(destroyed_token) });
+        internal.set(Some(w.merge(&[message, loading, destroyed])));
+        Ok(frame)
+    }
+
+    // The iframe's `name` attribute.
+    pub fn name(&self) -> Result<String> {
+        let mut name = PWSTR::default();
+        unsafe { self.frame.Name(&mut name)? };
+        Ok(take_pwstr(name))
+    }
+
+    pub fn is_destroyed(&self) -> Result<bool> {
+        let mut destroyed = BOOL::default();
+        unsafe { self.frame.IsDestroyed(&mut destroyed)? };
+        Ok(destroyed.as_bool())
+    }
+
+    // Called when the iframe is removed from the page or the page navigates away.
+    pub fn on_destroyed(&self, mut f: impl FnMut() + 'static) -> Result<Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.frame.Destroyed(
+                FrameDestroyedEventHandler::create(Box::new(move |_sender, _args| {
+                    f();
+                    Ok(())
+                })),
+                &mut token,
+            )?;
+        }
+        let frame = self.frame.clone();
+        Ok(self
+            .webview
+            .track(move || unsafe { frame.RemoveDestroyed(token) }))
+    }
+
+    // Runs `js` in the current document of the iframe and again in every document it loads.
+    // These aren't before-page scripts: they race the document's own, so check for what they
+    // define rather than relying on it at load. Doesn't wait for the script, so it can be
+    // called from `on_frame_created`.
+    pub fn init(&self, js: &str) -> Result<&Self> {
+        self.scripts.borrow_mut().push(js.to_string());
+        unsafe {
+            self.frame.ExecuteScript(
+                js,
+                ExecuteScriptCompletedHandler::create(Box::new(|_, _| Ok(()))),
+            )?;
+        }
+        Ok(self)
+    }
+
+    // Waits for the script to have run, pumping messages, so it can't be called from inside
+    // WebView2's event handlers such as `on_frame_created`'s; use `init` there.
+    pub fn eval(&self, js: &str) -> Result<&Self> {
+        let frame = self.frame.clone();
+        let js = String::from(js);
+        ExecuteScriptCompletedHandler::wait_for_async_operation(
+            Box::new(move |handler| unsafe {
+                frame
+                    .ExecuteScript(js, handler)
+                    .map_err(webview2_com::Error::WindowsError)
+            }),
+            Box::new(|error_code, _result| error_code),
+        )?;
+        Ok(self)
+    }
+
+    // Like `WebView::emit`, but only the iframe's `taco.on` callbacks see it.
+    pub fn emit(&self, event: &str, payload: impl Serialize) -> Result<&Self> {
+        let message = serde_json::json!({
+            "_tacoEvent": event,
+            "payload": payload,
+        });
+        unsafe { self.frame.PostWebMessageAsJson(message.to_string())? };
+        Ok(self)
+    }
+
+    // Defines `window[name]` in the iframe. Unlike the WebView's bindings, `f` runs as soon as
+    // the message arrives rather than on the next turn of the message loop. Like `init`, it
+    // can be called from `on_frame_created`.
+    pub fn bind_unsafe<F>(&self, name: &str, f: F) -> Result<()>
+    where
+        F: FnMut(Vec<Value>) -> std::result::Result<Value, String> + 'static,
+    {
+        let rebound = self
+            .bindings
+            .borrow_mut()
+            .insert(String::from(name), Rc::new(RefCell::new(f)))
+            .is_some();
+        // The shim looks the callback up by name, so it's only needed once.
+        if !rebound {
+            self.init(&binding_shim(name))?;
+        }
+        Ok(())
+    }
+}
+
+impl WebView {
+    // Called for each iframe of the top-level document; nested iframes aren't reported.
+    pub fn on_frame_created(&self, mut f: impl FnMut(Frame) + 'static) -> Result<Subscription> {
+        let w = self.clone();
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core.cast::<ICoreWebView2_4>()?.FrameCreated(
+                FrameCreatedEventHandler::create(Box::new(
+                    move |_core, args: Option<ICoreWebView2FrameCreatedEventArgs>| {
+                        let frame = match args {
+                            Some(args) => args.Frame()?.cast::<ICoreWebView2Frame2>()?,
+                            None => return Ok(()),
+                        };
+                        if let Ok(frame) = Frame::new(frame, w.clone()) {
+                            f(frame);
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut token,
+            )?;
+        }
+        let core: ICoreWebView2_4 = self.core.cast()?;
+        Ok(self.track(move || unsafe { core.RemoveFrameCreated(token) }))
+    }
+}
@@ -0,0 +1,118 @@
+use windows::Win32::{
+    Foundation::{BOOL, HWND, RECT},
+    Graphics::Gdi::{GetMonitorInfoA, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST},
+    System::WinRT::EventRegistrationToken,
+    UI::WindowsAndMessaging::*,
+};
+
+use webview2_com::*;
+
+use crate::{event::Subscription, GetWindowLong, Result, SetWindowLong, WebView};
+
+// What the window looked like before going fullscreen.
+#[derive(Clone, Copy)]
+pub(crate) struct Restore {
+    style: isize,
+    placement: WINDOWPLACEMENT,
+    bounds: RECT,
+}
+
+impl WebView {
+    pub fn is_fullscreen(&self) -> bool {
+        self.fullscreen.get().is_some()
+    }
+
+    // Borderless fullscreen on the window's monitor, like a browser's F11. The WebView fills the
+    // window in the meantime, and gets its previous bounds back afterwards.
+    pub fn set_fullscreen(&self, fullscreen: bool) -> Result<&Self> {
+        let hwnd = self.hwnd;
+        match (fullscreen, self.fullscreen.get()) {
+            (true, None) => unsafe {
+                let mut placement = WINDOWPLACEMENT {
+                    length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+                    ..Default::default()
+                };
+                GetWindowPlacement(hwnd, &mut placement);
+                let bounds = self.bounds()?;
+                let style = GetWindowLong(hwnd, GWL_STYLE);
+                self.fullscreen.set(Some(Restore {
+                    style,
+                    placement,
+                    bounds,
+                }));
+
+                let mut monitor = MONITORINFO {
+                    cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+                    ..Default::default()
+                };
+                GetMonitorInfoA(
+                    MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST),
+                    &mut monitor,
+                );
+                let area = monitor.rcMonitor;
+                SetWindowLong(
+                    hwnd,
+                    GWL_STYLE,
+                    style & !((WS_CAPTION | WS_THICKFRAME) as isize),
+                );
+                SetWindowPos(
+                    hwnd,
+                    HWND_TOP,
+                    area.left,
+                    area.top,
+                    area.right - area.left,
+                    area.bottom - area.top,
+                    SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
+                );
+                // The whole screen, without the insets of any native chrome.
+                self.set_bounds(RECT {
+                    left: 0,
+                    top: 0,
+                    right: area.right - area.left,
+                    bottom: area.bottom - area.top,
+                })?;
+            },
+            (false, Some(restore)) => unsafe {
+                self.fullscreen.set(None);
+                SetWindowLong(hwnd, GWL_STYLE, restore.style);
+                SetWindowPlacement(hwnd, &restore.placement);
+                SetWindowPos(
+                    hwnd,
+                    HWND::default(),
+                    0,
+                    0,
+                    0,
+                    0,
+                    SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
+                );
+                self.set_bounds(restore.bounds)?;
+            },
+            _ => {}
+        }
+        Ok(self)
+    }
+
+    // Follows `element.requestFullscreen()` and `document.exitFullscreen()` in the page, so a
+    // `<video>`'s fullscreen button covers the screen rather than just the window.
+    pub fn fullscreen_on_element(&self) -> Result<Subscription> {
+        let w = self.clone();
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core.ContainsFullScreenElementChanged(
+                ContainsFullScreenElementChangedEventHandler::create(Box::new(
+                    move |core, _args| {
+                        if let Some(core) = core {
+                            let mut fullscreen = BOOL::default();
+                            core.ContainsFullScreenElement(&mut fullscreen)?;
+                            w.set_fullscreen(fullscreen.as_bool()).ok();
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut token,
+            )?;
+        }
+        let core = self.core.clone();
+        Ok(self.track(move || unsafe { core.RemoveContainsFullScreenElementChanged(token) }))
+    }
+}
@@ -0,0 +1,80 @@
+use std::str::FromStr;
+
+use windows::Win32::{Foundation::HWND, UI::Input::KeyboardAndMouse::*};
+
+use crate::{Error, Result};
+
+// A system-wide hotkey such as "Ctrl+Shift+K" or "Alt+F4".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hotkey {
+    pub modifiers: HOT_KEY_MODIFIERS,
+    pub vk: u32,
+}
+
+fn virtual_key(key: &str) -> Option<u32> {
+    let upper = key.to_ascii_uppercase();
+    let vk = match upper.as_str() {
+        "SPACE" => VK_SPACE,
+        "ENTER" | "RETURN" => VK_RETURN,
+        "ESC" | "ESCAPE" => VK_ESCAPE,
+        "TAB" => VK_TAB,
+        "BACKSPACE" => VK_BACK,
+        "DELETE" | "DEL" => VK_DELETE,
+        "INSERT" | "INS" => VK_INSERT,
+        "HOME" => VK_HOME,
+        "END" => VK_END,
+        "PAGEUP" => VK_PRIOR,
+        "PAGEDOWN" => VK_NEXT,
+        "UP" => VK_UP,
+        "DOWN" => VK_DOWN,
+        "LEFT" => VK_LEFT,
+        "RIGHT" => VK_RIGHT,
+        _ => {
+            let mut chars = upper.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_alphanumeric() => Some(c as u32),
+                (Some('F'), Some(_)) => match upper[1..].parse::<u32>() {
+                    Ok(n @ 1..=24) => Some(VK_F1 as u32 + n - 1),
+                    _ => None,
+                },
+                _ => None,
+            };
+        }
+    };
+    Some(vk as u32)
+}
+
+impl FromStr for Hotkey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = HOT_KEY_MODIFIERS::default();
+        let mut vk = None;
+        for part in s.split('+').map(str::trim) {
+            match part.to_ascii_uppercase().as_str() {
+                "CTRL" | "CONTROL" => modifiers |= MOD_CONTROL,
+                "ALT" => modifiers |= MOD_ALT,
+                "SHIFT" => modifiers |= MOD_SHIFT,
+                "WIN" | "SUPER" => modifiers |= MOD_WIN,
+                _ if vk.is_none() => {
+                    vk = Some(virtual_key(part).ok_or_else(|| Error::InvalidHotkey(s.into()))?)
+                }
+                _ => return Err(Error::InvalidHotkey(s.into())),
+            }
+        }
+        let vk = vk.ok_or_else(|| Error::InvalidHotkey(s.into()))?;
+        Ok(Self { modifiers, vk })
+    }
+}
+
+// WM_HOTKEY is posted to `hwnd` with `id` as its WPARAM.
+pub fn register(hwnd: HWND, id: i32, hotkey: &Hotkey) -> Result<()> {
+    if !unsafe { RegisterHotKey(hwnd, id, hotkey.modifiers | MOD_NOREPEAT, hotkey.vk) }.as_bool() {
+        return Err(windows::core::Error::from_win32().into());
+    }
+    Ok(())
+}
+
+pub fn unregister(hwnd: HWND, id: i32) {
+    unsafe { UnregisterHotKey(hwnd, id) };
+}
@@ -0,0 +1,116 @@
+use serde_json::{json, Value};
+
+use crate::{Result, WebView};
+
+// Input is dispatched through the DevTools protocol, so it reaches the page
+// without moving the real cursor or stealing the keyboard focus (unlike SendInput).
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseButton {
+    None,
+    Left,
+    Middle,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MouseEventKind {
+    Pressed,
+    Released,
+    Moved,
+    Wheel { delta_x: f64, delta_y: f64 },
+}
+
+// `x` and `y` are in CSS pixels relative to the top-left of the viewport.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouseEvent {
+    pub kind: MouseEventKind,
+    pub x: f64,
+    pub y: f64,
+    pub button: MouseButton,
+    pub click_count: u32,
+}
+
+impl MouseButton {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MouseButton::None => "none",
+            MouseButton::Left => "left",
+            MouseButton::Middle => "middle",
+            MouseButton::Right => "right",
+        }
+    }
+}
+
+impl MouseEvent {
+    fn to_params(self) -> Value {
+        let mut params = json!({
+            "x": self.x,
+            "y": self.y,
+            "button": self.button.as_str(),
+            "clickCount": self.click_count,
+        });
+        let kind = match self.kind {
+            MouseEventKind::Pressed => "mousePressed",
+            MouseEventKind::Released => "mouseReleased",
+            MouseEventKind::Moved => "mouseMoved",
+            MouseEventKind::Wheel { delta_x, delta_y } => {
+                params["deltaX"] = json!(delta_x);
+                params["deltaY"] = json!(delta_y);
+                "mouseWheel"
+            }
+        };
+        params["type"] = json!(kind);
+        params
+    }
+}
+
+fn key_params(c: char) -> (Value, Value) {
+    match c {
+        '\n' | '\r' => (
+            json!({ "type": "keyDown", "key": "Enter", "code": "Enter", "windowsVirtualKeyCode": 13, "text": "\r" }),
+            json!({ "type": "keyUp", "key": "Enter", "code": "Enter", "windowsVirtualKeyCode": 13 }),
+        ),
+        '\t' => (
+            json!({ "type": "keyDown", "key": "Tab", "code": "Tab", "windowsVirtualKeyCode": 9 }),
+            json!({ "type": "keyUp", "key": "Tab", "code": "Tab", "windowsVirtualKeyCode": 9 }),
+        ),
+        c => {
+            let text = c.to_string();
+            (
+                json!({ "type": "keyDown", "key": text, "text": text, "unmodifiedText": text }),
+                json!({ "type": "keyUp", "key": text }),
+            )
+        }
+    }
+}
+
+impl WebView {
+    pub fn send_mouse(&self, event: MouseEvent) -> Result<&Self> {
+        self.call_devtools_method("Input.dispatchMouseEvent", event.to_params())?;
+        Ok(self)
+    }
+
+    pub fn click(&self, x: f64, y: f64) -> Result<&Self> {
+        let mut event = MouseEvent {
+            kind: MouseEventKind::Pressed,
+            x,
+            y,
+            button: MouseButton::Left,
+            click_count: 1,
+        };
+        self.send_mouse(event)?;
+        event.kind = MouseEventKind::Released;
+        self.send_mouse(event)
+    }
+
+    // Types `text` into the focused element, one key press per character.
+    pub fn send_keys(&self, text: &str) -> Result<&Self> {
+        for c in text.chars() {
+            let (down, up) = key_params(c);
+            self.call_devtools_method("Input.dispatchKeyEvent", down)?;
+            self.call_devtools_method("Input.dispatchKeyEvent", up)?;
+        }
+        Ok(self)
+    }
+}
@@ -0,0 +1,30 @@
+use serde_json::{json, Map, Value};
+
+use crate::{Result, WebView};
+
+// Makes `taco.launch.args` (the command line after the executable) and `taco.launch.env` (the
+// allow-listed variables that are set) available to pages before their scripts run, e.g. so
+// the frontend can honor `--debug` without a binding of its own.
+pub(crate) fn inject(webview: &WebView, args: bool, env: &[&str]) -> Result<()> {
+    let args = match args {
+        // Not `args`, which panics on arguments that aren't valid Unicode.
+        true => std::env::args_os()
+            .skip(1)
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect(),
+        false => Vec::new(),
+    };
+    let env = env
+        .iter()
+        .filter_map(|name| {
+            let value = std::env::var(name).ok()?;
+            Some((name.to_string(), Value::String(value)))
+        })
+        .collect::<Map<_, _>>();
+    let launch = json!({ "args": args, "env": env });
+    webview.init(&format!(
+        "(window.taco = window.taco || {{}}).launch = Object.freeze({});",
+        launch
+    ))?;
+    Ok(())
+}
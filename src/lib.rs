@@ -3,11 +3,83 @@ pub extern crate serde_json;
 pub extern crate webview2_com;
 pub extern crate windows;
 
+pub mod accelerator;
+pub mod app;
+pub mod assets;
+pub mod audio;
+pub mod auth;
+pub mod capture;
+pub mod certificate;
+pub mod child;
+pub mod cli;
+pub mod com;
+pub mod command;
+pub mod composition;
+pub mod cursor;
+pub mod devtools;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+pub mod dialog;
+pub mod download;
+pub mod drag;
+pub mod environment;
+pub mod error_page;
+pub mod event;
+pub mod external_uri;
+pub mod favicon;
+pub mod file_drop;
+pub mod find;
+pub mod frame;
+pub mod fullscreen;
+pub mod hotkey;
+pub mod input;
+pub mod launch;
+pub mod menu;
+pub mod metrics;
+pub mod mouse_tracking;
+pub mod navigation;
+pub mod network;
+pub mod notification;
+pub mod paint;
+pub mod permission;
+pub mod pool;
+pub mod print;
+pub mod process;
+pub mod progress;
+pub mod protocol;
+pub mod recovery;
+pub mod resource;
+pub mod retry;
+pub mod safe_mode;
+pub mod scrollbar;
+pub mod settings;
+pub mod shared_buffer;
+pub mod shutdown;
+pub mod state;
+pub mod suspend;
+pub mod sync;
+pub mod taskbar;
+pub mod text_input;
+pub mod theme;
+pub mod thread;
+pub mod touch_keyboard;
+pub mod tray;
+pub mod watchdog;
+pub mod webauthn;
 pub mod window;
 
-use std::{cell::RefCell, collections::HashMap, fmt, ptr, rc::Rc, sync::mpsc};
+pub use environment::Environment;
 
-use serde::Deserialize;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    rc::Rc,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use windows::{
     core::*,
@@ -26,6 +98,25 @@ pub enum Error {
     WebView2Error(webview2_com::Error),
     WindowsError(windows::core::Error),
     JsonError(serde_json::Error),
+    IoError(std::io::Error),
+    InvalidHotkey(String),
+    // The thread is initialized as a multithreaded COM apartment, but WebView2 needs an STA.
+    WrongApartment,
+    // No window was added to the App with this label.
+    UnknownWindow(String),
+    // Chromium's child windows didn't appear in time; see `child::ChildWindows::wait`.
+    ChildWindowNotFound,
+    // Neither the WebView2 Runtime nor an Edge channel is installed, or the fixed-version
+    // runtime folder doesn't have one; see `environment::install_runtime`.
+    RuntimeNotFound,
+    // See `WebViewBuilder::profile` for what names are allowed.
+    InvalidProfileName(String),
+    // See `WebView::set_background_color`.
+    InvalidBackgroundColor(String),
+    // `navigate` and friends gave up waiting; see `WebView::set_navigation_timeout`.
+    NavigationTimeout,
+    // Needs a WebView built with `WebViewBuilder::visual_hosting`.
+    NotVisuallyHosted,
     LockError,
 }
 
@@ -59,13 +150,19 @@ impl From<serde_json::Error> for Error {
     }
 }
 
-impl<'a, T: 'a> From<std::sync::PoisonError<T>> for Error {
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::IoError(err)
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for Error {
     fn from(_: std::sync::PoisonError<T>) -> Self {
         Self::LockError
     }
 }
 
-impl<'a, T: 'a> From<std::sync::TryLockError<T>> for Error {
+impl<T> From<std::sync::TryLockError<T>> for Error {
     fn from(_: std::sync::TryLockError<T>) -> Self {
         Self::LockError
     }
@@ -73,8 +170,10 @@ impl<'a, T: 'a> From<std::sync::TryLockError<T>> for Error {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-type BindingCallback = Box<dyn FnMut(Vec<Value>) -> std::result::Result<Value, String>>;
+// Shared, so that they're cloned out of the map before being called, and can bind and unbind.
+type BindingCallback = Rc<RefCell<dyn FnMut(Vec<Value>) -> std::result::Result<Value, String>>>;
 type BindingsMap = HashMap<String, BindingCallback>;
+type AsyncBindingCallback = Rc<RefCell<dyn FnMut(Vec<Value>, thread::Responder)>>;
 
 pub struct WebViewBuilder<'a> {
     pub style: WINDOW_STYLE,
@@ -85,11 +184,100 @@ pub struct WebViewBuilder<'a> {
     pub height: i32,
     pub title: &'a str,
     pub url: &'a str,
+    // Retries `url` while its server can't be reached, e.g. a dev server that's still starting.
+    pub retry_policy: Option<retry::RetryPolicy>,
+    // See `WebView::set_navigation_timeout`.
+    pub navigation_timeout: Option<Duration>,
+    // Shown instead of `url` when set.
+    pub html: Option<&'a str>,
+    // Dev tools and the default context menus. `settings` can override either.
     pub debug: bool,
     pub frameless: bool,
+    // Shows the cursor of `data-taco-cursor` elements, e.g. resize cursors on the edges of a
+    // frameless window; see cursor.js.
+    pub cursor_hints: bool,
     pub resizable: bool,
     pub transparent: bool,
     pub autosize: bool,
+    // Ctrl+wheel, Ctrl+plus/minus and touchpad zooming by the user.
+    pub zoom_control: bool,
+    pub pinch_zoom: bool,
+    // Keeps the window title in sync with `document.title`.
+    pub sync_title: bool,
+    // Uses the page's favicon as the window icon.
+    pub sync_favicon: bool,
+    // See `WebView::keep_awake_while_playing`.
+    pub keep_awake_while_playing: bool,
+    // See `WebView::enable_auto_recovery`.
+    pub auto_recovery: bool,
+    // Also restores what had been typed into the page's fields; off, as it keeps their values.
+    pub auto_recovery_form: bool,
+    // See `WebView::suspend_when_minimized`.
+    pub suspend_when_minimized: bool,
+    // See `WebView::fullscreen_on_element`.
+    pub fullscreen_on_element: bool,
+    // Dark or light native chrome: title bar, menus and tray menus. None leaves them as
+    // Windows draws them by default.
+    pub theme: Option<theme::Theme>,
+    // See `WindowRunner::set_animate_dpi_changes`.
+    pub animate_dpi_changes: bool,
+    // False keeps files dropped on the window from replacing the page; see
+    // `WebView::block_drop_navigation`. `Window::on_file_drop` does so either way.
+    pub drop_navigation: bool,
+    // Renders into a DirectComposition visual instead of Chromium's child windows; see
+    // `composition::Composition`. With `transparent`, the page blends per pixel with whatever is
+    // behind the window, rather than keying out one color.
+    pub visual_hosting: bool,
+    // `--<prefix>-url=` and the other `cli::CliOverrides` switches, `taco` by default. None
+    // ignores them, e.g. for builds that mustn't be pointed elsewhere. The url and opening the
+    // DevTools only apply to the first window built with a prefix, not to the app's others.
+    pub cli_prefix: Option<&'a str>,
+    // The folder of a fixed-version WebView2 runtime shipped with the app, used instead of the
+    // installed one, e.g. `Microsoft.WebView2.FixedVersionRuntime.97.0.1072.69.x64`. Overrides
+    // `release_channel_preference`.
+    pub browser_executable_folder: Option<&'a str>,
+    // Where cookies, caches and the like go, instead of a folder next to the executable.
+    pub data_directory: Option<&'a str>,
+    // Keeps cookies, caches and storage out of any lasting data directory, for sensitive
+    // sessions. This SDK predates in-private profiles, so the window gets a throwaway data
+    // directory, which is deleted once its browser process exits. Overrides `data_directory`
+    // and `profile`.
+    pub incognito: bool,
+    // Isolates cookies, storage and caches from WebViews with other profile names, e.g. `work`
+    // and `personal` accounts side by side; see `WebViewBuilder::profile`.
+    pub profile: Option<&'a str>,
+    // The UI language, e.g. `ja-JP` (also sent as Accept-Language), instead of the system's.
+    pub language: Option<&'a str>,
+    // Chromium command line switches, e.g. `--disable-gpu --autoplay-policy=no-user-gesture-required`.
+    pub additional_browser_args: Option<&'a str>,
+    // Merged with `additional_browser_args`; see `browser_args`.
+    pub browser_presets: Vec<environment::Preset>,
+    // Thin scrollbars drawn over the content, which fade out when not in use.
+    pub overlay_scrollbars: bool,
+    // See `WebView::thin_scrollbars`.
+    pub thin_scrollbars: bool,
+    // See `WebView::auto_touch_keyboard`.
+    pub auto_touch_keyboard: bool,
+    pub release_channel_preference: Option<environment::ReleaseChannelPreference>,
+    // Replaces the default user agent string, for this window's requests and `navigator.userAgent`.
+    pub user_agent: Option<&'a str>,
+    pub settings: settings::Settings,
+    pub resources: Vec<resource::Resource<'a>>,
+    pub notifications: bool,
+    // Binds `taco.startFileDrag`, which lets the page drag any local file out of the app, so
+    // only turn it on for trusted pages.
+    pub file_drag: bool,
+    pub init_scripts: Vec<String>,
+    // Available as `window.__TACO_CONFIG__` before any page script runs.
+    pub globals: Option<Value>,
+    // Shows pages the command line as `taco.launch.args`.
+    pub expose_args: bool,
+    // Environment variables pages can read from `taco.launch.env`, e.g. `["APP_ENV"]`. Unset
+    // ones are left out.
+    pub expose_env: Vec<&'a str>,
+    pub protocols: Vec<(&'a str, protocol::ProtocolHandler)>,
+    pub assets: Option<assets::Assets>,
+    pub host_mappings: Vec<(&'a str, &'a str, protocol::AccessKind)>,
 }
 
 impl<'a> Default for WebViewBuilder<'a> {
@@ -103,26 +291,85 @@ impl<'a> Default for WebViewBuilder<'a> {
             height: CW_USEDEFAULT,
             title: "",
             url: "",
+            retry_policy: None,
+            navigation_timeout: None,
+            html: None,
             debug: true,
             frameless: false,
+            cursor_hints: false,
             resizable: true,
             transparent: false,
             autosize: false,
+            zoom_control: true,
+            pinch_zoom: true,
+            sync_title: false,
+            sync_favicon: false,
+            keep_awake_while_playing: false,
+            auto_recovery: false,
+            auto_recovery_form: false,
+            suspend_when_minimized: false,
+            fullscreen_on_element: false,
+            theme: None,
+            animate_dpi_changes: false,
+            drop_navigation: true,
+            visual_hosting: false,
+            cli_prefix: Some("taco"),
+            browser_executable_folder: None,
+            data_directory: None,
+            incognito: false,
+            profile: None,
+            language: None,
+            additional_browser_args: None,
+            browser_presets: Vec::new(),
+            overlay_scrollbars: false,
+            thin_scrollbars: false,
+            auto_touch_keyboard: false,
+            release_channel_preference: None,
+            user_agent: None,
+            settings: settings::Settings::default(),
+            resources: Vec::new(),
+            notifications: false,
+            file_drag: false,
+            init_scripts: Vec::new(),
+            globals: None,
+            expose_args: false,
+            expose_env: Vec::new(),
+            protocols: Vec::new(),
+            assets: None,
+            host_mappings: Vec::new(),
         }
     }
 }
 
 #[derive(Clone)]
 pub struct WebView {
+    pub environment: ICoreWebView2Environment,
     pub controller: ICoreWebView2Controller,
     pub core: ICoreWebView2,
     bindings: Rc<RefCell<BindingsMap>>,
+    sync_bindings: Rc<RefCell<BindingsMap>>,
+    async_bindings: Rc<RefCell<HashMap<String, AsyncBindingCallback>>>,
+    // The shims of all kinds of bindings with their init script ids, by name.
+    binding_scripts: Rc<RefCell<HashMap<String, (String, String)>>>,
+    page_scale: Rc<Cell<Option<f64>>>,
+    insets: Rc<Cell<paint::Insets>>,
+    navigation_timeout: Rc<Cell<Option<Duration>>>,
+    // While `navigate_with` waits, so that its `file://` navigations aren't taken for drops.
+    host_navigating: Rc<Cell<bool>>,
+    fullscreen: Rc<Cell<Option<fullscreen::Restore>>>,
+    network_capture: Rc<RefCell<network::Capture>>,
+    error_pages: Rc<RefCell<error_page::ErrorPages>>,
+    shared_buffers: Rc<RefCell<shared_buffer::SharedBuffers>>,
+    find_results: Rc<RefCell<Option<find::FindCallback>>>,
+    devtools: Rc<RefCell<devtools::Tracked>>,
+    init_scripts: Rc<RefCell<Vec<String>>>,
+    reconnect: Rc<RefCell<recovery::Reconnect>>,
+    events: Rc<RefCell<event::Registry>>,
+    composition: Option<Rc<composition::Composition>>,
     pub hwnd: HWND,
-    pub hwnd_widget0: HWND,
-    pub hwnd_widget1: HWND,
-    pub hwnd_widgethost: HWND,
-    pub hwnd_d3d: HWND,
     pub hinstance: HINSTANCE,
+    // Last, so the COM objects above are released before the apartment is.
+    _apartment: Rc<com::Apartment>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -133,12 +380,140 @@ struct InvokeMessage {
 }
 
 impl<'a> WebViewBuilder<'a> {
-    pub fn build<T: 'static>(
+    // Registered before the first navigation, so they run on the initial page too.
+    pub fn with_init_script(mut self, js: impl Into<String>) -> Self {
+        self.init_scripts.push(js.into());
+        self
+    }
+
+    pub fn with_settings(mut self, settings: settings::Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    pub fn inject_globals(mut self, config: Value) -> Self {
+        self.globals = Some(config);
+        self
+    }
+
+    // Serves `<scheme>://...` from `handler`; see `protocol` for how the URLs are mapped.
+    pub fn register_protocol<F>(mut self, scheme: &'a str, handler: F) -> Self
+    where
+        F: FnMut(&protocol::Request) -> protocol::Response + 'static,
+    {
+        self.protocols.push((scheme, Box::new(handler)));
+        self
+    }
+
+    // e.g. `.map_host("app.local", "./web", AccessKind::Allow)` to open `https://app.local/index.html`.
+    pub fn map_host(
         mut self,
-    ) -> Result<(WebView, window::WindowRunner<T>, window::WindowHandle<T>)> {
-        unsafe {
-            use windows::Win32::System::Com::*;
-            CoInitializeEx(ptr::null_mut(), COINIT_APARTMENTTHREADED)?;
+        host: &'a str,
+        folder: &'a str,
+        access: protocol::AccessKind,
+    ) -> Self {
+        self.host_mappings.push((host, folder, access));
+        self
+    }
+
+    // Serves `assets` at `taco://app/...`. Opens `index.html` if there is one and `url` is empty.
+    pub fn with_assets(mut self, assets: assets::Assets) -> Self {
+        self.assets = Some(assets);
+        self
+    }
+
+    // This SDK predates ICoreWebView2ControllerOptions' profiles, so each profile gets a data
+    // directory, and a browser process, of its own under `data_directory`. Names are letters,
+    // digits, spaces, `-` and `_`.
+    pub fn profile(mut self, name: &'a str) -> Self {
+        self.profile = Some(name);
+        self
+    }
+
+    // What this process's command line overrides; see `cli_prefix`.
+    pub fn cli_overrides(&self) -> cli::CliOverrides {
+        self.cli_prefix
+            .map(cli::CliOverrides::from_args)
+            .unwrap_or_default()
+    }
+
+    // The switches the browser process will be started with: the presets', the ones for
+    // `overlay_scrollbars` and then `additional_browser_args`, merged.
+    pub fn browser_args(&self) -> String {
+        environment::merge_browser_args(
+            self.browser_presets
+                .iter()
+                .map(|preset| preset.args())
+                .chain(
+                    self.overlay_scrollbars
+                        .then_some(scrollbar::OVERLAY_SCROLLBAR_ARGS),
+                )
+                .chain(self.additional_browser_args),
+        )
+    }
+
+    // Creates the environment described by the fields from `browser_executable_folder` to
+    // `profile`, for `build_with_env`. `build` makes one per window.
+    pub fn create_environment(&self) -> Result<Environment> {
+        let apartment = Rc::new(com::ensure_sta()?);
+
+        let incognito_directory = match self.incognito {
+            true => Some(environment::incognito_directory()?),
+            false => None,
+        };
+        let cli = self.cli_overrides();
+        let data_directory = cli.user_data_dir.as_deref().or(self.data_directory);
+        let profile_directory = match self.profile {
+            Some(name) => Some(environment::profile_directory(data_directory, name)?),
+            None => None,
+        };
+        let browser_args = self.browser_args();
+        let environment = environment::create(&environment::EnvironmentOptions {
+            browser_executable_folder: self.browser_executable_folder,
+            data_directory: incognito_directory
+                .as_deref()
+                .or(profile_directory.as_deref())
+                .or(data_directory),
+            language: self.language,
+            additional_browser_args: Some(browser_args.as_str()).filter(|args| !args.is_empty()),
+            release_channel_preference: self.release_channel_preference,
+        })?;
+        if let Some(directory) = incognito_directory {
+            environment::remove_when_exited(&environment, directory)?;
+        }
+
+        Ok(Environment {
+            environment,
+            _apartment: apartment,
+        })
+    }
+
+    pub fn build<T: 'static>(self) -> Result<window::Window<T>> {
+        let environment = self.create_environment()?;
+        self.build_with_env(&environment)
+    }
+
+    // Builds the window in `environment`, so that it shares a browser process, caches and
+    // cookies with the other WebViews of the environment, and starts faster. This builder's
+    // environment fields, from `browser_executable_folder` to `profile`, are ignored.
+    // `environment` has to be from this thread.
+    pub fn build_with_env<T: 'static>(
+        mut self,
+        environment: &Environment,
+    ) -> Result<window::Window<T>> {
+        let apartment = Rc::new(com::ensure_sta()?);
+
+        let cli = self.cli_overrides();
+        let first = match self.cli_prefix {
+            Some(_) => cli.claim_first_window(),
+            None => None,
+        };
+        if let Some(Some(url)) = first {
+            self.url = url;
+            self.html = None;
+        }
+        if cli.devtools {
+            self.debug = true;
         }
 
         if self.frameless {
@@ -151,7 +526,14 @@ impl<'a> WebViewBuilder<'a> {
         }
 
         if self.transparent {
-            self.exstyle |= WS_EX_LAYERED
+            self.exstyle |= match self.visual_hosting {
+                true => WS_EX_NOREDIRECTIONBITMAP,
+                false => WS_EX_LAYERED,
+            };
+        }
+
+        if let Some(theme) = self.theme {
+            theme::set_app_theme(theme);
         }
 
         let (mut wrun, whandle) = window::create_window(
@@ -168,32 +550,20 @@ impl<'a> WebViewBuilder<'a> {
         let hwnd = whandle.hwnd;
         let hinstance = whandle.hinstance;
 
-        let environment = {
-            let (tx, rx) = mpsc::channel();
-
-            CreateCoreWebView2EnvironmentCompletedHandler::wait_for_async_operation(
-                Box::new(|environmentcreatedhandler| unsafe {
-                    CreateCoreWebView2Environment(environmentcreatedhandler)
-                        .map_err(webview2_com::Error::WindowsError)
-                }),
-                Box::new(
-                    move |error_code, environment: Option<ICoreWebView2Environment>| {
-                        error_code?;
-                        tx.send(
-                            environment.ok_or_else(|| windows::core::Error::fast_error(E_POINTER)),
-                        )
-                        .expect("send over mpsc channel");
-                        Ok(())
-                    },
-                ),
-            )?;
+        if let Some(theme) = self.theme {
+            theme::set_window_theme(hwnd, theme);
+        }
 
-            rx.recv()
-                .map_err(|_| Error::WebView2Error(webview2_com::Error::SendError))?
-        }?;
+        let environment = environment.environment.clone();
 
-        let controller = {
+        let mut composition = None;
+        let controller = if self.visual_hosting {
+            let (controller, visuals) = composition::create_controller(&environment, hwnd)?;
+            composition = Some(Rc::new(visuals));
+            controller
+        } else {
             let (tx, rx) = mpsc::channel();
+            let environment = environment.clone();
 
             CreateCoreWebView2ControllerCompletedHandler::wait_for_async_operation(
                 Box::new(move |handler| unsafe {
@@ -214,8 +584,8 @@ impl<'a> WebViewBuilder<'a> {
             )?;
 
             rx.recv()
-                .map_err(|_| Error::WebView2Error(webview2_com::Error::SendError))?
-        }?;
+                .map_err(|_| Error::WebView2Error(webview2_com::Error::SendError))??
+        };
 
         unsafe {
             controller.SetIsVisible(true)?;
@@ -231,33 +601,69 @@ impl<'a> WebViewBuilder<'a> {
             }
         }
 
-        fn find_child(hwndparent: HWND, lpszclass: &str) -> HWND {
-            unsafe { FindWindowExA(hwndparent, None, lpszclass, None) }
+        unsafe {
+            let settings = core.Settings()?;
+            if !self.zoom_control {
+                settings.SetIsZoomControlEnabled(false)?;
+            }
+            if !self.pinch_zoom {
+                settings
+                    .cast::<ICoreWebView2Settings5>()?
+                    .SetIsPinchZoomEnabled(false)?;
+            }
         }
-        let hwnd_widget0 = find_child(hwnd, "Chrome_WidgetWin_0");
-        let hwnd_widget1 = find_child(hwnd_widget0, "Chrome_WidgetWin_1");
-        let hwnd_widgethost = find_child(hwnd_widget1, "Chrome_RenderWidgetHostHWND");
-        // let hwnd_d3d = find_child(hwnd_widget1, "Intermediate D3D Window");  doesn't work
 
-        let mut webview = WebView {
+        self.settings.apply(&core)?;
+
+        if let Some(user_agent) = self.user_agent {
+            set_core_user_agent(&core, user_agent)?;
+        }
+
+        let webview = WebView {
+            environment,
             controller,
             core,
             bindings: Rc::new(RefCell::new(HashMap::new())),
+            sync_bindings: Rc::new(RefCell::new(HashMap::new())),
+            async_bindings: Rc::new(RefCell::new(HashMap::new())),
+            binding_scripts: Rc::new(RefCell::new(HashMap::new())),
+            page_scale: Rc::new(Cell::new(None)),
+            insets: Rc::new(Cell::new(paint::Insets::default())),
+            navigation_timeout: Rc::new(Cell::new(self.navigation_timeout)),
+            host_navigating: Rc::new(Cell::new(false)),
+            fullscreen: Rc::new(Cell::new(None)),
+            network_capture: Rc::new(RefCell::new(network::Capture::default())),
+            error_pages: Rc::new(RefCell::new(error_page::ErrorPages::default())),
+            shared_buffers: Rc::new(RefCell::new(shared_buffer::SharedBuffers::default())),
+            find_results: Rc::new(RefCell::new(None)),
+            devtools: Rc::new(RefCell::new(devtools::Tracked::default())),
+            init_scripts: Rc::new(RefCell::new(Vec::new())),
+            reconnect: Rc::new(RefCell::new(recovery::Reconnect::default())),
+            events: Rc::new(RefCell::new(event::Registry::default())),
+            composition: composition.clone(),
             hwnd,
-            hwnd_widget0,
-            hwnd_widget1,
-            hwnd_widgethost,
-            hwnd_d3d: HWND(0),
             hinstance,
+            _apartment: apartment,
         };
 
+        thread::listen(&webview, &mut wrun);
+
+        if let Some(composition) = &composition {
+            composition::forward_input(&webview, composition, &mut wrun)?;
+        }
+
+        // Handlers hold clones of the WebView, so they'd keep it alive forever.
+        let w = webview.clone();
+        wrun.add_event_listener(WM_DESTROY, move |_, _, _| w.unsubscribe_all());
+
         // Inject the invoke handler.
         webview
             .init(r#"window.external = { invoke: s => window.chrome.webview.postMessage(s) };"#)?;
+        webview.init(include_str!("events.js"))?;
 
         unsafe {
             let w = webview.clone();
-            let mut _token = EventRegistrationToken::default();
+            let mut token = EventRegistrationToken::default();
             webview.core.WebMessageReceived(
                 WebMessageReceivedEventHandler::create(Box::new(
                     move |_core, args: Option<ICoreWebView2WebMessageReceivedEventArgs>| {
@@ -266,20 +672,47 @@ impl<'a> WebViewBuilder<'a> {
                             if args.WebMessageAsJson(&mut message).is_ok() {
                                 let message = take_pwstr(message);
                                 if let Ok(value) = serde_json::from_str::<InvokeMessage>(&message) {
-                                    let mut bindings = w.bindings.borrow_mut();
-                                    if let Some(f) = bindings.get_mut(&value.method) {
-                                        let webview = &w;
+                                    let binding = w.bindings.borrow().get(&value.method).cloned();
+                                    let async_binding =
+                                        w.async_bindings.borrow().get(&value.method).cloned();
+                                    if let Some(f) = binding {
+                                        let webview = w.clone();
                                         window::dispatch_unsafe(hwnd, move |_: &T| {
-                                            match f(value.params) {
-                                                Ok(result) => resolve(webview, value.id, 0, result),
+                                            #[cfg(feature = "diagnostics")]
+                                            let started = std::time::Instant::now();
+                                            // A call made while the binding itself pumps
+                                            // messages, e.g. in `navigate`.
+                                            let result = match f.try_borrow_mut() {
+                                                Ok(mut f) => f(value.params),
+                                                Err(_) => Err(format!(
+                                                    "{} is still running",
+                                                    value.method
+                                                )),
+                                            };
+                                            #[cfg(feature = "diagnostics")]
+                                            diagnostics::record_ipc(
+                                                &value.method,
+                                                started,
+                                                result.is_ok(),
+                                            );
+                                            match result {
+                                                Ok(result) => {
+                                                    resolve(&webview, value.id, 0, result)
+                                                }
                                                 Err(err) => resolve(
-                                                    webview,
+                                                    &webview,
                                                     value.id,
                                                     1,
                                                     Value::String(err),
                                                 ),
                                             }
                                         })
+                                    } else if let Some(f) = async_binding {
+                                        // Dropping the responder rejects a re-entered call.
+                                        let responder = thread::Responder::new(&w, value.id);
+                                        if let Ok(mut f) = f.try_borrow_mut() {
+                                            f(value.params, responder);
+                                        }
                                     }
                                 }
                             }
@@ -287,8 +720,50 @@ impl<'a> WebViewBuilder<'a> {
                         Ok(())
                     },
                 )),
-                &mut _token,
+                &mut token,
             )?;
+            let core = webview.core.clone();
+            webview.track(move || core.RemoveWebMessageReceived(token));
+        }
+
+        let mut routes = Vec::new();
+        if !self.resources.is_empty() {
+            routes.push(resource::pin(&webview, &self.resources)?);
+        }
+        let mut schemes = self
+            .protocols
+            .iter()
+            .map(|(scheme, _)| *scheme)
+            .collect::<Vec<_>>();
+        for (scheme, handler) in self.protocols.drain(..) {
+            routes.push(protocol::Route::new(scheme, handler));
+        }
+        if let Some(assets) = self.assets.take() {
+            if self.url.is_empty() && assets.get("index.html").is_some() {
+                self.url = assets::ASSET_URL;
+            }
+            routes.push(assets.into_route());
+            schemes.push("taco");
+        }
+        if !routes.is_empty() {
+            protocol::serve(&webview, routes)?;
+        }
+
+        for (host, folder, access) in &self.host_mappings {
+            webview.map_host(host, folder, *access)?;
+        }
+
+        taskbar::inject(&webview)?;
+        shared_buffer::inject(&webview)?;
+        if self.file_drag {
+            drag::inject(&webview)?;
+        }
+        file_drop::inject(&webview)?;
+        find::inject(&webview)?;
+        cursor::inject(&webview, &mut wrun, self.cursor_hints)?;
+
+        if self.notifications {
+            notification::bridge(&webview, &mut wrun, self.title)?;
         }
 
         if self.autosize {
@@ -306,7 +781,7 @@ impl<'a> WebViewBuilder<'a> {
             webview.init(include_str!("autosize.js")).unwrap();
         } else {
             let w = webview.clone();
-            wrun.add_event_listener(WM_SIZE, move |_, _| {
+            wrun.add_event_listener(WM_SIZE, move |_, _, _| {
                 let size = get_window_size(hwnd);
                 w.set_webview_size(size.cx, size.cy);
             });
@@ -314,69 +789,195 @@ impl<'a> WebViewBuilder<'a> {
             webview.set_webview_size(size.cx, size.cy);
         }
 
+        // Monitor scale detection is off while a page scale is set, and otherwise lags behind
+        // until the next resize, leaving the content blurry; so keep it up to date here.
+        let w = webview.clone();
+        wrun.add_event_listener(WM_DPICHANGED, move |_, _, _| {
+            match w.page_scale.get() {
+                Some(scale) => {
+                    // Fails after the controller is closed, as does the call below.
+                    let _ = w.set_page_scale(scale);
+                }
+                None => {
+                    if let Ok(controller) = w.controller.cast::<ICoreWebView2Controller3>() {
+                        let _ = unsafe { controller.SetRasterizationScale(dpi_ratio(hwnd)) };
+                    }
+                }
+            }
+            let size = get_window_size(hwnd);
+            w.set_webview_size(size.cx, size.cy);
+        });
+
+        if self.animate_dpi_changes {
+            wrun.set_animate_dpi_changes(true);
+        }
+
         if self.transparent {
-            webview.bg();
+            webview.set_background_color(0, 0, 0, 0)?;
+            // A layered window stays invisible until it has attributes. Pure green is keyed
+            // out, which leaves everything else opaque.
+            if !self.visual_hosting {
+                unsafe { SetLayeredWindowAttributes(hwnd, 0x0000FF00, 0, LWA_COLORKEY) };
+            }
         }
 
-        if !self.url.is_empty() {
-            webview.navigate(self.url)?.set_visible(true)?;
+        if self.sync_title {
+            webview.sync_title()?;
+        }
+
+        if self.sync_favicon {
+            webview.sync_favicon()?;
+        }
+
+        if self.keep_awake_while_playing {
+            webview.keep_awake_while_playing()?;
+        }
+
+        if self.auto_recovery {
+            webview.enable_auto_recovery(self.auto_recovery_form)?;
+        }
+
+        if self.fullscreen_on_element {
+            webview.fullscreen_on_element()?;
+        }
+
+        if !self.drop_navigation {
+            webview.block_drop_navigation()?;
+        }
+
+        if self.suspend_when_minimized {
+            webview.suspend_when_minimized(&mut wrun)?;
+        }
+
+        if self.thin_scrollbars {
+            webview.thin_scrollbars()?;
+        }
+
+        if self.auto_touch_keyboard {
+            webview.auto_touch_keyboard()?;
+        }
+
+        if let Some(config) = &self.globals {
+            webview.init(&format!("window.__TACO_CONFIG__ = {};", config))?;
+        }
+
+        if self.expose_args || !self.expose_env.is_empty() {
+            launch::inject(&webview, self.expose_args, &self.expose_env)?;
+        }
+
+        for js in &self.init_scripts {
+            webview.init(js)?;
+        }
+
+        if let Some(html) = self.html {
+            webview.set_html(html)?.set_visible(true)?;
+        } else if !self.url.is_empty() {
+            let url = schemes
+                .iter()
+                .find_map(|scheme| protocol::to_https(self.url, scheme))
+                .unwrap_or_else(|| self.url.to_string());
+            if let Some(policy) = self.retry_policy.take() {
+                retry::watch(&webview, &mut wrun, &url, policy)?;
+            }
+            webview.navigate(&url)?.set_visible(true)?;
         }
 
-        // Here because it needs a delay of about 150 ms or more.
-        webview.hwnd_d3d = find_child(hwnd_widget1, "Intermediate D3D Window");
+        if cli.devtools && first.is_some() {
+            webview.open_devtools()?;
+        }
 
-        Ok((webview, wrun, whandle))
+        Ok(window::Window::new(webview, wrun, whandle))
     }
 }
 
 impl WebView {
     pub fn init(&self, js: &str) -> Result<&Self> {
+        self.add_init_script(js)?;
+        Ok(self)
+    }
+
+    // Returns the id to remove the script with again.
+    fn add_init_script(&self, js: &str) -> Result<String> {
+        self._apartment.assert_thread("WebView::init");
+        // Only kept for `enable_reconnect`, once each.
+        let mut scripts = self.init_scripts.borrow_mut();
+        if !scripts.iter().any(|script| script == js) {
+            scripts.push(js.to_string());
+        }
+        drop(scripts);
         let core = self.core.clone();
         let js = String::from(js);
+        let (tx, rx) = mpsc::channel();
         AddScriptToExecuteOnDocumentCreatedCompletedHandler::wait_for_async_operation(
             Box::new(move |handler| unsafe {
                 core.AddScriptToExecuteOnDocumentCreated(js, handler)
                     .map_err(webview2_com::Error::WindowsError)
             }),
-            Box::new(|error_code, _id| error_code),
+            Box::new(move |error_code, id| {
+                error_code?;
+                tx.send(id).expect("send over mpsc channel");
+                Ok(())
+            }),
         )?;
-        Ok(self)
+        rx.recv()
+            .map_err(|_| Error::WebView2Error(webview2_com::Error::SendError))
+    }
+
+    // Once per name and shim, however often it's bound again; the shims look the callback up
+    // by name. Replaces the shim of another kind of binding of the same name. Evaluated right
+    // away too, for bindings made after the page has loaded.
+    pub(crate) fn inject_binding(&self, name: &str, shim: String) -> Result<()> {
+        match self.binding_scripts.borrow().get(name) {
+            Some((_, injected)) if *injected == shim => return Ok(()),
+            _ => {}
+        }
+        self.remove_binding_script(name)?;
+        let id = self.add_init_script(&shim)?;
+        self.eval(&shim)?;
+        self.binding_scripts
+            .borrow_mut()
+            .insert(name.to_string(), (id, shim));
+        Ok(())
+    }
+
+    fn remove_binding_script(&self, name: &str) -> windows::core::Result<()> {
+        let script = self.binding_scripts.borrow_mut().remove(name);
+        if let Some((id, shim)) = script {
+            unsafe { self.core.RemoveScriptToExecuteOnDocumentCreated(id)? };
+            self.init_scripts.borrow_mut().retain(|js| *js != shim);
+        }
+        Ok(())
+    }
+
+    // Drops the other kinds of bindings of `name`, before binding it anew.
+    pub(crate) fn forget_binding(&self, name: &str) {
+        self.bindings.borrow_mut().remove(name);
+        self.async_bindings.borrow_mut().remove(name);
+        self.sync_bindings.borrow_mut().remove(name);
+    }
+
+    // Removes the binding, of whichever kind, and its `window[name]`, from the current document
+    // too. Calls still in flight are never settled.
+    pub fn unbind(&self, name: impl AsRef<str>) -> Result<&Self> {
+        self._apartment.assert_thread("WebView::unbind");
+        let name = name.as_ref();
+        self.forget_binding(name);
+        self.remove_binding_script(name)?;
+        self.eval(&format!("delete window[{}];", serde_json::to_string(name)?))
     }
 
     pub fn bind_unsafe<F>(&self, name: impl AsRef<str>, f: F)
     where
         F: FnMut(Vec<Value>) -> std::result::Result<Value, String> + 'static,
     {
+        self._apartment.assert_thread("WebView::bind");
         let name = name.as_ref();
+        self.forget_binding(name);
         self.bindings
             .borrow_mut()
-            .insert(String::from(name), Box::new(f));
-
-        let js = String::from(
-            r#"
-            (function() {
-                var name = '"#,
-        ) + name
-            + r#"';
-                var RPC = window._rpc = (window._rpc || {nextSeq: 1});
-                window[name] = function() {
-                    var seq = RPC.nextSeq++;
-                    var promise = new Promise(function(resolve, reject) {
-                        RPC[seq] = {
-                            resolve: resolve,
-                            reject: reject,
-                        };
-                    });
-                    window.external.invoke({
-                        id: seq,
-                        method: name,
-                        params: Array.prototype.slice.call(arguments),
-                    });
-                    return promise;
-                }
-            })()"#;
+            .insert(String::from(name), Rc::new(RefCell::new(f)));
 
-        self.init(&js).unwrap();
+        self.inject_binding(name, binding_shim(name)).unwrap();
     }
 
     pub fn bind<F>(&self, name: impl AsRef<str>, f: F)
@@ -386,28 +987,98 @@ impl WebView {
         self.bind_unsafe(name, f);
     }
 
+    // For bindings that take a while, e.g. network or disk I/O: `f` gets a `Responder` to
+    // settle the call with later, from any thread, and should return right away so the window
+    // keeps pumping messages. Futures can be run on the app's own executor and resolve it.
+    pub fn bind_async<F>(&self, name: impl AsRef<str>, f: F)
+    where
+        F: FnMut(Vec<Value>, thread::Responder) + 'static,
+    {
+        self._apartment.assert_thread("WebView::bind_async");
+        let name = name.as_ref();
+        self.forget_binding(name);
+        self.async_bindings
+            .borrow_mut()
+            .insert(String::from(name), Rc::new(RefCell::new(f)));
+
+        self.inject_binding(name, binding_shim(name)).unwrap();
+    }
+
+    // Deserializes the JS arguments into `Args` (usually a tuple) and serializes the result,
+    // so type mismatches reject the promise instead of being matched by hand.
+    pub fn bind_typed<Args, R, E, F>(&self, name: impl AsRef<str>, mut f: F)
+    where
+        Args: DeserializeOwned,
+        R: Serialize,
+        E: fmt::Display,
+        F: FnMut(Args) -> std::result::Result<R, E> + Send + 'static,
+    {
+        self.bind(name, move |params| {
+            let args =
+                serde_json::from_value(Value::Array(params)).map_err(|err| err.to_string())?;
+            let result = f(args).map_err(|err| err.to_string())?;
+            serde_json::to_value(result).map_err(|err| err.to_string())
+        });
+    }
+
     pub fn navigate(&self, url: &str) -> Result<&Self> {
+        self.navigate_with(|core| unsafe { core.Navigate(url) })
+    }
+
+    // Replaces the page with `html`, which gets an `about:blank` origin. Limited to 2 MB.
+    pub fn set_html(&self, html: &str) -> Result<&Self> {
+        self.navigate_with(|core| unsafe { core.NavigateToString(html) })
+    }
+
+    // How long `navigate`, `set_html` and the like wait for the page before failing with
+    // `Error::NavigationTimeout` and stopping it. None waits as long as it takes.
+    pub fn set_navigation_timeout(&self, timeout: Option<Duration>) -> &Self {
+        self.navigation_timeout.set(timeout);
+        self
+    }
+
+    // Starts a navigation and waits for it to complete.
+    fn navigate_with(
+        &self,
+        start: impl FnOnce(&ICoreWebView2) -> windows::core::Result<()>,
+    ) -> Result<&Self> {
+        self._apartment.assert_thread("WebView::navigate");
+        let was_navigating = self.host_navigating.replace(true);
+        let result = self.start_and_wait(start);
+        self.host_navigating.set(was_navigating);
+        result.map(|_| self)
+    }
+
+    fn start_and_wait(
+        &self,
+        start: impl FnOnce(&ICoreWebView2) -> windows::core::Result<()>,
+    ) -> Result<()> {
         let core = &self.core;
         let (tx, rx) = mpsc::channel();
 
         let handler = NavigationCompletedEventHandler::create(Box::new(move |_sender, _args| {
-            tx.send(()).expect("send over mpsc channel");
+            let _ = tx.send(());
             Ok(())
         }));
         let mut token = EventRegistrationToken::default();
         unsafe {
             core.NavigationCompleted(handler, &mut token)?;
-            if let Err(err) = core.Navigate(url) {
+            if let Err(err) = start(core) {
                 core.RemoveNavigationCompleted(token)?;
                 return Err(err.into());
             }
-            webview2_com::wait_with_pump(rx)?;
+            let completed = wait_with_pump_timeout(rx, self.navigation_timeout.get());
             core.RemoveNavigationCompleted(token)?;
+            if completed?.is_none() {
+                core.Stop()?;
+                return Err(Error::NavigationTimeout);
+            }
         }
-        Ok(self)
+        Ok(())
     }
 
     pub fn eval(&self, js: &str) -> Result<&Self> {
+        self._apartment.assert_thread("WebView::eval");
         let core = self.core.clone();
         let js = String::from(js);
         ExecuteScriptCompletedHandler::wait_for_async_operation(
@@ -420,43 +1091,120 @@ impl WebView {
         Ok(self)
     }
 
-    // 背景を透明化
-    // TODO: タイトルバーが透明化されないようにする
-    pub fn bg(&self) {
-        let t = one_to_two(&self.controller);
+    // Calls the global function `name` with `args` passed as JSON, e.g.
+    // `call_js("showToast", &["Saved", "it's done"])`. Its return value is dropped.
+    pub fn call_js(&self, name: &str, args: &[impl Serialize]) -> Result<&Self> {
+        let js = format!(
+            "window[{}].apply(window, {});",
+            serde_json::to_string(name)?,
+            serde_json::to_string(args)?
+        );
+        self.eval(&js)
+    }
 
-        // セット
-        let backgroundcolor = COREWEBVIEW2_COLOR {
-            R: 20,
-            G: 200,
-            B: 70,
-            A: 0,
-        };
-        unsafe {
-            t.SetDefaultBackgroundColor(backgroundcolor).unwrap();
-        }
+    // Delivered to the callbacks registered with `taco.on(event, callback)` in the page.
+    pub fn emit(&self, event: &str, payload: impl Serialize) -> Result<&Self> {
+        let message = serde_json::json!({
+            "_tacoEvent": event,
+            "payload": payload,
+        });
+        recovery::record_emit(&self.reconnect, event, &message["payload"]);
+        unsafe { self.core.PostWebMessageAsJson(message.to_string())? };
+        Ok(self)
+    }
 
-        // ゲット
-        let mut backgroundcolor = Default::default();
+    // Chrome DevTools Protocol, e.g. `("Network.emulateNetworkConditions", json!({...}))`.
+    pub fn call_devtools_method(&self, method: &str, params: Value) -> Result<Value> {
+        self._apartment
+            .assert_thread("WebView::call_devtools_method");
+        let core = self.core.clone();
+        let method = String::from(method);
+        let params = params.to_string();
+        let (tx, rx) = mpsc::channel();
+        CallDevToolsProtocolMethodCompletedHandler::wait_for_async_operation(
+            Box::new(move |handler| unsafe {
+                core.CallDevToolsProtocolMethod(method, params, handler)
+                    .map_err(webview2_com::Error::WindowsError)
+            }),
+            Box::new(move |error_code, result| {
+                error_code?;
+                tx.send(result).expect("send over mpsc channel");
+                Ok(())
+            }),
+        )?;
+        let result = rx
+            .recv()
+            .map_err(|_| Error::WebView2Error(webview2_com::Error::SendError))?;
+        Ok(serde_json::from_str(&result)?)
+    }
+
+    // CDP events only fire once their domain is enabled, e.g. with `Network.enable`.
+    pub fn on_devtools_event(
+        &self,
+        event: &str,
+        mut f: impl FnMut(Value) + 'static,
+    ) -> Result<event::Subscription> {
+        let receiver = unsafe { self.core.GetDevToolsProtocolEventReceiver(event)? };
+        let mut token = EventRegistrationToken::default();
         unsafe {
-            t.DefaultBackgroundColor(&mut backgroundcolor).unwrap();
+            receiver.DevToolsProtocolEventReceived(
+                DevToolsProtocolEventReceivedEventHandler::create(Box::new(
+                    move |_core, args: Option<ICoreWebView2DevToolsProtocolEventReceivedEventArgs>| {
+                        if let Some(args) = args {
+                            let mut json = PWSTR::default();
+                            args.ParameterObjectAsJson(&mut json)?;
+                            if let Ok(params) = serde_json::from_str(&take_pwstr(json)) {
+                                f(params);
+                            }
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut token,
+            )?;
         }
-        println!("{:?}", backgroundcolor);
+        Ok(self.track(move || unsafe { receiver.RemoveDevToolsProtocolEventReceived(token) }))
+    }
 
+    // Shown wherever the page itself is transparent, and before the first page loads.
+    // WebView2 only does fully transparent (alpha 0) or opaque (255), and alpha 0 only shows
+    // through windows built with `transparent`.
+    pub fn set_background_color(&self, r: u8, g: u8, b: u8, a: u8) -> Result<&Self> {
+        match a {
+            255 => {}
+            0 => {
+                let exstyle = unsafe { GetWindowLong(self.hwnd, GWL_EXSTYLE) } as u32;
+                if exstyle & (WS_EX_LAYERED | WS_EX_NOREDIRECTIONBITMAP) == 0 {
+                    return Err(Error::InvalidBackgroundColor(
+                        "alpha 0 needs a transparent window".into(),
+                    ));
+                }
+            }
+            _ => {
+                return Err(Error::InvalidBackgroundColor(format!(
+                    "alpha {} isn't supported, only 0 or 255",
+                    a
+                )))
+            }
+        }
+        let controller: ICoreWebView2Controller2 = self.controller.cast()?;
         unsafe {
-            SetLayeredWindowAttributes(self.hwnd, 0x0000FF00, 50, LWA_COLORKEY);
-            // SetLayeredWindowAttributes(self.hwnd, 0x0000FF00, 100, LWA_ALPHA);
-
-            //     let hrgndst = Gdi::CreateEllipticRgn(0, 0, 500, 400);
-
-            //     let hrgn = Gdi::CreateRectRgn(-100, -100, 900, 100);
-            //     Gdi::CombineRgn(hrgndst, hrgndst, hrgn, Gdi::RGN_OR);
-
-            //     Gdi::SetWindowRgn(self.hwnd, hrgndst, true);
+            controller.SetDefaultBackgroundColor(COREWEBVIEW2_COLOR {
+                A: a,
+                R: r,
+                G: g,
+                B: b,
+            })?
+        };
+        Ok(self)
+    }
 
-            // Gdi::InvalidateRect(self.hwnd, null(), true).ok().unwrap();
-            // Gdi::UpdateWindow(self.hwnd).ok().unwrap();
-        }
+    // (r, g, b, a)
+    pub fn background_color(&self) -> Result<(u8, u8, u8, u8)> {
+        let controller: ICoreWebView2Controller2 = self.controller.cast()?;
+        let mut color = COREWEBVIEW2_COLOR::default();
+        unsafe { controller.DefaultBackgroundColor(&mut color)? };
+        Ok((color.R, color.G, color.B, color.A))
     }
 
     pub fn set_position(&self, x: i32, y: i32) -> Result<&Self> {
@@ -467,6 +1215,7 @@ impl WebView {
     }
 
     pub fn set_visible(&self, visible: bool) -> Result<&Self> {
+        self._apartment.assert_thread("WebView::set_visible");
         unsafe {
             ShowWindow(self.hwnd, if visible { SW_SHOW } else { SW_HIDE });
         }
@@ -493,23 +1242,182 @@ impl WebView {
         Ok(self)
     }
 
+    // The size of the client area; the webview covers it except for `set_insets`.
     pub fn set_webview_size(&self, width: i32, height: i32) {
+        self.set_bounds(self.insets.get().apply(width, height))
+            .unwrap();
+    }
+
+    // In client coordinates. A visually hosted WebView is moved by its visual's offset instead,
+    // so that its clip moves along.
+    pub(crate) fn set_bounds(&self, bounds: RECT) -> Result<()> {
+        match &self.composition {
+            Some(composition) => composition.set_bounds(&self.controller, bounds),
+            None => {
+                unsafe { self.controller.SetBounds(bounds)? };
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn bounds(&self) -> Result<RECT> {
+        if let Some(composition) = &self.composition {
+            return Ok(composition.bounds());
+        }
+        let mut bounds = RECT::default();
+        unsafe { self.controller.Bounds(&mut bounds)? };
+        Ok(bounds)
+    }
+
+    // None unless built with `visual_hosting`.
+    pub fn composition(&self) -> Option<&composition::Composition> {
+        self.composition.as_deref()
+    }
+
+    // Uniformly scales the whole page layout, independent of the user's zoom factor.
+    pub fn set_page_scale(&self, scale: f64) -> Result<&Self> {
+        let controller: ICoreWebView2Controller3 = self.controller.cast()?;
         unsafe {
-            self.controller
-                .SetBounds(RECT {
-                    left: 0,
-                    top: 0,
-                    right: width,
-                    bottom: height,
-                })
-                .unwrap();
+            controller.SetShouldDetectMonitorScaleChanges(false)?;
+            controller.SetRasterizationScale(dpi_ratio(self.hwnd) * scale)?;
         }
+        self.page_scale.set(Some(scale));
+        Ok(self)
+    }
+
+    pub fn page_scale(&self) -> f64 {
+        self.page_scale.get().unwrap_or(1.)
+    }
+
+    // Fits a fixed-resolution layout of `width` x `height` CSS pixels into the client area.
+    pub fn fit_page_scale(&self, width: f64, height: f64) -> Result<&Self> {
+        let size = get_window_size(self.hwnd);
+        let ratio = dpi_ratio(self.hwnd);
+        let scale_x = size.cx as f64 / ratio / width;
+        let scale_y = size.cy as f64 / ratio / height;
+        self.set_page_scale(scale_x.min(scale_y))
     }
+
+    pub fn reset_page_scale(&self) -> Result<&Self> {
+        let controller: ICoreWebView2Controller3 = self.controller.cast()?;
+        unsafe {
+            controller.SetRasterizationScale(dpi_ratio(self.hwnd))?;
+            controller.SetShouldDetectMonitorScaleChanges(true)?;
+        }
+        self.page_scale.set(None);
+        Ok(self)
+    }
+
+    // Browser-style zoom, as with Ctrl+wheel. Unlike the page scale, this changes the layout width.
+    pub fn set_zoom(&self, zoom: f64) -> Result<&Self> {
+        unsafe { self.controller.SetZoomFactor(zoom)? };
+        Ok(self)
+    }
+
+    pub fn zoom(&self) -> Result<f64> {
+        let mut zoom = 1.;
+        unsafe { self.controller.ZoomFactor(&mut zoom)? };
+        Ok(zoom)
+    }
+
+    pub fn set_user_agent(&self, user_agent: &str) -> Result<&Self> {
+        set_core_user_agent(&self.core, user_agent)?;
+        Ok(self)
+    }
+
+    pub fn user_agent(&self) -> Result<String> {
+        let mut user_agent = PWSTR::default();
+        unsafe {
+            self.core
+                .Settings()?
+                .cast::<ICoreWebView2Settings2>()?
+                .UserAgent(&mut user_agent)?
+        };
+        Ok(take_pwstr(user_agent))
+    }
+
+    pub fn on_zoom_changed(&self, mut f: impl FnMut(f64) + 'static) -> Result<event::Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.controller.ZoomFactorChanged(
+                ZoomFactorChangedEventHandler::create(Box::new(move |controller, _args| {
+                    if let Some(controller) = controller {
+                        let mut zoom = 1.;
+                        controller.ZoomFactor(&mut zoom)?;
+                        f(zoom);
+                    }
+                    Ok(())
+                })),
+                &mut token,
+            )?;
+        }
+        let controller = self.controller.clone();
+        Ok(self.track(move || unsafe { controller.RemoveZoomFactorChanged(token) }))
+    }
+}
+
+fn set_core_user_agent(core: &ICoreWebView2, user_agent: &str) -> Result<()> {
+    unsafe {
+        core.Settings()?
+            .cast::<ICoreWebView2Settings2>()?
+            .SetUserAgent(user_agent)?
+    };
+    Ok(())
+}
+
+fn dpi_ratio(hwnd: HWND) -> f64 {
+    let dpi = unsafe { windows::Win32::UI::HiDpi::GetDpiForWindow(hwnd) };
+    dpi as f64 / 96.
+}
+
+// Like `webview2_com::wait_with_pump`, but None once `timeout` is up. A thread timer wakes the
+// pump up to check.
+fn wait_with_pump_timeout<T>(
+    rx: mpsc::Receiver<T>,
+    timeout: Option<Duration>,
+) -> Result<Option<T>> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return Ok(Some(webview2_com::wait_with_pump(rx)?)),
+    };
+    let deadline = Instant::now() + timeout;
+    let ms = timeout.as_millis().clamp(1, u32::MAX as u128) as u32;
+    let timer = unsafe { SetTimer(None, 0, ms, None) };
+
+    let mut msg = MSG::default();
+    let result = loop {
+        if let Ok(result) = rx.try_recv() {
+            break Ok(Some(result));
+        }
+        if Instant::now() >= deadline {
+            break Ok(None);
+        }
+        unsafe {
+            match GetMessageA(&mut msg, None, 0, 0).0 {
+                -1 => break Err(windows::core::Error::from_win32().into()),
+                0 => break Err(webview2_com::Error::TaskCanceled.into()),
+                // Ours, only there to wake us up.
+                _ if msg.hwnd.0 == 0 && msg.message == WM_TIMER && msg.wParam.0 == timer => {}
+                _ => {
+                    TranslateMessage(&msg);
+                    DispatchMessageA(&msg);
+                }
+            }
+        }
+    };
+    unsafe { KillTimer(None, timer) };
+    result
+}
+
+// Pumps messages for `duration`.
+pub(crate) fn pump_for(duration: Duration) -> Result<()> {
+    let (_tx, rx) = mpsc::channel::<()>();
+    wait_with_pump_timeout(rx, Some(duration)).map(|_| ())
 }
 
 fn get_window_size(hwnd: HWND) -> SIZE {
     let mut client_rect = RECT::default();
-    unsafe { GetClientRect(hwnd, std::mem::transmute(&mut client_rect)) };
+    unsafe { GetClientRect(hwnd, &mut client_rect) };
     SIZE {
         cx: client_rect.right - client_rect.left,
         cy: client_rect.bottom - client_rect.top,
@@ -540,20 +1448,68 @@ unsafe fn GetWindowLong(window: HWND, index: WINDOW_LONG_PTR_INDEX) -> isize {
     GetWindowLongPtrA(window, index)
 }
 
-pub fn resolve(webview: &WebView, id: u64, status: i32, result: Value) -> Result<()> {
+// Like `call_devtools_method`, but doesn't wait for (or return) the result, so it's safe to call
+// from inside WebView2 event handlers.
+pub(crate) fn send_devtools_method(core: &ICoreWebView2, method: &str, params: Value) {
+    unsafe {
+        core.CallDevToolsProtocolMethod(
+            method,
+            params.to_string(),
+            CallDevToolsProtocolMethodCompletedHandler::create(Box::new(|_, _| Ok(()))),
+        )
+        .ok();
+    }
+}
+
+// Defines `window[name]`, which sends an InvokeMessage and returns a promise for the result.
+fn binding_shim(name: &str) -> String {
+    String::from(
+        r#"
+            (function() {
+                var name = '"#,
+    ) + name
+        + r#"';
+                var RPC = window._rpc = (window._rpc || {nextSeq: 1});
+                window[name] = function() {
+                    var seq = RPC.nextSeq++;
+                    var promise = new Promise(function(resolve, reject) {
+                        RPC[seq] = {
+                            resolve: resolve,
+                            reject: reject,
+                        };
+                    });
+                    window.external.invoke({
+                        id: seq,
+                        method: name,
+                        params: Array.prototype.slice.call(arguments),
+                    });
+                    return promise;
+                }
+            })()"#
+}
+
+fn resolve_script(id: u64, status: i32, result: Value) -> String {
     let result = result.to_string();
     let method = match status {
         0 => "resolve",
         _ => "reject",
     };
-    let js = format!(
+    format!(
         r#"
             window._rpc[{}].{}({});
             window._rpc[{}] = undefined;"#,
         id, method, result, id
-    );
+    )
+}
 
-    webview.eval(&js).unwrap();
+// The version of the WebView2 Runtime new WebViews would use, e.g. `97.0.1072.69`, so apps
+// can check for one before building a window; see also `environment::browser_version`.
+pub fn webview2_version() -> Result<String> {
+    environment::browser_version(None)
+}
+
+pub fn resolve(webview: &WebView, id: u64, status: i32, result: Value) -> Result<()> {
+    webview.eval(&resolve_script(id, status, result)).unwrap();
     Ok(())
 }
 
@@ -569,8 +1525,7 @@ pub fn adjust_to_content(webview: &WebView, offset_width: f64, offset_height: f6
     let non_client_width = (window.right - window.left) - (client.right - client.left);
     let non_client_height = (window.bottom - window.top) - (client.bottom - client.top);
 
-    let dpi = unsafe { windows::Win32::UI::HiDpi::GetDpiForWindow(webview.hwnd) };
-    let ratio = dpi as f64 / 96.;
+    let ratio = dpi_ratio(webview.hwnd);
     let width = (offset_width * ratio) as i32;
     let height = (offset_height * ratio) as i32;
     unsafe {
@@ -586,7 +1541,3 @@ pub fn adjust_to_content(webview: &WebView, offset_width: f64, offset_height: f6
     }
     webview.set_webview_size(width, height);
 }
-
-fn one_to_two(one: &ICoreWebView2Controller) -> &ICoreWebView2Controller2 {
-    unsafe { std::mem::transmute(one) }
-}
@@ -11,7 +11,7 @@ use std::{
 
 fn main() -> taco::Result<()> {
     std::thread::spawn(|| {
-        let (_webview2, wrun2, _whandle2) = taco::WebViewBuilder {
+        let window2 = taco::WebViewBuilder {
             x: 1,
             y: 1,
             width: 300,
@@ -20,18 +20,25 @@ fn main() -> taco::Result<()> {
             ..Default::default()
         }
         .build()?;
-        wrun2.run(())
+        window2.run(())
     });
 
     let mut count = 0;
     let counter = Arc::new(Mutex::new(0));
     let c = counter.clone();
-    let (webview, mut wrun, whandle) = taco::WebViewBuilder {
+    let mut window = taco::WebViewBuilder {
         title: "たいとるです",
         autosize: true,
         ..Default::default()
     }
+    .with_assets(
+        [("main.html", include_bytes!("../web/main.html").as_slice())]
+            .into_iter()
+            .collect(),
+    )
     .build()?;
+    let webview = window.webview().clone();
+    let whandle = window.handle();
 
     webview.bind("hostCallback", move |request| {
         if let [Value::String(str), Value::Number(a), Value::Number(b)] = &request[..] {
@@ -60,7 +67,7 @@ fn main() -> taco::Result<()> {
     // NOTE: Navigate and show manually after calling "bind".
     // This makes the page visible with bindings already available.
     webview
-        .navigate("C:\\Users\\haruo\\projects\\taco\\web\\main.html")?
+        .navigate(&format!("{}/main.html", taco::assets::ASSET_ORIGIN))?
         .set_visible(true)?;
 
     spawn(move || {
@@ -110,12 +117,12 @@ fn main() -> taco::Result<()> {
         // });
     });
 
-    wrun.add_event_listener(WM_KEYDOWN, move |_, _| {
+    window.add_event_listener(WM_KEYDOWN, move |_, _, _| {
         webview.eval("console.log('ぴゃあ')").unwrap();
         count += 1;
         println!("かー {}", count);
     });
 
     // Off we go....
-    wrun.run(88888)
+    window.run(88888)
 }
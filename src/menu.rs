@@ -0,0 +1,83 @@
+use serde::Deserialize;
+use serde_json::Value;
+use windows::Win32::{
+    Foundation::{HWND, POINT},
+    UI::WindowsAndMessaging::*,
+};
+
+use crate::command::{Command, CommandSource};
+
+// WM_COMMAND ids of menu items (and hotkey ids) start here, so they don't collide with
+// ids chosen by the host app.
+pub const COMMAND_ID_BASE: usize = 0x1000;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct MenuItem {
+    pub label: String,
+    pub command: Option<String>,
+    // Passed to the command's handler.
+    pub payload: Value,
+    pub items: Vec<MenuItem>,
+    pub separator: bool,
+}
+
+// Each item with a command gets the id `COMMAND_ID_BASE + i`, where `i` is the index
+// of its command pushed onto `commands`.
+pub fn build_menu(
+    items: &[MenuItem],
+    popup: bool,
+    source: &CommandSource,
+    commands: &mut Vec<Command>,
+) -> HMENU {
+    unsafe {
+        let menu = if popup {
+            CreatePopupMenu()
+        } else {
+            CreateMenu()
+        };
+        for item in items {
+            if item.separator {
+                AppendMenuW(menu, MF_SEPARATOR, 0, None);
+            } else if !item.items.is_empty() {
+                let submenu = build_menu(&item.items, true, source, commands);
+                AppendMenuW(menu, MF_POPUP, submenu.0 as _, item.label.as_str());
+            } else if let Some(command) = &item.command {
+                AppendMenuW(
+                    menu,
+                    MF_STRING,
+                    COMMAND_ID_BASE + commands.len(),
+                    item.label.as_str(),
+                );
+                commands.push(Command {
+                    id: command.clone(),
+                    payload: item.payload.clone(),
+                    source: source.clone(),
+                });
+            } else {
+                AppendMenuW(menu, MF_STRING | MF_GRAYED, 0, item.label.as_str());
+            }
+        }
+        menu
+    }
+}
+
+// Shows a popup menu at the cursor; the chosen item is sent to `hwnd` as WM_COMMAND.
+pub fn show_popup_menu(hwnd: HWND, menu: HMENU) {
+    unsafe {
+        let mut point = POINT::default();
+        GetCursorPos(&mut point);
+        // Needed so that the menu closes when clicking elsewhere.
+        SetForegroundWindow(hwnd);
+        TrackPopupMenu(
+            menu,
+            TPM_RIGHTBUTTON,
+            point.x,
+            point.y,
+            0,
+            hwnd,
+            std::ptr::null(),
+        );
+        PostMessageA(hwnd, WM_NULL, None, None);
+    }
+}
@@ -0,0 +1,147 @@
+use std::{path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{Result, WebView};
+
+// How long frames are counted for `PerformanceMetrics::fps`.
+const FPS_SAMPLE_MS: u32 = 500;
+
+// Milliseconds since navigation start, from the page's PerformanceNavigationTiming entry.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NavigationTiming {
+    pub response_start: f64,
+    pub response_end: f64,
+    pub dom_interactive: f64,
+    pub dom_content_loaded_event_end: f64,
+    pub load_event_end: f64,
+    pub transfer_size: u64,
+}
+
+// What `WebView::profile` recorded.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Profile {
+    // A CDP Profiler.Profile, the format of DevTools' .cpuprofile files.
+    pub cpu_profile: Value,
+    // CDP Profiler.ScriptCoverage entries with precise call counts per function and block.
+    pub coverage: Vec<Value>,
+}
+
+impl Profile {
+    // Can be opened in the Performance panel of DevTools.
+    pub fn write_cpuprofile(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec(&self.cpu_profile)?)?;
+        Ok(())
+    }
+}
+
+// A snapshot of CDP `Performance.getMetrics`, plus a short FPS sample and navigation timing.
+// Durations are in seconds, as reported by CDP.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PerformanceMetrics {
+    pub timestamp: f64,
+    pub documents: u64,
+    pub frames: u64,
+    pub nodes: u64,
+    pub js_event_listeners: u64,
+    pub layout_count: u64,
+    pub recalc_style_count: u64,
+    pub layout_duration: f64,
+    pub recalc_style_duration: f64,
+    pub script_duration: f64,
+    pub task_duration: f64,
+    pub js_heap_used_size: u64,
+    pub js_heap_total_size: u64,
+    pub fps: Option<f64>,
+    pub navigation: Option<NavigationTiming>,
+}
+
+impl WebView {
+    // Blocks for about FPS_SAMPLE_MS while frames are counted.
+    pub fn performance_metrics(&self) -> Result<PerformanceMetrics> {
+        self.call_devtools_method("Performance.enable", json!({}))?;
+        let result = self.call_devtools_method("Performance.getMetrics", json!({}))?;
+
+        let mut metrics = PerformanceMetrics::default();
+        for metric in result["metrics"].as_array().into_iter().flatten() {
+            let value = metric["value"].as_f64().unwrap_or_default();
+            match metric["name"].as_str().unwrap_or_default() {
+                "Timestamp" => metrics.timestamp = value,
+                "Documents" => metrics.documents = value as u64,
+                "Frames" => metrics.frames = value as u64,
+                "Nodes" => metrics.nodes = value as u64,
+                "JSEventListeners" => metrics.js_event_listeners = value as u64,
+                "LayoutCount" => metrics.layout_count = value as u64,
+                "RecalcStyleCount" => metrics.recalc_style_count = value as u64,
+                "LayoutDuration" => metrics.layout_duration = value,
+                "RecalcStyleDuration" => metrics.recalc_style_duration = value,
+                "ScriptDuration" => metrics.script_duration = value,
+                "TaskDuration" => metrics.task_duration = value,
+                "JSHeapUsedSize" => metrics.js_heap_used_size = value as u64,
+                "JSHeapTotalSize" => metrics.js_heap_total_size = value as u64,
+                _ => {}
+            }
+        }
+
+        metrics.fps = self
+            .evaluate_in_page(&format!(
+                r#"new Promise(resolve => {{
+                    let frames = 0;
+                    const start = performance.now();
+                    const tick = now => {{
+                        frames++;
+                        if (now - start < {}) requestAnimationFrame(tick);
+                        else resolve(frames * 1000 / (now - start));
+                    }};
+                    requestAnimationFrame(tick);
+                }})"#,
+                FPS_SAMPLE_MS
+            ))?
+            .as_f64();
+
+        let navigation = self.evaluate_in_page(
+            "(() => { const e = performance.getEntriesByType('navigation')[0]; return e ? e.toJSON() : null; })()",
+        )?;
+        metrics.navigation = serde_json::from_value(navigation).ok();
+
+        Ok(metrics)
+    }
+
+    // Records a CPU profile and precise JS coverage while the page runs for `duration`.
+    // Messages are pumped meanwhile, so the app stays responsive.
+    pub fn profile(&self, duration: Duration) -> Result<Profile> {
+        self.call_devtools_method("Profiler.enable", json!({}))?;
+        self.call_devtools_method(
+            "Profiler.startPreciseCoverage",
+            json!({ "callCount": true, "detailed": true }),
+        )?;
+        self.call_devtools_method("Profiler.start", json!({}))?;
+
+        crate::pump_for(duration)?;
+
+        let coverage = self.call_devtools_method("Profiler.takePreciseCoverage", json!({}))?;
+        let profile = self.call_devtools_method("Profiler.stop", json!({}))?;
+        self.call_devtools_method("Profiler.stopPreciseCoverage", json!({}))?;
+        self.call_devtools_method("Profiler.disable", json!({}))?;
+
+        Ok(Profile {
+            cpu_profile: profile["profile"].clone(),
+            coverage: coverage["result"].as_array().cloned().unwrap_or_default(),
+        })
+    }
+
+    // Runs `expression` with CDP Runtime.evaluate, awaiting promises, and returns its value.
+    pub(crate) fn evaluate_in_page(&self, expression: &str) -> Result<Value> {
+        let result = self.call_devtools_method(
+            "Runtime.evaluate",
+            json!({
+                "expression": expression,
+                "awaitPromise": true,
+                "returnByValue": true,
+            }),
+        )?;
+        Ok(result["result"]["value"].clone())
+    }
+}
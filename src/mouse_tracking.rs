@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+
+use serde_json::json;
+use windows::Win32::{
+    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, POINT, WPARAM},
+    Graphics::Gdi::ScreenToClient,
+    UI::WindowsAndMessaging::*,
+};
+
+use crate::{dpi_ratio, thread::WebViewHandle, Error, Result, WebView};
+
+// One low-level hook per thread, shared by its tracking WebViews.
+#[derive(Default)]
+struct Tracking {
+    hook: HHOOK,
+    webviews: Vec<WebViewHandle>,
+    // Moves are coalesced into the latest position until the window threads get to them.
+    pending_move: Option<POINT>,
+}
+
+thread_local! {
+    static TRACKING: RefCell<Tracking> = RefCell::new(Tracking::default());
+}
+
+fn emit(webview: &WebView, kind: &str, button: Option<&str>, point: POINT) -> Result<()> {
+    let mut client = point;
+    unsafe { ScreenToClient(webview.hwnd, &mut client) };
+    let ratio = dpi_ratio(webview.hwnd);
+    webview.emit(
+        "globalMouse",
+        json!({
+            "type": kind,
+            "button": button,
+            "x": client.x as f64 / ratio,
+            "y": client.y as f64 / ratio,
+            "screenX": point.x,
+            "screenY": point.y,
+        }),
+    )?;
+    Ok(())
+}
+
+fn deliver(kind: &'static str, button: Option<&'static str>, point: POINT) {
+    let webviews = TRACKING.with(|t| t.borrow().webviews.clone());
+    let count = webviews.len();
+    for (i, handle) in webviews.into_iter().enumerate() {
+        let dispatched = if kind == "move" {
+            // Posted messages of a thread run in order, so the last window clears the position
+            // after the others have read it.
+            let last = i + 1 == count;
+            handle.dispatch(move |w| {
+                let point = TRACKING.with(|t| {
+                    let mut t = t.borrow_mut();
+                    if last {
+                        t.pending_move.take()
+                    } else {
+                        t.pending_move
+                    }
+                });
+                match point {
+                    Some(point) => emit(w, "move", None, point),
+                    None => Ok(()),
+                }
+            })
+        } else {
+            handle.dispatch(move |w| emit(w, kind, button, point))
+        };
+        // The window is gone.
+        if dispatched.is_err() {
+            untrack(handle.hwnd);
+            if i + 1 == count {
+                TRACKING.with(|t| t.borrow_mut().pending_move = None);
+            }
+        }
+    }
+}
+
+// Runs on the thread that installed it, between its messages, so it only hands events on.
+unsafe extern "system" fn hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code >= 0 {
+        let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+        let event = match wparam.0 as u32 {
+            WM_MOUSEMOVE => Some(("move", None)),
+            WM_LBUTTONDOWN => Some(("down", Some("left"))),
+            WM_LBUTTONUP => Some(("up", Some("left"))),
+            WM_RBUTTONDOWN => Some(("down", Some("right"))),
+            WM_RBUTTONUP => Some(("up", Some("right"))),
+            WM_MBUTTONDOWN => Some(("down", Some("middle"))),
+            WM_MBUTTONUP => Some(("up", Some("middle"))),
+            _ => None,
+        };
+        if let Some((kind, button)) = event {
+            let first_move = kind == "move"
+                && TRACKING.with(|t| t.borrow_mut().pending_move.replace(info.pt).is_none());
+            if kind != "move" || first_move {
+                deliver(kind, button, info.pt);
+            }
+        }
+    }
+    CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+}
+
+fn untrack(hwnd: HWND) {
+    TRACKING.with(|t| {
+        let mut t = t.borrow_mut();
+        t.webviews.retain(|handle| handle.hwnd != hwnd);
+        if t.webviews.is_empty() && !t.hook.is_invalid() {
+            unsafe { UnhookWindowsHookEx(t.hook) };
+            t.hook = HHOOK::default();
+        }
+    });
+}
+
+impl WebView {
+    // Reports the mouse to the page as `taco.on('globalMouse', e => ...)` even while it's
+    // outside the window or the window is click-through, e.g. for a crosshair overlay. `e` has
+    // `type` ('move', 'down' or 'up'), `button`, `x`/`y` in CSS pixels relative to the webview
+    // and `screenX`/`screenY` in physical pixels. Moves are coalesced to the latest position.
+    pub fn set_global_mouse_tracking(&self, enabled: bool) -> Result<()> {
+        if !enabled {
+            untrack(self.hwnd);
+            return Ok(());
+        }
+        TRACKING.with(|t| {
+            let mut t = t.borrow_mut();
+            if t.hook.is_invalid() {
+                t.hook =
+                    unsafe { SetWindowsHookExA(WH_MOUSE_LL, Some(hook), HINSTANCE::default(), 0) };
+                if t.hook.is_invalid() {
+                    return Err(Error::from(windows::core::Error::from_win32()));
+                }
+            }
+            let handle = self.handle();
+            if !t.webviews.contains(&handle) {
+                t.webviews.push(handle);
+            }
+            Ok(())
+        })
+    }
+}
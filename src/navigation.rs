@@ -0,0 +1,322 @@
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::{BOOL, PWSTR},
+        System::WinRT::EventRegistrationToken,
+        UI::WindowsAndMessaging::SetWindowTextW,
+    },
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{event::Subscription, Result, WebView};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    Allow,
+    Cancel,
+}
+
+#[derive(Clone, Debug)]
+pub struct NavigationStartingArgs {
+    pub uri: String,
+    pub navigation_id: u64,
+    pub is_user_initiated: bool,
+    pub is_redirected: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ContentLoadingArgs {
+    pub navigation_id: u64,
+    pub is_error_page: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DomContentLoadedArgs {
+    pub navigation_id: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct SourceChangedArgs {
+    pub uri: String,
+    // False for fragment navigations and history.pushState within the same document.
+    pub is_new_document: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct NavigationCompletedArgs {
+    pub navigation_id: u64,
+    pub is_success: bool,
+    // A COREWEBVIEW2_WEB_ERROR_STATUS value, 0 when unknown.
+    pub web_error_status: i32,
+}
+
+// These also fire for navigations started by the page itself, unlike `navigate` which
+// only waits for its own.
+impl WebView {
+    pub fn on_navigation_starting<F>(&self, mut f: F) -> Result<Subscription>
+    where
+        F: FnMut(&NavigationStartingArgs) -> Decision + 'static,
+    {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core.NavigationStarting(
+                NavigationStartingEventHandler::create(Box::new(
+                    move |_core, args: Option<ICoreWebView2NavigationStartingEventArgs>| {
+                        if let Some(args) = args {
+                            let mut uri = PWSTR::default();
+                            let mut navigation_id = 0;
+                            let mut is_user_initiated = BOOL::default();
+                            let mut is_redirected = BOOL::default();
+                            args.Uri(&mut uri)?;
+                            args.NavigationId(&mut navigation_id)?;
+                            args.IsUserInitiated(&mut is_user_initiated)?;
+                            args.IsRedirected(&mut is_redirected)?;
+
+                            let decision = f(&NavigationStartingArgs {
+                                uri: take_pwstr(uri),
+                                navigation_id,
+                                is_user_initiated: is_user_initiated.as_bool(),
+                                is_redirected: is_redirected.as_bool(),
+                            });
+                            if decision == Decision::Cancel {
+                                args.SetCancel(true)?;
+                            }
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut token,
+            )?;
+        }
+        let core = self.core.clone();
+        Ok(self.track(move || unsafe { core.RemoveNavigationStarting(token) }))
+    }
+
+    pub fn on_content_loading(
+        &self,
+        mut f: impl FnMut(&ContentLoadingArgs) + 'static,
+    ) -> Result<Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core.ContentLoading(
+                ContentLoadingEventHandler::create(Box::new(
+                    move |_core, args: Option<ICoreWebView2ContentLoadingEventArgs>| {
+                        if let Some(args) = args {
+                            let mut navigation_id = 0;
+                            let mut is_error_page = BOOL::default();
+                            args.NavigationId(&mut navigation_id)?;
+                            args.IsErrorPage(&mut is_error_page)?;
+                            f(&ContentLoadingArgs {
+                                navigation_id,
+                                is_error_page: is_error_page.as_bool(),
+                            });
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut token,
+            )?;
+        }
+        let core = self.core.clone();
+        Ok(self.track(move || unsafe { core.RemoveContentLoading(token) }))
+    }
+
+    pub fn on_dom_content_loaded(
+        &self,
+        mut f: impl FnMut(&DomContentLoadedArgs) + 'static,
+    ) -> Result<Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core.cast::<ICoreWebView2_2>()?.DOMContentLoaded(
+                DOMContentLoadedEventHandler::create(Box::new(
+                    move |_core, args: Option<ICoreWebView2DOMContentLoadedEventArgs>| {
+                        if let Some(args) = args {
+                            let mut navigation_id = 0;
+                            args.NavigationId(&mut navigation_id)?;
+                            f(&DomContentLoadedArgs { navigation_id });
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut token,
+            )?;
+        }
+        let core: ICoreWebView2_2 = self.core.cast()?;
+        Ok(self.track(move || unsafe { core.RemoveDOMContentLoaded(token) }))
+    }
+
+    pub fn on_source_changed(
+        &self,
+        mut f: impl FnMut(&SourceChangedArgs) + 'static,
+    ) -> Result<Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core.SourceChanged(
+                SourceChangedEventHandler::create(Box::new(
+                    move |core: Option<ICoreWebView2>,
+                          args: Option<ICoreWebView2SourceChangedEventArgs>| {
+                        if let (Some(core), Some(args)) = (core, args) {
+                            let mut uri = PWSTR::default();
+                            let mut is_new_document = BOOL::default();
+                            core.Source(&mut uri)?;
+                            args.IsNewDocument(&mut is_new_document)?;
+                            f(&SourceChangedArgs {
+                                uri: take_pwstr(uri),
+                                is_new_document: is_new_document.as_bool(),
+                            });
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut token,
+            )?;
+        }
+        let core = self.core.clone();
+        Ok(self.track(move || unsafe { core.RemoveSourceChanged(token) }))
+    }
+
+    pub fn on_navigation_completed(
+        &self,
+        f: impl FnMut(&NavigationCompletedArgs) + 'static,
+    ) -> Result<Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core
+                .NavigationCompleted(navigation_completed_handler(f), &mut token)?
+        };
+        let core = self.core.clone();
+        Ok(self.track(move || unsafe { core.RemoveNavigationCompleted(token) }))
+    }
+
+    // The same for navigations of the page's iframes.
+    pub fn on_frame_navigation_completed(
+        &self,
+        f: impl FnMut(&NavigationCompletedArgs) + 'static,
+    ) -> Result<Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core
+                .FrameNavigationCompleted(navigation_completed_handler(f), &mut token)?
+        };
+        let core = self.core.clone();
+        Ok(self.track(move || unsafe { core.RemoveFrameNavigationCompleted(token) }))
+    }
+}
+
+fn navigation_completed_handler(
+    mut f: impl FnMut(&NavigationCompletedArgs) + 'static,
+) -> ICoreWebView2NavigationCompletedEventHandler {
+    NavigationCompletedEventHandler::create(Box::new(
+        move |_core, args: Option<ICoreWebView2NavigationCompletedEventArgs>| {
+            if let Some(args) = args {
+                let mut navigation_id = 0;
+                let mut is_success = BOOL::default();
+                let mut web_error_status = COREWEBVIEW2_WEB_ERROR_STATUS::default();
+                unsafe {
+                    args.NavigationId(&mut navigation_id)?;
+                    args.IsSuccess(&mut is_success)?;
+                    args.WebErrorStatus(&mut web_error_status)?;
+                }
+                f(&NavigationCompletedArgs {
+                    navigation_id,
+                    is_success: is_success.as_bool(),
+                    web_error_status,
+                });
+            }
+            Ok(())
+        },
+    ))
+}
+
+impl WebView {
+    pub fn go_back(&self) -> Result<&Self> {
+        unsafe { self.core.GoBack()? };
+        Ok(self)
+    }
+
+    pub fn go_forward(&self) -> Result<&Self> {
+        unsafe { self.core.GoForward()? };
+        Ok(self)
+    }
+
+    pub fn can_go_back(&self) -> Result<bool> {
+        let mut can = BOOL::default();
+        unsafe { self.core.CanGoBack(&mut can)? };
+        Ok(can.as_bool())
+    }
+
+    pub fn can_go_forward(&self) -> Result<bool> {
+        let mut can = BOOL::default();
+        unsafe { self.core.CanGoForward(&mut can)? };
+        Ok(can.as_bool())
+    }
+
+    pub fn reload(&self) -> Result<&Self> {
+        unsafe { self.core.Reload()? };
+        Ok(self)
+    }
+
+    pub fn stop(&self) -> Result<&Self> {
+        unsafe { self.core.Stop()? };
+        Ok(self)
+    }
+
+    // Fires when `can_go_back` / `can_go_forward` may have changed.
+    pub fn on_history_changed(&self, mut f: impl FnMut() + 'static) -> Result<Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core.HistoryChanged(
+                HistoryChangedEventHandler::create(Box::new(move |_core, _args| {
+                    f();
+                    Ok(())
+                })),
+                &mut token,
+            )?;
+        }
+        let core = self.core.clone();
+        Ok(self.track(move || unsafe { core.RemoveHistoryChanged(token) }))
+    }
+}
+
+impl WebView {
+    // The address of the current page.
+    pub fn url(&self) -> Result<String> {
+        let mut uri = PWSTR::default();
+        unsafe { self.core.Source(&mut uri)? };
+        Ok(take_pwstr(uri))
+    }
+
+    pub fn document_title(&self) -> Result<String> {
+        let mut title = PWSTR::default();
+        unsafe { self.core.DocumentTitle(&mut title)? };
+        Ok(take_pwstr(title))
+    }
+
+    pub fn on_title_changed(&self, mut f: impl FnMut(&str) + 'static) -> Result<Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core.DocumentTitleChanged(
+                DocumentTitleChangedEventHandler::create(Box::new(move |core, _args| {
+                    if let Some(core) = core {
+                        let mut title = PWSTR::default();
+                        core.DocumentTitle(&mut title)?;
+                        f(&take_pwstr(title));
+                    }
+                    Ok(())
+                })),
+                &mut token,
+            )?;
+        }
+        let core = self.core.clone();
+        Ok(self.track(move || unsafe { core.RemoveDocumentTitleChanged(token) }))
+    }
+
+    // Makes the native title bar follow `document.title`, like a browser does.
+    pub fn sync_title(&self) -> Result<Subscription> {
+        let hwnd = self.hwnd;
+        self.on_title_changed(move |title| unsafe {
+            SetWindowTextW(hwnd, title);
+        })
+    }
+}
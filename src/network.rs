@@ -0,0 +1,359 @@
+use std::{cell::RefCell, collections::HashMap, path::Path, rc::Rc};
+
+use serde_json::{json, Value};
+use windows::{
+    core::Interface,
+    Win32::{Foundation::PWSTR, System::Com::IStream, System::WinRT::EventRegistrationToken},
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{
+    event::Subscription,
+    protocol::{read_headers, read_stream},
+    Result, WebView,
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct ResponseInfo {
+    pub method: String,
+    pub uri: String,
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+    // Only read for `on_response_with_body`. None if the body isn't available (e.g. redirects).
+    pub body: Option<Vec<u8>>,
+}
+
+impl ResponseInfo {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// `*` matches any run of characters, like WebView2's own resource filters.
+pub fn matches_filter(filter: &str, uri: &str) -> bool {
+    let mut parts = filter.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match uri.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts = parts.collect::<Vec<_>>();
+    for (i, part) in parts.iter().enumerate() {
+        if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+unsafe fn read_response(
+    args: &ICoreWebView2WebResourceResponseReceivedEventArgs,
+) -> Result<(ResponseInfo, ICoreWebView2WebResourceResponseView)> {
+    let request = args.Request()?;
+    let response = args.Response()?;
+
+    let (mut method, mut uri, mut reason) = (PWSTR::default(), PWSTR::default(), PWSTR::default());
+    let mut status = 0;
+    request.Method(&mut method)?;
+    request.Uri(&mut uri)?;
+    response.StatusCode(&mut status)?;
+    response.ReasonPhrase(&mut reason)?;
+
+    let info = ResponseInfo {
+        method: take_pwstr(method),
+        uri: take_pwstr(uri),
+        status: status as u16,
+        reason: take_pwstr(reason),
+        headers: read_headers(response.Headers()?.GetIterator()?)?,
+        body: None,
+    };
+    Ok((info, response))
+}
+
+impl WebView {
+    // Called for every response whose URI matches `filter`, e.g. to catch 401s from an API.
+    pub fn on_response(
+        &self,
+        filter: &str,
+        f: impl FnMut(&ResponseInfo) + 'static,
+    ) -> Result<Subscription> {
+        self.response_received(filter, false, f)
+    }
+
+    // Like `on_response`, but waits for and includes the response body.
+    pub fn on_response_with_body(
+        &self,
+        filter: &str,
+        f: impl FnMut(&ResponseInfo) + 'static,
+    ) -> Result<Subscription> {
+        self.response_received(filter, true, f)
+    }
+
+    fn response_received(
+        &self,
+        filter: &str,
+        with_body: bool,
+        f: impl FnMut(&ResponseInfo) + 'static,
+    ) -> Result<Subscription> {
+        let filter = filter.to_string();
+        let f = Rc::new(RefCell::new(f));
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core.cast::<ICoreWebView2_2>()?.WebResourceResponseReceived(
+                WebResourceResponseReceivedEventHandler::create(Box::new(
+                    move |_core, args: Option<ICoreWebView2WebResourceResponseReceivedEventArgs>| {
+                        let args = match args {
+                            Some(args) => args,
+                            None => return Ok(()),
+                        };
+                        let (mut info, response) = match read_response(&args) {
+                            Ok(response) => response,
+                            Err(_) => return Ok(()),
+                        };
+                        if !matches_filter(&filter, &info.uri) {
+                            return Ok(());
+                        }
+                        if !with_body {
+                            (f.borrow_mut())(&info);
+                            return Ok(());
+                        }
+
+                        let f = f.clone();
+                        response.GetContent(WebResourceResponseViewGetContentCompletedHandler::create(
+                            Box::new(move |result, stream: Option<IStream>| {
+                                if result.is_ok() {
+                                    info.body = stream.and_then(|stream| read_stream(&stream).ok());
+                                }
+                                (f.borrow_mut())(&info);
+                                Ok(())
+                            }),
+                        ))
+                    },
+                )),
+                &mut token,
+            )?;
+        }
+        let core: ICoreWebView2_2 = self.core.cast()?;
+        Ok(self.track(move || unsafe { core.RemoveWebResourceResponseReceived(token) }))
+    }
+}
+
+// Network activity recorded between `start_network_capture` and `stop_and_export_har`.
+#[derive(Default)]
+pub(crate) struct Capture {
+    installed: bool,
+    recording: bool,
+    entries: Vec<Value>,
+    // Request id -> (index into entries, CDP timestamp when the request was sent).
+    open: HashMap<String, (usize, f64)>,
+}
+
+impl Capture {
+    fn start_entry(&mut self, params: &Value) {
+        let id = params["requestId"].as_str().unwrap_or_default().to_string();
+        let request = &params["request"];
+        let wall_time = params["wallTime"].as_f64().unwrap_or_default();
+        let post_data = request["postData"].as_str();
+
+        let mut entry = json!({
+            "startedDateTime": iso8601(wall_time),
+            "time": 0,
+            "request": {
+                "method": request["method"],
+                "url": request["url"],
+                "httpVersion": "",
+                "headers": har_headers(&request["headers"]),
+                "queryString": [],
+                "cookies": [],
+                "headersSize": -1,
+                "bodySize": post_data.map_or(0, str::len),
+            },
+            "response": {
+                "status": 0,
+                "statusText": "",
+                "httpVersion": "",
+                "headers": [],
+                "cookies": [],
+                "content": { "size": 0, "mimeType": "" },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": -1,
+            },
+            "cache": {},
+            "timings": { "send": 0, "wait": 0, "receive": 0 },
+        });
+        if let Some(text) = post_data {
+            entry["request"]["postData"] = json!({
+                "mimeType": request["headers"]["Content-Type"].as_str().unwrap_or_default(),
+                "text": text,
+            });
+        }
+
+        // A redirect reuses the request id, so the previous hop ends here.
+        if let Some(response) = params.get("redirectResponse") {
+            self.response(&id, response, params["timestamp"].as_f64());
+            if let Some((index, _)) = self.open.remove(&id) {
+                self.entries[index]["response"]["redirectURL"] = request["url"].clone();
+            }
+        }
+
+        let timestamp = params["timestamp"].as_f64().unwrap_or_default();
+        self.open.insert(id, (self.entries.len(), timestamp));
+        self.entries.push(entry);
+    }
+
+    fn response(&mut self, id: &str, response: &Value, timestamp: Option<f64>) {
+        let (index, started) = match self.open.get(id) {
+            Some(open) => *open,
+            None => return,
+        };
+        let entry = &mut self.entries[index];
+        entry["request"]["httpVersion"] = response["protocol"].clone();
+        entry["response"]["status"] = response["status"].clone();
+        entry["response"]["statusText"] = response["statusText"].clone();
+        entry["response"]["httpVersion"] = response["protocol"].clone();
+        entry["response"]["headers"] = har_headers(&response["headers"]);
+        entry["response"]["content"]["mimeType"] = response["mimeType"].clone();
+        if let Some(timestamp) = timestamp {
+            entry["timings"]["wait"] = json!((timestamp - started) * 1000.);
+        }
+    }
+
+    fn finish(&mut self, params: &Value, error: Option<&str>) {
+        let id = params["requestId"].as_str().unwrap_or_default();
+        let (index, started) = match self.open.remove(id) {
+            Some(open) => open,
+            None => return,
+        };
+        let entry = &mut self.entries[index];
+        let time = (params["timestamp"].as_f64().unwrap_or(started) - started) * 1000.;
+        let wait = entry["timings"]["wait"].as_f64().unwrap_or_default();
+        entry["time"] = json!(time);
+        entry["timings"]["receive"] = json!((time - wait).max(0.));
+        if let Some(length) = params["encodedDataLength"].as_f64() {
+            entry["response"]["bodySize"] = json!(length);
+            entry["response"]["content"]["size"] = json!(length);
+        }
+        if let Some(error) = error {
+            entry["response"]["_error"] = json!(error);
+        }
+    }
+
+    fn har(&self) -> Value {
+        json!({
+            "log": {
+                "version": "1.2",
+                "creator": { "name": "taco", "version": env!("CARGO_PKG_VERSION") },
+                "pages": [],
+                "entries": self.entries,
+            }
+        })
+    }
+}
+
+fn har_headers(headers: &Value) -> Value {
+    let headers = headers.as_object().map(|headers| {
+        headers
+            .iter()
+            .map(|(name, value)| json!({ "name": name, "value": value.as_str().unwrap_or_default() }))
+            .collect::<Vec<_>>()
+    });
+    json!(headers.unwrap_or_default())
+}
+
+// Seconds since the Unix epoch -> `2021-01-02T03:04:05.678Z`.
+fn iso8601(seconds: f64) -> String {
+    let millis = (seconds * 1000.) as i64;
+    let (days, ms) = (millis.div_euclid(86_400_000), millis.rem_euclid(86_400_000));
+
+    // Howard Hinnant's civil_from_days.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+impl WebView {
+    // Records requests from now on, for `stop_and_export_har`. Restarting clears the recording.
+    pub fn start_network_capture(&self) -> Result<()> {
+        let install = {
+            let mut capture = self.network_capture.borrow_mut();
+            capture.recording = true;
+            capture.entries.clear();
+            capture.open.clear();
+            !std::mem::replace(&mut capture.installed, true)
+        };
+        if !install {
+            return Ok(());
+        }
+
+        self.call_devtools_method("Network.enable", json!({}))?;
+
+        let c = self.network_capture.clone();
+        self.on_devtools_event("Network.requestWillBeSent", move |params| {
+            let mut capture = c.borrow_mut();
+            if capture.recording {
+                capture.start_entry(&params);
+            }
+        })?;
+
+        let c = self.network_capture.clone();
+        self.on_devtools_event("Network.responseReceived", move |params| {
+            let id = params["requestId"].as_str().unwrap_or_default();
+            c.borrow_mut()
+                .response(id, &params["response"], params["timestamp"].as_f64());
+        })?;
+
+        let c = self.network_capture.clone();
+        self.on_devtools_event("Network.loadingFinished", move |params| {
+            c.borrow_mut().finish(&params, None);
+        })?;
+
+        let c = self.network_capture.clone();
+        self.on_devtools_event("Network.loadingFailed", move |params| {
+            let error = params["errorText"].as_str().unwrap_or("failed").to_string();
+            c.borrow_mut().finish(&params, Some(&error));
+        })?;
+
+        Ok(())
+    }
+
+    // Writes everything recorded since `start_network_capture` as a HAR 1.2 file.
+    pub fn stop_and_export_har(&self, path: impl AsRef<Path>) -> Result<()> {
+        let har = {
+            let mut capture = self.network_capture.borrow_mut();
+            capture.recording = false;
+            let har = capture.har();
+            capture.entries.clear();
+            capture.open.clear();
+            har
+        };
+        std::fs::write(path, serde_json::to_vec_pretty(&har)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+(function () {
+    const notifications = {};
+
+    class TacoNotification extends EventTarget {
+        constructor(title, options = {}) {
+            super();
+            this.title = title;
+            this.body = options.body || '';
+            this.tag = options.tag || '';
+            this.data = options.data;
+            this.onclick = null;
+            this.onshow = null;
+            this.onclose = null;
+            this.onerror = null;
+
+            _taco_showNotification(this.title, this.body)
+                .then(id => {
+                    notifications[id] = this;
+                    this._fire('show');
+                })
+                .catch(() => this._fire('error'));
+        }
+
+        _fire(type) {
+            const event = new Event(type);
+            this.dispatchEvent(event);
+            if (this['on' + type]) this['on' + type](event);
+        }
+
+        close() {
+            this._fire('close');
+        }
+
+        static get permission() {
+            return 'granted';
+        }
+
+        static requestPermission(callback) {
+            if (callback) callback('granted');
+            return Promise.resolve('granted');
+        }
+    }
+
+    window.Notification = TacoNotification;
+
+    window._taco_notificationClicked = function (id) {
+        const notification = notifications[id];
+        delete notifications[id];
+        if (notification) notification._fire('click');
+    };
+})();
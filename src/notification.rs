@@ -0,0 +1,97 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use serde_json::Value;
+use windows::Win32::{
+    Foundation::{HWND, LPARAM, WPARAM},
+    UI::{Shell::*, WindowsAndMessaging::*},
+};
+
+use crate::{
+    tray::{self, TrayIcon, WM_TRAYICON},
+    window::WindowRunner,
+    Result, WebView,
+};
+
+const NOTIFICATION_TRAY_ID: u32 = 1;
+
+// Native notifications are shown as balloons of a tray icon owned by the window,
+// which Windows 10 and later render as toasts in the action center.
+pub struct Notifier {
+    tray: TrayIcon,
+    next_id: Cell<u32>,
+    shown: Cell<Option<u32>>,
+}
+
+impl Notifier {
+    pub fn new(hwnd: HWND, tooltip: &str) -> Result<Self> {
+        let icon = tray::load_icon(None);
+        Ok(Self {
+            tray: TrayIcon::new(hwnd, NOTIFICATION_TRAY_ID, icon, tooltip)?,
+            next_id: Cell::new(1),
+            shown: Cell::new(None),
+        })
+    }
+
+    pub fn show(&self, title: &str, body: &str) -> Result<u32> {
+        self.tray.show_balloon(title, body)?;
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.shown.set(Some(id));
+        Ok(id)
+    }
+
+    // Returns the id of the clicked notification, given the parameters of a WM_TRAYICON message.
+    pub fn clicked(&self, wparam: WPARAM, lparam: LPARAM) -> Option<u32> {
+        if wparam.0 as u32 != self.tray.id() {
+            return None;
+        }
+        match lparam.0 as u32 {
+            NIN_BALLOONUSERCLICK => self.shown.take(),
+            NIN_BALLOONHIDE | NIN_BALLOONTIMEOUT => {
+                self.shown.set(None);
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+// Replaces `window.Notification` in the page so that web notifications become native ones,
+// with clicks routed back to the `onclick` handler of the originating Notification object.
+pub(crate) fn bridge<T: 'static>(
+    webview: &WebView,
+    wrun: &mut WindowRunner<T>,
+    tooltip: &str,
+) -> Result<()> {
+    let notifier = Rc::new(Notifier::new(webview.hwnd, tooltip)?);
+
+    let n = notifier.clone();
+    webview.bind_unsafe("_taco_showNotification", move |params| {
+        if let [Value::String(title), Value::String(body)] = &params[..] {
+            return n
+                .show(title, body)
+                .map(Value::from)
+                .map_err(|err| err.to_string());
+        }
+        Err("Usage: _taco_showNotification(title, body)".into())
+    });
+    webview.init(include_str!("notification.js"))?;
+
+    let w = webview.clone();
+    wrun.add_event_listener(WM_TRAYICON, move |wparam, lparam, _| {
+        if let Some(id) = notifier.clicked(wparam, lparam) {
+            unsafe {
+                ShowWindow(w.hwnd, SW_RESTORE);
+                SetForegroundWindow(w.hwnd);
+            }
+            // Not waiting inside the window procedure; a page that navigated away meanwhile has
+            // nobody left to tell.
+            let _ = w
+                .handle()
+                .eval(format!("window._taco_notificationClicked({})", id));
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use windows::Win32::{
+    Foundation::{HWND, RECT},
+    Graphics::Gdi::{BeginPaint, EndPaint, InvalidateRect, HDC, PAINTSTRUCT},
+    UI::WindowsAndMessaging::{GetClientRect, WM_PAINT, WM_SIZE},
+};
+
+use crate::{window::Window, WebView};
+
+// Client area left uncovered by the webview on each side, in physical pixels, for native
+// chrome drawn with `Window::on_paint`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Insets {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Insets {
+    pub(crate) fn apply(&self, width: i32, height: i32) -> RECT {
+        RECT {
+            left: self.left,
+            top: self.top,
+            right: (width - self.right).max(self.left),
+            bottom: (height - self.bottom).max(self.top),
+        }
+    }
+}
+
+pub struct PaintContext {
+    // From BeginPaint; wrap it in an ID2D1DCRenderTarget for Direct2D.
+    pub hdc: HDC,
+    pub client: RECT,
+    // Where the webview is; anything drawn there is hidden behind it.
+    pub webview: RECT,
+    // The part that needs repainting.
+    pub dirty: RECT,
+}
+
+fn client_rect(hwnd: HWND) -> RECT {
+    let mut rect = RECT::default();
+    unsafe { GetClientRect(hwnd, &mut rect) };
+    rect
+}
+
+impl WebView {
+    // Shrinks the webview to leave `insets` of the client area to the host window, and has it
+    // repainted. Kept through resizes.
+    pub fn set_insets(&self, insets: Insets) {
+        self.insets.set(insets);
+        let client = client_rect(self.hwnd);
+        self.set_webview_size(client.right, client.bottom);
+        unsafe { InvalidateRect(self.hwnd, std::ptr::null(), false) };
+    }
+
+    pub fn insets(&self) -> Insets {
+        self.insets.get()
+    }
+}
+
+impl<T: 'static> Window<T> {
+    // Draws the parts of the client area outside the webview, see `WebView::set_insets`.
+    // Call InvalidateRect on the window to have `f` run again.
+    pub fn on_paint(&mut self, mut f: impl FnMut(&PaintContext) + 'static) {
+        let hwnd = self.webview().hwnd;
+        let w = self.webview().clone();
+        self.add_event_listener(WM_PAINT, move |_, _, _| {
+            let mut ps = PAINTSTRUCT::default();
+            let hdc = unsafe { BeginPaint(hwnd, &mut ps) };
+            let client = client_rect(hwnd);
+            f(&PaintContext {
+                hdc,
+                client,
+                webview: w.insets.get().apply(client.right, client.bottom),
+                dirty: ps.rcPaint,
+            });
+            unsafe { EndPaint(hwnd, &ps) };
+        });
+        // The window class doesn't redraw on resize by itself.
+        self.add_event_listener(WM_SIZE, move |_, _, _| unsafe {
+            InvalidateRect(hwnd, std::ptr::null(), false);
+        });
+    }
+}
@@ -0,0 +1,88 @@
+use windows::Win32::{Foundation::PWSTR, System::WinRT::EventRegistrationToken};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{event::Subscription, Result, WebView};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PermissionKind {
+    Microphone,
+    Camera,
+    Geolocation,
+    Notifications,
+    OtherSensors,
+    ClipboardRead,
+    Unknown,
+}
+
+impl PermissionKind {
+    fn from_raw(kind: COREWEBVIEW2_PERMISSION_KIND) -> Self {
+        match kind {
+            COREWEBVIEW2_PERMISSION_KIND_MICROPHONE => Self::Microphone,
+            COREWEBVIEW2_PERMISSION_KIND_CAMERA => Self::Camera,
+            COREWEBVIEW2_PERMISSION_KIND_GEOLOCATION => Self::Geolocation,
+            COREWEBVIEW2_PERMISSION_KIND_NOTIFICATIONS => Self::Notifications,
+            COREWEBVIEW2_PERMISSION_KIND_OTHER_SENSORS => Self::OtherSensors,
+            COREWEBVIEW2_PERMISSION_KIND_CLIPBOARD_READ => Self::ClipboardRead,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PermissionState {
+    // Lets WebView2 ask the user with its own prompt.
+    Default,
+    Allow,
+    Deny,
+}
+
+impl PermissionState {
+    fn to_raw(self) -> COREWEBVIEW2_PERMISSION_STATE {
+        match self {
+            Self::Default => COREWEBVIEW2_PERMISSION_STATE_DEFAULT,
+            Self::Allow => COREWEBVIEW2_PERMISSION_STATE_ALLOW,
+            Self::Deny => COREWEBVIEW2_PERMISSION_STATE_DENY,
+        }
+    }
+}
+
+// `https://example.com:8080/path?q` -> `https://example.com:8080`.
+pub(crate) fn origin(uri: &str) -> &str {
+    let start = uri.find("://").map_or(0, |i| i + 3);
+    match uri[start..].find(['/', '?', '#']) {
+        Some(end) => &uri[..start + end],
+        None => uri,
+    }
+}
+
+impl WebView {
+    // e.g. `|origin, kind| if origin == "https://app.taco.localhost" { Allow } else { Default }`.
+    pub fn on_permission_request(
+        &self,
+        mut f: impl FnMut(&str, PermissionKind) -> PermissionState + 'static,
+    ) -> Result<Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core.PermissionRequested(
+                PermissionRequestedEventHandler::create(Box::new(
+                    move |_core, args: Option<ICoreWebView2PermissionRequestedEventArgs>| {
+                        if let Some(args) = args {
+                            let mut uri = PWSTR::default();
+                            let mut kind = COREWEBVIEW2_PERMISSION_KIND::default();
+                            args.Uri(&mut uri)?;
+                            args.PermissionKind(&mut kind)?;
+                            let uri = take_pwstr(uri);
+                            let state = f(origin(&uri), PermissionKind::from_raw(kind));
+                            args.SetState(state.to_raw())?;
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut token,
+            )?;
+        }
+        let core = self.core.clone();
+        Ok(self.track(move || unsafe { core.RemovePermissionRequested(token) }))
+    }
+}
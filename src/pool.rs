@@ -0,0 +1,149 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+use windows::Win32::UI::WindowsAndMessaging::{DestroyWindow, WM_CLOSE};
+
+use crate::{window, Environment, Result, WebView, WebViewBuilder};
+
+type MakeBuilder = Rc<dyn Fn() -> WebViewBuilder<'static>>;
+
+struct State {
+    environment: Environment,
+    make: MakeBuilder,
+    size: usize,
+    idle: Vec<WebView>,
+    // Handed out, each with its first subscription that isn't the pool's own.
+    busy: Vec<(WebView, u64)>,
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        for webview in &self.idle {
+            unsafe { DestroyWindow(webview.hwnd) };
+        }
+    }
+}
+
+// Hidden windows with their WebView already created, for popups that should show up at once,
+// like command palettes and pickers. Creating a controller takes a noticeable while, so the
+// pool makes `size` of them ahead of time, in one environment, and refills itself after each
+// `acquire`. The windows run detached, on the thread's loop; see `WindowRunner::run_detached`.
+#[derive(Clone)]
+pub struct WebViewPool {
+    state: Rc<RefCell<State>>,
+}
+
+impl WebViewPool {
+    // `make` describes the windows; its `url` and `html` are ignored, as they'd show up right
+    // away. The environment fields of the first builder are used for all of them.
+    pub fn new(size: usize, make: impl Fn() -> WebViewBuilder<'static> + 'static) -> Result<Self> {
+        let environment = make().create_environment()?;
+        let pool = Self {
+            state: Rc::new(RefCell::new(State {
+                environment,
+                make: Rc::new(make),
+                size,
+                idle: Vec::new(),
+                busy: Vec::new(),
+            })),
+        };
+        pool.refill()?;
+        Ok(pool)
+    }
+
+    pub fn idle(&self) -> usize {
+        self.state.borrow().idle.len()
+    }
+
+    // A hidden window to navigate and show; closing it returns it to the pool. Made on the spot
+    // if none is left.
+    pub fn acquire(&self) -> Result<WebView> {
+        let idle = self.state.borrow_mut().idle.pop();
+        let webview = match idle {
+            Some(webview) => webview,
+            None => self.spawn()?,
+        };
+        let mark = webview.subscription_mark();
+        self.state.borrow_mut().busy.push((webview.clone(), mark));
+
+        // After the caller had a chance to show it, as making a replacement takes a while.
+        let pool = Rc::downgrade(&self.state);
+        window::dispatch_unsafe(webview.hwnd, move |_: &()| match Weak::upgrade(&pool) {
+            Some(state) => Self { state }.refill(),
+            None => Ok(()),
+        });
+        Ok(webview)
+    }
+
+    // Hides the window and takes it back, removing the handlers registered on it since
+    // `acquire` and leaving it on about:blank. Bindings and init scripts stay, so give every
+    // acquired window the same ones. Windows beyond `size` are destroyed instead.
+    pub fn release(&self, webview: &WebView) -> Result<()> {
+        let (webview, mark) = {
+            let mut state = self.state.borrow_mut();
+            let i = match state.busy.iter().position(|(w, _)| w.hwnd == webview.hwnd) {
+                Some(i) => i,
+                // Not from this pool, or already released.
+                None => return Ok(()),
+            };
+            let (webview, mark) = state.busy.swap_remove(i);
+            if state.idle.len() >= state.size {
+                drop(state);
+                // From its own WM_CLOSE listener too; `window::wndproc` frees the listeners
+                // once they have returned.
+                unsafe { DestroyWindow(webview.hwnd) };
+                return Ok(());
+            }
+            (webview, mark)
+        };
+        webview.set_visible(false)?;
+        webview.unsubscribe_since(mark);
+        unsafe { webview.core.Navigate("about:blank")? };
+        self.state.borrow_mut().idle.push(webview);
+        Ok(())
+    }
+
+    fn refill(&self) -> Result<()> {
+        loop {
+            let missing = {
+                let state = self.state.borrow();
+                state.size.saturating_sub(state.idle.len())
+            };
+            if missing == 0 {
+                return Ok(());
+            }
+            let webview = self.spawn()?;
+            self.state.borrow_mut().idle.push(webview);
+        }
+    }
+
+    // Not borrowing the state while building, as that pumps messages.
+    fn spawn(&self) -> Result<WebView> {
+        let (environment, make) = {
+            let state = self.state.borrow();
+            (state.environment.clone(), state.make.clone())
+        };
+        let mut builder = make();
+        builder.url = "";
+        builder.html = None;
+        // A `--taco-url` meant for the main window would show every pooled one.
+        builder.cli_prefix = None;
+        let (webview, mut wrun, _) = builder.build_with_env::<()>(&environment)?.into_parts();
+
+        let pool = Rc::downgrade(&self.state);
+        let w = webview.clone();
+        wrun.reset_event_listeners(WM_CLOSE);
+        wrun.add_event_listener(WM_CLOSE, move |_, _, _| match Weak::upgrade(&pool) {
+            Some(state) => {
+                let _ = Self { state }.release(&w);
+            }
+            None => unsafe {
+                DestroyWindow(w.hwnd);
+            },
+        });
+        wrun.run_detached(());
+        Ok(webview)
+    }
+}
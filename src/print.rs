@@ -0,0 +1,103 @@
+use std::{path::Path, sync::mpsc};
+
+use windows::core::Interface;
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{Error, Result, WebView};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+// Sizes and margins are in inches. The defaults match WebView2's.
+#[derive(Clone, Debug)]
+pub struct PrintSettings {
+    pub orientation: Orientation,
+    pub scale: f64,
+    pub page_width: f64,
+    pub page_height: f64,
+    pub margin_top: f64,
+    pub margin_bottom: f64,
+    pub margin_left: f64,
+    pub margin_right: f64,
+    pub backgrounds: bool,
+    pub selection_only: bool,
+    pub header_and_footer: bool,
+    // Shown in the header and footer instead of the document title and URI.
+    pub header_title: Option<String>,
+    pub footer_uri: Option<String>,
+}
+
+impl Default for PrintSettings {
+    fn default() -> Self {
+        Self {
+            orientation: Orientation::Portrait,
+            scale: 1.,
+            page_width: 8.5,
+            page_height: 11.,
+            margin_top: 0.4,
+            margin_bottom: 0.4,
+            margin_left: 0.4,
+            margin_right: 0.4,
+            backgrounds: false,
+            selection_only: false,
+            header_and_footer: false,
+            header_title: None,
+            footer_uri: None,
+        }
+    }
+}
+
+impl WebView {
+    // Blocks until the PDF is written. Returns false if WebView2 couldn't write it.
+    pub fn print_to_pdf(&self, path: impl AsRef<Path>, settings: &PrintSettings) -> Result<bool> {
+        let core = self.core.cast::<ICoreWebView2_7>()?;
+        let path = path.as_ref().to_string_lossy().into_owned();
+
+        let print_settings = unsafe {
+            let print_settings = self
+                .environment
+                .cast::<ICoreWebView2Environment6>()?
+                .CreatePrintSettings()?;
+            print_settings.SetOrientation(match settings.orientation {
+                Orientation::Portrait => COREWEBVIEW2_PRINT_ORIENTATION_PORTRAIT,
+                Orientation::Landscape => COREWEBVIEW2_PRINT_ORIENTATION_LANDSCAPE,
+            })?;
+            print_settings.SetScaleFactor(settings.scale)?;
+            print_settings.SetPageWidth(settings.page_width)?;
+            print_settings.SetPageHeight(settings.page_height)?;
+            print_settings.SetMarginTop(settings.margin_top)?;
+            print_settings.SetMarginBottom(settings.margin_bottom)?;
+            print_settings.SetMarginLeft(settings.margin_left)?;
+            print_settings.SetMarginRight(settings.margin_right)?;
+            print_settings.SetShouldPrintBackgrounds(settings.backgrounds)?;
+            print_settings.SetShouldPrintSelectionOnly(settings.selection_only)?;
+            print_settings.SetShouldPrintHeaderAndFooter(settings.header_and_footer)?;
+            if let Some(title) = &settings.header_title {
+                print_settings.SetHeaderTitle(title.as_str())?;
+            }
+            if let Some(uri) = &settings.footer_uri {
+                print_settings.SetFooterUri(uri.as_str())?;
+            }
+            print_settings
+        };
+
+        let (tx, rx) = mpsc::channel();
+        PrintToPdfCompletedHandler::wait_for_async_operation(
+            Box::new(move |handler| unsafe {
+                core.PrintToPdf(path, print_settings, handler)
+                    .map_err(webview2_com::Error::WindowsError)
+            }),
+            Box::new(move |error_code, succeeded| {
+                error_code?;
+                tx.send(succeeded).expect("send over mpsc channel");
+                Ok(())
+            }),
+        )?;
+        rx.recv()
+            .map_err(|_| Error::WebView2Error(webview2_com::Error::SendError))
+    }
+}
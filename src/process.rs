@@ -0,0 +1,130 @@
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::CloseHandle,
+        System::{
+            ProcessStatus::{K32GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS},
+            Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION},
+        },
+    },
+};
+
+use webview2_com::Microsoft::Web::WebView2::Win32::*;
+
+use crate::{Result, WebView};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProcessKind {
+    Browser,
+    Renderer,
+    Utility,
+    SandboxHelper,
+    Gpu,
+    PpapiPlugin,
+    PpapiBroker,
+    Unknown,
+}
+
+impl ProcessKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Browser => "browser",
+            Self::Renderer => "renderer",
+            Self::Utility => "utility",
+            Self::SandboxHelper => "sandbox helper",
+            Self::Gpu => "gpu",
+            Self::PpapiPlugin => "plugin",
+            Self::PpapiBroker => "plugin broker",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+impl From<COREWEBVIEW2_PROCESS_KIND> for ProcessKind {
+    fn from(kind: COREWEBVIEW2_PROCESS_KIND) -> Self {
+        match kind {
+            COREWEBVIEW2_PROCESS_KIND_BROWSER => Self::Browser,
+            COREWEBVIEW2_PROCESS_KIND_RENDERER => Self::Renderer,
+            COREWEBVIEW2_PROCESS_KIND_UTILITY => Self::Utility,
+            COREWEBVIEW2_PROCESS_KIND_SANDBOX_HELPER => Self::SandboxHelper,
+            COREWEBVIEW2_PROCESS_KIND_GPU => Self::Gpu,
+            COREWEBVIEW2_PROCESS_KIND_PPAPI_PLUGIN => Self::PpapiPlugin,
+            COREWEBVIEW2_PROCESS_KIND_PPAPI_BROKER => Self::PpapiBroker,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BrowserProcess {
+    pub pid: u32,
+    pub kind: ProcessKind,
+    // In bytes, both zero if the process has exited since it was listed.
+    pub working_set: usize,
+    pub private_bytes: usize,
+}
+
+// (working set, private bytes), or zeros if the process is already gone.
+pub(crate) fn memory(pid: u32) -> (usize, usize) {
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid);
+        if process.is_invalid() {
+            return (0, 0);
+        }
+        let mut counters = PROCESS_MEMORY_COUNTERS {
+            cb: std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+            ..Default::default()
+        };
+        let ok = K32GetProcessMemoryInfo(process, &mut counters, counters.cb).as_bool();
+        CloseHandle(process);
+        match ok {
+            true => (counters.WorkingSetSize, counters.PagefileUsage),
+            false => (0, 0),
+        }
+    }
+}
+
+// Every process of the environment's browser process tree, i.e. of all WebViews sharing its
+// data directory.
+pub(crate) fn list(environment: &ICoreWebView2Environment) -> Result<Vec<BrowserProcess>> {
+    let mut processes = Vec::new();
+    unsafe {
+        let infos = environment
+            .cast::<ICoreWebView2Environment8>()?
+            .GetProcessInfos()?;
+        let mut count = 0;
+        infos.Count(&mut count)?;
+        for i in 0..count {
+            let info = infos.GetValueAtIndex(i)?;
+            let (mut pid, mut kind) = (0, COREWEBVIEW2_PROCESS_KIND::default());
+            info.ProcessId(&mut pid)?;
+            info.Kind(&mut kind)?;
+            let (working_set, private_bytes) = memory(pid as u32);
+            processes.push(BrowserProcess {
+                pid: pid as u32,
+                kind: kind.into(),
+                working_set,
+                private_bytes,
+            });
+        }
+    }
+    Ok(processes)
+}
+
+impl WebView {
+    // Opens Edge's browser task manager, listing this WebView's processes and what runs in them.
+    pub fn open_task_manager(&self) -> Result<&Self> {
+        unsafe {
+            self.core
+                .cast::<ICoreWebView2_6>()?
+                .OpenTaskManagerWindow()?
+        };
+        Ok(self)
+    }
+
+    // The browser, renderer, GPU and other processes serving this WebView and the others
+    // sharing its environment, with their memory use.
+    pub fn browser_processes(&self) -> Result<Vec<BrowserProcess>> {
+        list(&self.environment)
+    }
+}
@@ -0,0 +1,145 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use serde_json::{json, Value};
+
+use crate::{event::Subscription, navigation::Decision, Result, WebView};
+
+// Share of the bar taken before any resource loads, and by the resources themselves.
+const STARTED: f32 = 0.1;
+const RESOURCES: f32 = 0.8;
+
+#[derive(Default)]
+struct Resource {
+    expected: Option<u64>,
+    received: u64,
+    finished: bool,
+}
+
+impl Resource {
+    fn fraction(&self) -> f32 {
+        match (self.finished, self.expected) {
+            (true, _) => 1.,
+            (false, Some(expected)) if expected > 0 => {
+                (self.received as f32 / expected as f32).min(1.)
+            }
+            _ => 0.,
+        }
+    }
+}
+
+#[derive(Default)]
+struct PageLoad {
+    loading: bool,
+    resources: HashMap<String, Resource>,
+    // The bar never moves backwards, even when new requests are discovered.
+    reported: f32,
+}
+
+impl PageLoad {
+    fn progress(&self) -> f32 {
+        if self.resources.is_empty() {
+            return STARTED;
+        }
+        let total: f32 = self.resources.values().map(Resource::fraction).sum();
+        STARTED + RESOURCES * total / self.resources.len() as f32
+    }
+}
+
+fn request_id(params: &Value) -> Option<String> {
+    params["requestId"].as_str().map(String::from)
+}
+
+fn content_length(params: &Value) -> Option<u64> {
+    let headers = params["response"]["headers"].as_object()?;
+    headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.as_str()?.trim().parse().ok())
+}
+
+impl WebView {
+    // Calls `f` with a 0.0..=1.0 estimate of how far the current page load is, weighted by the
+    // bytes received for each resource (from the CDP Network domain).
+    pub fn on_progress(&self, f: impl FnMut(f32) + 'static) -> Result<Subscription> {
+        let state = Rc::new(RefCell::new(PageLoad::default()));
+        let f = Rc::new(RefCell::new(f));
+
+        let report = {
+            let state = state.clone();
+            move |progress: Option<f32>| {
+                let mut state = state.borrow_mut();
+                if !state.loading {
+                    return;
+                }
+                let progress = progress.unwrap_or_else(|| state.progress());
+                if progress > state.reported {
+                    state.reported = progress;
+                    drop(state);
+                    (f.borrow_mut())(progress);
+                }
+            }
+        };
+
+        self.call_devtools_method("Network.enable", json!({}))?;
+
+        let mut subscriptions = Vec::new();
+        let (s, r) = (state.clone(), report.clone());
+        subscriptions.push(self.on_navigation_starting(move |_| {
+            *s.borrow_mut() = PageLoad {
+                loading: true,
+                ..Default::default()
+            };
+            r(Some(STARTED));
+            Decision::Allow
+        })?);
+
+        let s = state.clone();
+        subscriptions.push(
+            self.on_devtools_event("Network.requestWillBeSent", move |params| {
+                if let Some(id) = request_id(&params) {
+                    s.borrow_mut().resources.entry(id).or_default();
+                }
+            })?,
+        );
+
+        let (s, r) = (state.clone(), report.clone());
+        subscriptions.push(
+            self.on_devtools_event("Network.responseReceived", move |params| {
+                if let Some(id) = request_id(&params) {
+                    s.borrow_mut().resources.entry(id).or_default().expected =
+                        content_length(&params);
+                    r(None);
+                }
+            })?,
+        );
+
+        let (s, r) = (state.clone(), report.clone());
+        subscriptions.push(
+            self.on_devtools_event("Network.dataReceived", move |params| {
+                if let Some(id) = request_id(&params) {
+                    let length = params["encodedDataLength"].as_u64().unwrap_or_default();
+                    s.borrow_mut().resources.entry(id).or_default().received += length;
+                    r(None);
+                }
+            })?,
+        );
+
+        for event in ["Network.loadingFinished", "Network.loadingFailed"] {
+            let (s, r) = (state.clone(), report.clone());
+            subscriptions.push(self.on_devtools_event(event, move |params| {
+                if let Some(id) = request_id(&params) {
+                    s.borrow_mut().resources.entry(id).or_default().finished = true;
+                    r(None);
+                }
+            })?);
+        }
+
+        let s = state;
+        subscriptions.push(self.on_navigation_completed(move |_| {
+            report(Some(1.));
+            s.borrow_mut().loading = false;
+        })?);
+
+        Ok(self.merge(&subscriptions))
+    }
+}
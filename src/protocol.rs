@@ -0,0 +1,297 @@
+use std::path::Path;
+
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::{BOOL, PWSTR},
+        System::{Com::IStream, WinRT::EventRegistrationToken},
+        UI::Shell::SHCreateMemStream,
+    },
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{
+    error_page::{self, ErrorClass, NavigationError},
+    Result, WebView,
+};
+
+// This WebView2 SDK can't register custom schemes, so `<scheme>://<host>/...` is served on
+// `https://<host>.<scheme>.localhost/...`; `.localhost` is reserved, so no real site is taken
+// over. Handlers still see the `<scheme>://` form of the URI.
+#[derive(Clone, Debug, Default)]
+pub struct Request {
+    pub method: String,
+    pub uri: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self::new(200, body)
+    }
+
+    pub fn not_found() -> Self {
+        Self::new(404, Vec::new())
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+// How pages from other origins may use files of a host mapped with `map_host`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    Allow,
+    Deny,
+    // Allowed for anything except CORS requests such as fetch/XHR from other origins.
+    DenyCors,
+}
+
+impl From<AccessKind> for COREWEBVIEW2_HOST_RESOURCE_ACCESS_KIND {
+    fn from(kind: AccessKind) -> Self {
+        match kind {
+            AccessKind::Allow => COREWEBVIEW2_HOST_RESOURCE_ACCESS_KIND_ALLOW,
+            AccessKind::Deny => COREWEBVIEW2_HOST_RESOURCE_ACCESS_KIND_DENY,
+            AccessKind::DenyCors => COREWEBVIEW2_HOST_RESOURCE_ACCESS_KIND_DENY_CORS,
+        }
+    }
+}
+
+pub type ProtocolHandler = Box<dyn FnMut(&Request) -> Response>;
+
+pub(crate) struct Route {
+    pub(crate) scheme: String,
+    // None handles every host of the scheme; routes for one host go first.
+    pub(crate) host: Option<String>,
+    pub(crate) handler: ProtocolHandler,
+}
+
+impl Route {
+    pub(crate) fn new(scheme: &str, handler: ProtocolHandler) -> Self {
+        Self {
+            // Chromium lowercases hosts.
+            scheme: scheme.to_ascii_lowercase(),
+            host: None,
+            handler,
+        }
+    }
+
+    fn filter(&self) -> String {
+        format!(
+            "https://{}/*",
+            https_host(self.host.as_deref().unwrap_or("*"), &self.scheme)
+        )
+    }
+
+    // The `<scheme>://` form of `uri`, if the route handles it.
+    fn custom_uri(&self, uri: &str) -> Option<String> {
+        let rest = uri.strip_prefix("https://")?;
+        let end = rest.find(&['/', '?', '#'][..]).unwrap_or(rest.len());
+        let (authority, path) = rest.split_at(end);
+        let host = authority.strip_suffix(&format!(".{}.localhost", self.scheme))?;
+        let ours = !host.is_empty() && (self.host.is_none() || self.host.as_deref() == Some(host));
+        ours.then(|| format!("{}://{}{}", self.scheme, host, path))
+    }
+}
+
+fn https_host(host: &str, scheme: &str) -> String {
+    format!("{}.{}.localhost", host, scheme)
+}
+
+// `taco://app/index.html` -> `https://app.taco.localhost/index.html`
+pub fn to_https(url: &str, scheme: &str) -> Option<String> {
+    let rest = url.strip_prefix(scheme)?.strip_prefix("://")?;
+    let end = rest.find(&['/', '?', '#'][..]).unwrap_or(rest.len());
+    let (host, path) = rest.split_at(end);
+    Some(format!(
+        "https://{}{}",
+        https_host(&host.to_ascii_lowercase(), &scheme.to_ascii_lowercase()),
+        path
+    ))
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+pub(crate) unsafe fn read_headers(
+    iterator: ICoreWebView2HttpHeadersCollectionIterator,
+) -> Result<Vec<(String, String)>> {
+    let mut headers = Vec::new();
+    let mut has_current = BOOL::default();
+    iterator.HasCurrentHeader(&mut has_current)?;
+    while has_current.as_bool() {
+        let (mut name, mut value) = (PWSTR::default(), PWSTR::default());
+        iterator.GetCurrentHeader(&mut name, &mut value)?;
+        headers.push((take_pwstr(name), take_pwstr(value)));
+        iterator.MoveNext(&mut has_current)?;
+    }
+    Ok(headers)
+}
+
+pub(crate) unsafe fn read_stream(stream: &IStream) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buffer = [0u8; 4096];
+    loop {
+        let mut read = 0;
+        stream.Read(buffer.as_mut_ptr() as _, buffer.len() as _, &mut read)?;
+        if read == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&buffer[..read as usize]);
+    }
+    Ok(bytes)
+}
+
+unsafe fn read_request(request: &ICoreWebView2WebResourceRequest, uri: String) -> Result<Request> {
+    let mut method = PWSTR::default();
+    request.Method(&mut method)?;
+
+    let body = match request.Content() {
+        Ok(stream) => read_stream(&stream)?,
+        Err(_) => Vec::new(),
+    };
+
+    Ok(Request {
+        method: take_pwstr(method),
+        uri,
+        headers: read_headers(request.Headers()?.GetIterator()?)?,
+        body,
+    })
+}
+
+pub(crate) fn serve(webview: &WebView, mut routes: Vec<Route>) -> Result<()> {
+    routes.sort_by_key(|route| route.host.is_none());
+
+    let environment = webview.environment.clone();
+    let error_pages = webview.error_pages.clone();
+    unsafe {
+        for route in &routes {
+            webview.core.AddWebResourceRequestedFilter(
+                route.filter(),
+                COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL,
+            )?;
+        }
+
+        let mut token = EventRegistrationToken::default();
+        webview.core.WebResourceRequested(
+            WebResourceRequestedEventHandler::create(Box::new(
+                move |_core, args: Option<ICoreWebView2WebResourceRequestedEventArgs>| {
+                    if let Some(args) = args {
+                        let request = args.Request()?;
+                        let mut uri = PWSTR::default();
+                        request.Uri(&mut uri)?;
+                        let uri = take_pwstr(uri);
+
+                        let matched = routes
+                            .iter_mut()
+                            .find_map(|route| Some((route.custom_uri(&uri)?, route)));
+                        if let Some((uri, route)) = matched {
+                            let mut response = match read_request(&request, uri.clone()) {
+                                Ok(request) => (route.handler)(&request),
+                                Err(_) => Response::new(400, Vec::new()),
+                            };
+
+                            let mut context = COREWEBVIEW2_WEB_RESOURCE_CONTEXT::default();
+                            args.ResourceContext(&mut context)?;
+                            if response.status == 404
+                                && response.body.is_empty()
+                                && context == COREWEBVIEW2_WEB_RESOURCE_CONTEXT_DOCUMENT
+                            {
+                                let error = NavigationError {
+                                    uri,
+                                    class: ErrorClass::NotFound,
+                                    web_error_status: 0,
+                                };
+                                if let Some(html) = error_page::page(&error_pages, &error) {
+                                    response = Response::new(404, html)
+                                        .with_header("Content-Type", "text/html; charset=utf-8");
+                                }
+                            }
+
+                            let stream =
+                                SHCreateMemStream(response.body.as_ptr(), response.body.len() as _);
+                            let headers = response
+                                .headers
+                                .iter()
+                                .map(|(name, value)| format!("{}: {}", name, value))
+                                .collect::<Vec<_>>()
+                                .join("\r\n");
+                            args.SetResponse(environment.CreateWebResourceResponse(
+                                stream,
+                                response.status as i32,
+                                reason_phrase(response.status),
+                                headers,
+                            )?)?;
+                        }
+                    }
+                    Ok(())
+                },
+            )),
+            &mut token,
+        )?;
+        let core = webview.core.clone();
+        webview.track(move || core.RemoveWebResourceRequested(token));
+    }
+
+    Ok(())
+}
+
+impl WebView {
+    // Serves the files of `folder` at `https://<host>/...`, so pages get a real origin for
+    // fetch/XHR and module scripts. Relative folders are resolved against the working directory.
+    pub fn map_host(&self, host: &str, folder: impl AsRef<Path>, access: AccessKind) -> Result<()> {
+        let folder = std::env::current_dir()?.join(folder);
+        unsafe {
+            self.core
+                .cast::<ICoreWebView2_3>()?
+                .SetVirtualHostNameToFolderMapping(
+                    host,
+                    folder.to_string_lossy().as_ref(),
+                    access.into(),
+                )?;
+        }
+        Ok(())
+    }
+
+    pub fn unmap_host(&self, host: &str) -> Result<()> {
+        unsafe {
+            self.core
+                .cast::<ICoreWebView2_3>()?
+                .ClearVirtualHostNameToFolderMapping(host)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,365 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use serde_json::Value;
+
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::{BOOL, PWSTR},
+        System::WinRT::EventRegistrationToken,
+    },
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{
+    event::Subscription,
+    state::{self, PageState, StateSnapshot},
+    Result, WebView,
+};
+
+type RecoveredCallback = Rc<RefCell<dyn FnMut(ProcessFailedKind)>>;
+
+// What `enable_reconnect` replays to a recovered page.
+#[derive(Default)]
+pub(crate) struct Reconnect {
+    // Of `enable_reconnect`'s handlers, while enabled.
+    subscription: Option<Subscription>,
+    // The last payload of each event, in the order they were first emitted.
+    emitted: Vec<(String, Value)>,
+    callback: Option<RecoveredCallback>,
+}
+
+pub(crate) fn record_emit(reconnect: &RefCell<Reconnect>, event: &str, payload: &Value) {
+    let mut reconnect = reconnect.borrow_mut();
+    if reconnect.subscription.is_none() {
+        return;
+    }
+    match reconnect.emitted.iter_mut().find(|(e, _)| e == event) {
+        Some((_, last)) => *last = payload.clone(),
+        None => reconnect.emitted.push((event.to_string(), payload.clone())),
+    }
+}
+
+// Runs the init scripts again if the page lost them, then the emits, then the hook. Evaluating
+// waits, so this is dispatched rather than run inside WebView2's event handlers.
+fn reconnect(webview: &WebView, kind: ProcessFailedKind) {
+    let scripts = webview.init_scripts.borrow().clone();
+    if let Ok(scripts) = serde_json::to_string(&scripts) {
+        let js = format!(
+            "if (!(window.taco && window.taco._emit)) {{ {}.forEach(s => (0, eval)(s)); }}",
+            scripts
+        );
+        let _ = webview.eval(&js);
+    }
+    // Cloned out, as emitting records them again.
+    let emitted = webview.reconnect.borrow().emitted.clone();
+    for (event, payload) in emitted {
+        let _ = webview.emit(&event, payload);
+    }
+    recovered(webview, kind);
+}
+
+fn recovered(webview: &WebView, kind: ProcessFailedKind) {
+    let callback = webview.reconnect.borrow().callback.clone();
+    if let Some(f) = callback {
+        (f.borrow_mut())(kind);
+    }
+}
+
+// Loads the iframes of the failed renderer again, from the top document, whose renderer is
+// fine. They're found by name, or else by source.
+fn reload_frames(frames: &[FailedFrame]) -> String {
+    let frames = frames
+        .iter()
+        .map(|frame| [frame.name.as_str(), frame.source.as_str()])
+        .collect::<Vec<_>>();
+    format!(
+        r#"{}.forEach(([name, source]) => {{
+            for (const frame of document.querySelectorAll('iframe')) {{
+                if (name ? frame.name === name : frame.src === source) frame.src = source || frame.src;
+            }}
+        }});"#,
+        serde_json::to_string(&frames).unwrap_or_else(|_| "[]".into())
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProcessFailedKind {
+    // The WebView is gone for good; the window has to be rebuilt.
+    BrowserProcessExited,
+    RenderProcessExited,
+    RenderProcessUnresponsive,
+    // Only an iframe's renderer; the rest of the page still works.
+    FrameRenderProcessExited,
+    GpuProcessExited,
+    // Utility, sandbox helper, plugin and unknown processes, which WebView2 restarts itself.
+    OtherProcessExited,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProcessFailedReason {
+    Unexpected,
+    Unresponsive,
+    Terminated,
+    Crashed,
+    LaunchFailed,
+    OutOfMemory,
+}
+
+// An iframe whose renderer failed.
+#[derive(Clone, Debug, Default)]
+pub struct FailedFrame {
+    // Its `name` attribute, often empty.
+    pub name: String,
+    pub source: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProcessFailure {
+    pub kind: ProcessFailedKind,
+    pub reason: ProcessFailedReason,
+    pub exit_code: i32,
+    // e.g. the name of the utility process. Often empty.
+    pub description: String,
+    // For `FrameRenderProcessExited`; empty on older runtimes.
+    pub frames: Vec<FailedFrame>,
+}
+
+unsafe fn failed_frames(args: &ICoreWebView2ProcessFailedEventArgs2) -> Result<Vec<FailedFrame>> {
+    let mut frames = Vec::new();
+    let iterator = match args.FrameInfosForFailedProcess() {
+        Ok(infos) => infos.GetIterator()?,
+        Err(_) => return Ok(frames),
+    };
+    let mut has_current = BOOL::default();
+    iterator.HasCurrent(&mut has_current)?;
+    while has_current.as_bool() {
+        let info = iterator.GetCurrent()?;
+        let (mut name, mut source) = (PWSTR::default(), PWSTR::default());
+        info.Name(&mut name)?;
+        info.Source(&mut source)?;
+        frames.push(FailedFrame {
+            name: take_pwstr(name),
+            source: take_pwstr(source),
+        });
+        iterator.MoveNext(&mut has_current)?;
+    }
+    Ok(frames)
+}
+
+fn process_failure(args: &ICoreWebView2ProcessFailedEventArgs) -> Result<ProcessFailure> {
+    let mut kind = COREWEBVIEW2_PROCESS_FAILED_KIND::default();
+    unsafe { args.ProcessFailedKind(&mut kind)? };
+    let kind = match kind {
+        COREWEBVIEW2_PROCESS_FAILED_KIND_BROWSER_PROCESS_EXITED => {
+            ProcessFailedKind::BrowserProcessExited
+        }
+        COREWEBVIEW2_PROCESS_FAILED_KIND_RENDER_PROCESS_EXITED => {
+            ProcessFailedKind::RenderProcessExited
+        }
+        COREWEBVIEW2_PROCESS_FAILED_KIND_RENDER_PROCESS_UNRESPONSIVE => {
+            ProcessFailedKind::RenderProcessUnresponsive
+        }
+        COREWEBVIEW2_PROCESS_FAILED_KIND_FRAME_RENDER_PROCESS_EXITED => {
+            ProcessFailedKind::FrameRenderProcessExited
+        }
+        COREWEBVIEW2_PROCESS_FAILED_KIND_GPU_PROCESS_EXITED => ProcessFailedKind::GpuProcessExited,
+        _ => ProcessFailedKind::OtherProcessExited,
+    };
+
+    let mut failure = ProcessFailure {
+        kind,
+        reason: ProcessFailedReason::Unexpected,
+        exit_code: 0,
+        description: String::new(),
+        frames: Vec::new(),
+    };
+    // Older runtimes only report the kind.
+    if let Ok(args) = args.cast::<ICoreWebView2ProcessFailedEventArgs2>() {
+        let mut reason = COREWEBVIEW2_PROCESS_FAILED_REASON::default();
+        let mut description = PWSTR::default();
+        unsafe {
+            args.Reason(&mut reason)?;
+            args.ExitCode(&mut failure.exit_code)?;
+            args.ProcessDescription(&mut description)?;
+        }
+        failure.description = take_pwstr(description);
+        failure.frames = unsafe { failed_frames(&args)? };
+        failure.reason = match reason {
+            COREWEBVIEW2_PROCESS_FAILED_REASON_UNRESPONSIVE => ProcessFailedReason::Unresponsive,
+            COREWEBVIEW2_PROCESS_FAILED_REASON_TERMINATED => ProcessFailedReason::Terminated,
+            COREWEBVIEW2_PROCESS_FAILED_REASON_CRASHED => ProcessFailedReason::Crashed,
+            COREWEBVIEW2_PROCESS_FAILED_REASON_LAUNCH_FAILED => ProcessFailedReason::LaunchFailed,
+            COREWEBVIEW2_PROCESS_FAILED_REASON_OUT_OF_MEMORY => ProcessFailedReason::OutOfMemory,
+            _ => ProcessFailedReason::Unexpected,
+        };
+    }
+    Ok(failure)
+}
+
+impl WebView {
+    pub fn on_process_failed(
+        &self,
+        mut f: impl FnMut(&ProcessFailure) + 'static,
+    ) -> Result<Subscription> {
+        let mut token = EventRegistrationToken::default();
+        unsafe {
+            self.core.ProcessFailed(
+                ProcessFailedEventHandler::create(Box::new(
+                    move |_core, args: Option<ICoreWebView2ProcessFailedEventArgs>| {
+                        if let Some(args) = args {
+                            if let Ok(failure) = process_failure(&args) {
+                                f(&failure);
+                            }
+                        }
+                        Ok(())
+                    },
+                )),
+                &mut token,
+            )?;
+        }
+        let core = self.core.clone();
+        Ok(self.track(move || unsafe { core.RemoveProcessFailed(token) }))
+    }
+
+    // Opens the last page again when its renderer crashes or hangs, instead of leaving a dead
+    // window, scrolled to where it was, and with what had been typed into its fields if `form`
+    // is set (passwords and files aside). Init scripts and bindings are tied to the WebView, so
+    // they run again as usual.
+    // The controller isn't recreated after a browser process exit, which takes the environment
+    // with it: the WebView's handlers, bindings and filters are all registered on the dead
+    // `core`, which clones of the WebView hold on to, so a new controller would be a new WebView
+    // with none of them. Handle `ProcessFailedKind::BrowserProcessExited` with
+    // `on_process_failed` and build the window again with the app's builder.
+    pub fn enable_auto_recovery(&self, form: bool) -> Result<Subscription> {
+        let last = Rc::new(RefCell::new(StateSnapshot::default()));
+
+        let l = last.clone();
+        let source = self.on_source_changed(move |args| {
+            *l.borrow_mut() = StateSnapshot {
+                url: args.uri.clone(),
+                ..Default::default()
+            }
+        })?;
+
+        let l = last.clone();
+        self.bind_unsafe("_taco_reportState", move |args| {
+            let page: PageState = args
+                .into_iter()
+                .next()
+                .and_then(|state| serde_json::from_value(state).ok())
+                .ok_or("Bad page state")?;
+            let mut last = l.borrow_mut();
+            let url = std::mem::take(&mut last.url);
+            *last = page.into_snapshot(url, 0.);
+            Ok(Value::Null)
+        });
+        let binding = self.track_binding("_taco_reportState");
+        let script = self.track_init(&state::reporter_script("_taco_reportState", form))?;
+
+        let w = self.clone();
+        let failed = self.on_process_failed(move |failure| {
+            if let ProcessFailedKind::RenderProcessExited
+            | ProcessFailedKind::RenderProcessUnresponsive = failure.kind
+            {
+                let mut snapshot = last.borrow().clone();
+                // `restore_state` waits for the page, which can't happen inside WebView2's
+                // event dispatch.
+                let _ = w.handle().dispatch(move |w| {
+                    if snapshot.url.is_empty() {
+                        w.reload().ok();
+                    } else {
+                        snapshot.zoom = w.zoom().unwrap_or(0.);
+                        w.restore_state(&snapshot).ok();
+                    }
+                    Ok(())
+                });
+            }
+        })?;
+        Ok(self.merge(&[source, binding, script, failed]))
+    }
+
+    // Keeps an SPA working across renderer failures. Once the page is back after being reloaded
+    // (see `enable_auto_recovery`), the init scripts run again if the page lost them, and the
+    // last payload of every event emitted so far is emitted once more, so that state the page
+    // only learns from events is restored. Iframes whose renderer died while the page lived on
+    // are loaded again, which runs the init scripts and their `Frame` bindings as usual; emits
+    // only ever reach the top document, so there's nothing to replay to them. Then
+    // `on_recovered` is called. Enabling it again returns the same subscription.
+    pub fn enable_reconnect(&self) -> Result<Subscription> {
+        if let Some(subscription) = self.reconnect.borrow().subscription {
+            return Ok(subscription);
+        }
+        let failed = Rc::new(Cell::new(None));
+        let frames_failed = Rc::new(Cell::new(false));
+
+        let w = self.clone();
+        let f = failed.clone();
+        let frames = frames_failed.clone();
+        let process = self.on_process_failed(move |failure| match failure.kind {
+            ProcessFailedKind::FrameRenderProcessExited => {
+                frames.set(true);
+                let js = reload_frames(&failure.frames);
+                let _ = w.handle().eval(js);
+            }
+            // After the next navigation brings the page back.
+            ProcessFailedKind::RenderProcessExited
+            | ProcessFailedKind::RenderProcessUnresponsive => f.set(Some(failure.kind)),
+            _ => {}
+        })?;
+
+        let w = self.clone();
+        let navigation = self.on_navigation_completed(move |args| {
+            if let (true, Some(kind)) = (args.is_success, failed.get()) {
+                failed.set(None);
+                let _ = w.handle().dispatch(move |w| {
+                    reconnect(w, kind);
+                    Ok(())
+                });
+            }
+        })?;
+
+        let w = self.clone();
+        let frame = self.on_frame_navigation_completed(move |args| {
+            if args.is_success && frames_failed.replace(false) {
+                recovered(&w, ProcessFailedKind::FrameRenderProcessExited);
+            }
+        })?;
+
+        let state = self.reconnect.clone();
+        let enabled = self.track(move || {
+            let mut state = state.borrow_mut();
+            state.subscription = None;
+            state.emitted.clear();
+            Ok(())
+        });
+        let subscription = self.merge(&[process, navigation, frame, enabled]);
+        self.reconnect.borrow_mut().subscription = Some(subscription);
+        Ok(subscription)
+    }
+
+    // Called by `enable_reconnect` once a page has been reconnected, with the kind of failure
+    // it recovered from, e.g. to refetch data the events don't cover. Replaces the previous
+    // callback.
+    pub fn on_recovered(&self, f: impl FnMut(ProcessFailedKind) + 'static) -> Subscription {
+        let callback: RecoveredCallback = Rc::new(RefCell::new(f));
+        self.reconnect.borrow_mut().callback = Some(callback.clone());
+
+        let state = self.reconnect.clone();
+        self.track(move || {
+            let mut state = state.borrow_mut();
+            if state
+                .callback
+                .as_ref()
+                .is_some_and(|f| Rc::ptr_eq(f, &callback))
+            {
+                state.callback = None;
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,124 @@
+use std::{collections::HashMap, path::Path};
+
+use serde_json::json;
+
+use crate::{
+    protocol::{Request, Response, Route},
+    Result, WebView,
+};
+
+pub const RESOURCE_ORIGIN: &str = "https://resources.taco.localhost";
+// How resource requests appear to protocol handlers.
+const RESOURCE_PREFIX: &str = "taco://resources/";
+
+// Pinned resources are read once at build time and served from memory with
+// long-lived cache headers, so packaged apps don't depend on system-wide fonts.
+#[derive(Clone, Copy, Debug)]
+pub enum Resource<'a> {
+    // Served at `https://resources.taco.localhost/<file name>` and registered as `family` in `document.fonts`.
+    Font { family: &'a str, path: &'a str },
+    // Served at `https://resources.taco.localhost/<name>`.
+    File { name: &'a str, path: &'a str },
+}
+
+struct Pinned {
+    mime: &'static str,
+    bytes: Vec<u8>,
+}
+
+pub fn mime_type(path: &str) -> &'static str {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "woff2" => "font/woff2",
+        "woff" => "font/woff",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" => "text/javascript",
+        "json" | "map" => "application/json",
+        "txt" => "text/plain",
+        "xml" => "application/xml",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "svg" => "image/svg+xml",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path)
+}
+
+pub(crate) fn pin(webview: &WebView, resources: &[Resource]) -> Result<Route> {
+    let mut pinned = HashMap::new();
+    let mut fonts = String::new();
+
+    for resource in resources {
+        let (name, path) = match *resource {
+            Resource::Font { family, path } => {
+                let name = file_name(path);
+                let url = format!("{}/{}", RESOURCE_ORIGIN, name);
+                fonts += &format!(
+                    "document.fonts.add(new FontFace({}, {}));",
+                    json!(family),
+                    json!(format!("url({})", url))
+                );
+                (name, path)
+            }
+            Resource::File { name, path } => (name, path),
+        };
+        let bytes = std::fs::read(path)?;
+        pinned.insert(
+            name.to_string(),
+            Pinned {
+                mime: mime_type(path),
+                bytes,
+            },
+        );
+    }
+
+    let handler = move |request: &Request| {
+        let name = request
+            .uri
+            .trim_start_matches(RESOURCE_PREFIX)
+            .split(&['?', '#'][..])
+            .next()
+            .unwrap_or_default();
+        match pinned.get(name) {
+            Some(resource) => Response::ok(resource.bytes.clone())
+                .with_header("Content-Type", resource.mime)
+                .with_header("Cache-Control", "public, max-age=31536000, immutable")
+                .with_header("Access-Control-Allow-Origin", "*"),
+            None => Response::not_found(),
+        }
+    };
+
+    if !fonts.is_empty() {
+        webview.init(&fonts)?;
+    }
+
+    Ok(Route {
+        scheme: "taco".to_string(),
+        host: Some("resources".to_string()),
+        handler: Box::new(handler),
+    })
+}
@@ -0,0 +1,125 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use windows::Win32::UI::WindowsAndMessaging::{KillTimer, SetTimer, WM_DESTROY, WM_TIMER};
+
+use webview2_com::Microsoft::Web::WebView2::Win32::*;
+
+use crate::{error_page::escape_html, window::WindowRunner, Result, WebView};
+
+const RETRY_TIMER_ID: usize = 3;
+
+// How `WebViewBuilder::url` is retried while it can't be reached, e.g. a dev server that's
+// still starting. The delay grows by `multiplier` after each attempt, up to `max_delay`.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    // Including the first one. None keeps trying.
+    pub max_attempts: Option<u32>,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    // Shown between attempts, with `{url}` replaced by the URL. None shows a plain
+    // "Waiting for ..." page.
+    pub fallback_html: Option<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: None,
+            initial_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2.,
+            fallback_html: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Before the attempt after `attempt`, which starts from 1.
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.powi(attempt.saturating_sub(1) as i32);
+        self.initial_delay.mul_f64(factor).min(self.max_delay)
+    }
+
+    fn fallback(&self, url: &str) -> String {
+        self.fallback_html
+            .as_deref()
+            .unwrap_or(include_str!("retry.html"))
+            .replace("{url}", &escape_html(url))
+    }
+}
+
+// The server isn't there (yet). Certificate errors and the like won't go away by retrying.
+fn is_unreachable(status: COREWEBVIEW2_WEB_ERROR_STATUS) -> bool {
+    matches!(
+        status,
+        COREWEBVIEW2_WEB_ERROR_STATUS_CANNOT_CONNECT
+            | COREWEBVIEW2_WEB_ERROR_STATUS_HOST_NAME_NOT_RESOLVED
+            | COREWEBVIEW2_WEB_ERROR_STATUS_SERVER_UNREACHABLE
+            | COREWEBVIEW2_WEB_ERROR_STATUS_TIMEOUT
+            | COREWEBVIEW2_WEB_ERROR_STATUS_CONNECTION_ABORTED
+            | COREWEBVIEW2_WEB_ERROR_STATUS_CONNECTION_RESET
+            | COREWEBVIEW2_WEB_ERROR_STATUS_DISCONNECTED
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    // Navigating to the URL for the nth time.
+    Loading(u32),
+    // Showing the fallback page until the timer fires.
+    Waiting(u32),
+    Done,
+}
+
+// Watches the navigation to `url` the builder is about to start, and retries it whenever it
+// fails to connect. Stops at the first success, or the first other kind of failure, after which
+// navigations are left alone. Error pages are only shown for the failure it stops at.
+pub(crate) fn watch<T: 'static>(
+    webview: &WebView,
+    wrun: &mut WindowRunner<T>,
+    url: &str,
+    policy: RetryPolicy,
+) -> Result<()> {
+    let hwnd = wrun.hwnd();
+    let phase = Rc::new(Cell::new(Phase::Loading(1)));
+    webview.error_pages.borrow_mut().retrying = true;
+
+    let p = phase.clone();
+    let w = webview.clone();
+    let fallback = policy.fallback(url);
+    webview.on_navigation_completed(move |args| {
+        let attempt = match p.get() {
+            Phase::Loading(attempt) => attempt,
+            // The fallback page itself, or a navigation after we're done.
+            _ => return,
+        };
+        let give_up = policy.max_attempts.is_some_and(|max| attempt >= max);
+        if args.is_success || !is_unreachable(args.web_error_status) || give_up {
+            p.set(Phase::Done);
+            w.error_pages.borrow_mut().retrying = false;
+            return;
+        }
+        p.set(Phase::Waiting(attempt));
+        let _ = unsafe { w.core.NavigateToString(fallback.as_str()) };
+        let delay = policy.delay(attempt).as_millis() as u32;
+        unsafe { SetTimer(hwnd, RETRY_TIMER_ID, delay, None) };
+    })?;
+
+    let w = webview.clone();
+    let url = url.to_string();
+    wrun.add_event_listener(WM_TIMER, move |wparam, _, _| {
+        if wparam.0 != RETRY_TIMER_ID {
+            return;
+        }
+        unsafe { KillTimer(hwnd, RETRY_TIMER_ID) };
+        if let Phase::Waiting(attempt) = phase.get() {
+            phase.set(Phase::Loading(attempt + 1));
+            let _ = unsafe { w.core.Navigate(url.as_str()) };
+        }
+    });
+    wrun.add_event_listener(WM_DESTROY, move |_, _, _| unsafe {
+        KillTimer(hwnd, RETRY_TIMER_ID);
+    });
+    Ok(())
+}
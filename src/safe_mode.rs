@@ -0,0 +1,106 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{environment, Result, WebViewBuilder};
+
+// Caches under a data directory, which a bad update or disk error can leave in a state that
+// crashes the browser process on every start.
+const CACHES: &[&str] = &[
+    r"EBWebView\Default\Cache",
+    r"EBWebView\Default\Code Cache",
+    r"EBWebView\Default\GPUCache",
+    r"EBWebView\Default\Service Worker\CacheStorage",
+    r"EBWebView\GrShaderCache",
+    r"EBWebView\ShaderCache",
+];
+
+// Recovers from crashes on startup. Every start is counted in a marker file until `finish`
+// resets it on a clean exit, so the count is the number of starts in a row that crashed:
+//
+//     let safe_mode = SafeMode::check("app.starts", 3, |crashes| ask_user(crashes))?;
+//     let window = safe_mode.apply(builder, "taco://app/index.html")?.build()?;
+//     ...
+//     safe_mode.finish()?;
+pub struct SafeMode {
+    marker: PathBuf,
+    crashes: u32,
+    active: bool,
+}
+
+impl SafeMode {
+    // Counts this start. Once `threshold` starts in a row crashed, `confirm` gets their number
+    // and decides whether this one runs in safe mode, e.g. by asking the user.
+    pub fn check(
+        marker: impl AsRef<Path>,
+        threshold: u32,
+        confirm: impl FnOnce(u32) -> bool,
+    ) -> Result<Self> {
+        let marker = marker.as_ref().to_path_buf();
+        let crashes = fs::read_to_string(&marker)
+            .ok()
+            .and_then(|count| count.trim().parse().ok())
+            .unwrap_or(0);
+        fs::write(&marker, (crashes + 1).to_string())?;
+        let active = crashes >= threshold && confirm(crashes);
+        Ok(Self {
+            marker,
+            crashes,
+            active,
+        })
+    }
+
+    pub fn crashes(&self) -> u32 {
+        self.crashes
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // In safe mode, opens `url` instead of the builder's page, leaves out the init scripts,
+    // globals, launch info and other additions to the injected API, starts the browser without
+    // extra arguments and deletes the caches of the data directory. Otherwise returns `builder`
+    // as it is.
+    pub fn apply<'a>(
+        &self,
+        builder: WebViewBuilder<'a>,
+        url: &'a str,
+    ) -> Result<WebViewBuilder<'a>> {
+        if !self.active {
+            return Ok(builder);
+        }
+        let base = match builder.profile {
+            Some(name) => environment::profile_directory(builder.data_directory, name)?.into(),
+            None => environment::data_directory_or_default(builder.data_directory)?,
+        };
+        for cache in CACHES {
+            let _ = fs::remove_dir_all(base.join(cache));
+        }
+
+        Ok(WebViewBuilder {
+            url,
+            html: None,
+            init_scripts: Vec::new(),
+            globals: None,
+            expose_args: false,
+            expose_env: Vec::new(),
+            notifications: false,
+            file_drag: false,
+            cursor_hints: false,
+            additional_browser_args: None,
+            browser_presets: Vec::new(),
+            overlay_scrollbars: false,
+            thin_scrollbars: false,
+            auto_touch_keyboard: false,
+            ..builder
+        })
+    }
+
+    // The app got to a clean exit, so the next start is a normal one.
+    pub fn finish(&self) -> Result<()> {
+        fs::write(&self.marker, "0")?;
+        Ok(())
+    }
+}
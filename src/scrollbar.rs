@@ -0,0 +1,24 @@
+use crate::{Result, WebView};
+
+// Chromium's overlay scrollbars, styled like Windows 11's: thin, fading out, and drawn over the
+// content instead of taking space from it. Added to the browser args by
+// `WebViewBuilder::overlay_scrollbars`.
+pub(crate) const OVERLAY_SCROLLBAR_ARGS: &str =
+    "--enable-features=OverlayScrollbar,msOverlayScrollbarWinStyle,msOverlayScrollbarWinStyleAnimation";
+
+impl WebView {
+    // Gives every page thin, rounded scrollbars that suit light and dark pages, for when
+    // `overlay_scrollbars` can't be used. Page styles still win over these.
+    pub fn thin_scrollbars(&self) -> Result<&Self> {
+        let css = serde_json::to_string(include_str!("scrollbar.css"))?;
+        self.init(&format!(
+            r#"(function () {{
+    const style = document.createElement('style');
+    style.textContent = {};
+    // The head doesn't exist yet when this runs.
+    (document.head || document.documentElement).prepend(style);
+}})();"#,
+            css
+        ))
+    }
+}
@@ -0,0 +1,144 @@
+use windows::core::Interface;
+
+use webview2_com::Microsoft::Web::WebView2::Win32::*;
+
+use crate::Result;
+
+// WebView2 settings applied right after the controller is created. `None` keeps WebView2's
+// default (or what `debug`, `zoom_control` and `pinch_zoom` chose), so only set what you mean.
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    pub dev_tools: Option<bool>,
+    pub context_menus: Option<bool>,
+    pub status_bar: Option<bool>,
+    // alert(), confirm() and prompt().
+    pub script_dialogs: Option<bool>,
+    pub script: Option<bool>,
+    // Disabling this also disables bindings and `emit`.
+    pub web_message: Option<bool>,
+    // Disabling this also disables `bind_sync`.
+    pub host_objects: Option<bool>,
+    pub built_in_error_page: Option<bool>,
+    // Ctrl+F, F5, Ctrl+P and the like.
+    pub browser_accelerator_keys: Option<bool>,
+    pub password_autosave: Option<bool>,
+    pub general_autofill: Option<bool>,
+    pub swipe_navigation: Option<bool>,
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn dev_tools(mut self, enabled: bool) -> Self {
+        self.dev_tools = Some(enabled);
+        self
+    }
+
+    pub fn context_menus(mut self, enabled: bool) -> Self {
+        self.context_menus = Some(enabled);
+        self
+    }
+
+    pub fn status_bar(mut self, enabled: bool) -> Self {
+        self.status_bar = Some(enabled);
+        self
+    }
+
+    pub fn script_dialogs(mut self, enabled: bool) -> Self {
+        self.script_dialogs = Some(enabled);
+        self
+    }
+
+    pub fn script(mut self, enabled: bool) -> Self {
+        self.script = Some(enabled);
+        self
+    }
+
+    pub fn web_message(mut self, enabled: bool) -> Self {
+        self.web_message = Some(enabled);
+        self
+    }
+
+    pub fn host_objects(mut self, allowed: bool) -> Self {
+        self.host_objects = Some(allowed);
+        self
+    }
+
+    pub fn built_in_error_page(mut self, enabled: bool) -> Self {
+        self.built_in_error_page = Some(enabled);
+        self
+    }
+
+    pub fn browser_accelerator_keys(mut self, enabled: bool) -> Self {
+        self.browser_accelerator_keys = Some(enabled);
+        self
+    }
+
+    pub fn password_autosave(mut self, enabled: bool) -> Self {
+        self.password_autosave = Some(enabled);
+        self
+    }
+
+    pub fn general_autofill(mut self, enabled: bool) -> Self {
+        self.general_autofill = Some(enabled);
+        self
+    }
+
+    pub fn swipe_navigation(mut self, enabled: bool) -> Self {
+        self.swipe_navigation = Some(enabled);
+        self
+    }
+
+    pub(crate) fn apply(&self, core: &ICoreWebView2) -> Result<()> {
+        unsafe {
+            let settings = core.Settings()?;
+            if let Some(enabled) = self.dev_tools {
+                settings.SetAreDevToolsEnabled(enabled)?;
+            }
+            if let Some(enabled) = self.context_menus {
+                settings.SetAreDefaultContextMenusEnabled(enabled)?;
+            }
+            if let Some(enabled) = self.status_bar {
+                settings.SetIsStatusBarEnabled(enabled)?;
+            }
+            if let Some(enabled) = self.script_dialogs {
+                settings.SetAreDefaultScriptDialogsEnabled(enabled)?;
+            }
+            if let Some(enabled) = self.script {
+                settings.SetIsScriptEnabled(enabled)?;
+            }
+            if let Some(enabled) = self.web_message {
+                settings.SetIsWebMessageEnabled(enabled)?;
+            }
+            if let Some(allowed) = self.host_objects {
+                settings.SetAreHostObjectsAllowed(allowed)?;
+            }
+            if let Some(enabled) = self.built_in_error_page {
+                settings.SetIsBuiltInErrorPageEnabled(enabled)?;
+            }
+            if let Some(enabled) = self.browser_accelerator_keys {
+                settings
+                    .cast::<ICoreWebView2Settings3>()?
+                    .SetAreBrowserAcceleratorKeysEnabled(enabled)?;
+            }
+            if let Some(enabled) = self.password_autosave {
+                settings
+                    .cast::<ICoreWebView2Settings4>()?
+                    .SetIsPasswordAutosaveEnabled(enabled)?;
+            }
+            if let Some(enabled) = self.general_autofill {
+                settings
+                    .cast::<ICoreWebView2Settings4>()?
+                    .SetIsGeneralAutofillEnabled(enabled)?;
+            }
+            if let Some(enabled) = self.swipe_navigation {
+                settings
+                    .cast::<ICoreWebView2Settings6>()?
+                    .SetIsSwipeNavigationEnabled(enabled)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+(function () {
+    const taco = window.taco = window.taco || {};
+    const listeners = [];
+
+    // Called with an ArrayBuffer for each `WebView::post_shared_buffer`.
+    taco.onSharedBuffer = callback => {
+        listeners.push(callback);
+        return () => listeners.splice(listeners.indexOf(callback) >>> 0, 1);
+    };
+
+    // Sends an ArrayBuffer, typed array or Blob to `WebView::on_shared_buffer`. Resolves once
+    // the handler has run.
+    taco.postSharedBuffer = async buffer => {
+        const response = await fetch('https://local.taco-buffer.localhost/post', { method: 'POST', body: buffer });
+        if (!response.ok) throw new Error(`postSharedBuffer failed with ${response.status}`);
+    };
+
+    taco.on('_taco_sharedBuffer', async ({ url }) => {
+        const buffer = await (await fetch(url)).arrayBuffer();
+        listeners.slice().forEach(f => f(buffer));
+    });
+})();
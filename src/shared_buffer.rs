@@ -0,0 +1,159 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use windows::Win32::Security::Cryptography::{
+    BCryptGenRandom, BCRYPT_ALG_HANDLE, BCRYPT_USE_SYSTEM_PREFERRED_RNG,
+};
+
+use crate::{
+    event::Subscription,
+    permission,
+    protocol::{self, Request, Response, Route},
+    Result, WebView,
+};
+
+type SharedBufferCallback = Rc<RefCell<dyn FnMut(Vec<u8>)>>;
+
+// This WebView2 SDK has no PostSharedBufferToScript, so buffers go through a protocol route
+// instead: the page fetches what Rust posted and POSTs what it sends, as raw bytes either way.
+// Buffers wait here until the page has fetched them.
+#[derive(Default)]
+pub(crate) struct SharedBuffers {
+    // By their random id, with the origin of the page they were emitted to.
+    pending: HashMap<String, (String, Vec<u8>)>,
+    callback: Option<SharedBufferCallback>,
+    // The route is only served once the buffers are used.
+    serving: bool,
+}
+
+// 128 random bits, so that other origins in the WebView can't guess the URLs of buffers meant
+// for the page.
+fn random_id() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    unsafe {
+        BCryptGenRandom(
+            BCRYPT_ALG_HANDLE::default(),
+            bytes.as_mut_ptr(),
+            bytes.len() as u32,
+            BCRYPT_USE_SYSTEM_PREFERRED_RNG,
+        )?
+    };
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// `about:blank`, `file:` and the like; the page's fetches say `Origin: null` then.
+fn page_origin(url: &str) -> String {
+    match url.contains("://") && !url.starts_with("file:") {
+        true => permission::origin(url).to_string(),
+        false => "null".to_string(),
+    }
+}
+
+fn handle(webview: &WebView, request: &Request) -> Response {
+    let buffers = &webview.shared_buffers;
+    let origin = request
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("origin"))
+        .map(|(_, value)| value.as_str())
+        .unwrap_or_default();
+    let path = request.uri.trim_start_matches("taco-buffer://local/");
+    // Only the page itself, not its iframes from elsewhere, which would otherwise be able to
+    // read the page's buffers or post into `on_shared_buffer`.
+    let allowed = match (request.method.as_str(), path) {
+        ("GET", id) => buffers
+            .borrow()
+            .pending
+            .get(id)
+            .is_some_and(|(page, _)| page == origin),
+        _ => webview.url().is_ok_and(|url| page_origin(&url) == origin),
+    };
+    if !allowed {
+        return Response::new(403, Vec::new());
+    }
+    // The page's origin is never ours.
+    let cors = |response: Response| {
+        response
+            .with_header("Access-Control-Allow-Origin", origin)
+            .with_header("Access-Control-Allow-Methods", "GET, POST")
+            .with_header("Vary", "Origin")
+    };
+    match (request.method.as_str(), path) {
+        ("OPTIONS", _) => cors(Response::new(204, Vec::new())),
+        ("POST", "post") => {
+            // Cloned out, so that the callback can post buffers back.
+            let callback = buffers.borrow().callback.clone();
+            match callback {
+                Some(f) => {
+                    (f.borrow_mut())(request.body.clone());
+                    cors(Response::new(204, Vec::new()))
+                }
+                None => cors(Response::new(404, Vec::new())),
+            }
+        }
+        ("GET", id) => match buffers.borrow_mut().pending.remove(id) {
+            Some((_, buffer)) => {
+                cors(Response::ok(buffer).with_header("Content-Type", "application/octet-stream"))
+            }
+            None => cors(Response::not_found()),
+        },
+        _ => cors(Response::new(405, Vec::new())),
+    }
+}
+
+// The page's side, `taco.onSharedBuffer` and `taco.postSharedBuffer`.
+pub(crate) fn inject(webview: &WebView) -> Result<()> {
+    webview.init(include_str!("shared_buffer.js"))?;
+    Ok(())
+}
+
+impl WebView {
+    fn serve_shared_buffers(&self) -> Result<()> {
+        if std::mem::replace(&mut self.shared_buffers.borrow_mut().serving, true) {
+            return Ok(());
+        }
+        let w = self.clone();
+        protocol::serve(
+            self,
+            vec![Route::new(
+                "taco-buffer",
+                Box::new(move |request| handle(&w, request)),
+            )],
+        )
+    }
+
+    // Hands `bytes` to the page's `taco.onSharedBuffer` callbacks as an ArrayBuffer, without
+    // serializing them to JSON. Buffers the page never picks up, e.g. because it navigated
+    // away meanwhile, are kept until the WebView is dropped.
+    pub fn post_shared_buffer(&self, bytes: &[u8]) -> Result<&Self> {
+        self.serve_shared_buffers()?;
+        let id = random_id()?;
+        let origin = page_origin(&self.url()?);
+        self.shared_buffers
+            .borrow_mut()
+            .pending
+            .insert(id.clone(), (origin, bytes.to_vec()));
+        let url = format!("https://local.taco-buffer.localhost/{}", id);
+        self.emit("_taco_sharedBuffer", serde_json::json!({ "url": url }))
+    }
+
+    // Receives the buffers passed to `taco.postSharedBuffer` in the page, only from the page
+    // itself. Replaces the previous callback.
+    pub fn on_shared_buffer(&self, f: impl FnMut(Vec<u8>) + 'static) -> Result<Subscription> {
+        self.serve_shared_buffers()?;
+        let callback: SharedBufferCallback = Rc::new(RefCell::new(f));
+        self.shared_buffers.borrow_mut().callback = Some(callback.clone());
+
+        let buffers = self.shared_buffers.clone();
+        Ok(self.track(move || {
+            let mut buffers = buffers.borrow_mut();
+            if buffers
+                .callback
+                .as_ref()
+                .is_some_and(|f| Rc::ptr_eq(f, &callback))
+            {
+                buffers.callback = None;
+            }
+            Ok(())
+        }))
+    }
+}
@@ -0,0 +1,20 @@
+(function () {
+    const taco = window.taco = window.taco || {};
+
+    // Listeners of `taco.on('before-quit', e => ...)` can call `e.preventDefault()` to veto,
+    // or `e.waitUntil(promise)` to delay quitting until the promise settles.
+    window._taco_beforeQuit = async id => {
+        let allowed = true;
+        const pending = [];
+        taco._emit('before-quit', {
+            preventDefault: () => { allowed = false; },
+            waitUntil: promise => { pending.push(promise); },
+        });
+        try {
+            await Promise.all(pending);
+        } catch (e) {
+            allowed = false;
+        }
+        _taco_quitReply(id, allowed);
+    };
+})();
@@ -0,0 +1,185 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde_json::Value;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::{thread::WebViewHandle, window::WindowRunner, Result, WebView};
+
+const QUIT_TIMER_ID: usize = 2;
+// Pages that don't answer `before-quit` in time are treated as agreeing to quit.
+const QUIT_TIMEOUT_MS: u32 = 5000;
+
+struct PendingQuit {
+    id: u64,
+    // The window whose thread runs the timeout and the closing.
+    timer: WebViewHandle,
+    waiting: usize,
+}
+
+#[derive(Default)]
+struct State {
+    windows: Vec<WebViewHandle>,
+    before_quit: Vec<Box<dyn FnMut() -> bool + Send>>,
+    pending: Option<PendingQuit>,
+    next_id: u64,
+}
+
+// Quits all managed windows together, whichever threads they run on: `before-quit` is
+// broadcast to the Rust listeners and to every page, any of which can veto it, and only then
+// are the windows closed in order. Each window is only touched from its own thread, through
+// `WebViewHandle::dispatch`.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    state: Arc<Mutex<State>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    // Closing a managed window asks to quit the whole app instead of destroying it right away.
+    // Call it on the window's thread.
+    pub fn manage<T: 'static>(&self, webview: &WebView, wrun: &mut WindowRunner<T>) -> Result<()> {
+        self.state().windows.push(webview.handle());
+
+        let s = self.clone();
+        webview.bind_unsafe("_taco_quitReply", move |params| {
+            if let [Value::Number(id), Value::Bool(allowed)] = &params[..] {
+                s.reply(id.as_u64().unwrap_or_default(), *allowed);
+                return Ok(Value::Null);
+            }
+            Err("Usage: _taco_quitReply(id, allowed)".into())
+        });
+        webview.init(include_str!("shutdown.js"))?;
+
+        wrun.reset_event_listeners(WM_CLOSE);
+        let s = self.clone();
+        wrun.add_event_listener(WM_CLOSE, move |_, _, _| s.quit());
+
+        let s = self.clone();
+        wrun.add_event_listener(WM_TIMER, move |wparam, _, _| {
+            if wparam.0 == QUIT_TIMER_ID {
+                s.finish();
+            }
+        });
+
+        Ok(())
+    }
+
+    // Return false to veto. Long-running work such as flushing saves can simply block here.
+    // Called on the thread of the window that asked to quit.
+    pub fn on_before_quit(&self, f: impl FnMut() -> bool + Send + 'static) {
+        self.state().before_quit.push(Box::new(f));
+    }
+
+    pub fn is_quitting(&self) -> bool {
+        self.state().pending.is_some()
+    }
+
+    // From any thread.
+    pub fn quit(&self) {
+        if self.is_quitting() {
+            return;
+        }
+
+        // Not locked while they run, so that they can call back.
+        let mut listeners = std::mem::take(&mut self.state().before_quit);
+        let allowed = listeners.iter_mut().all(|f| f());
+        let mut state = self.state();
+        listeners.append(&mut state.before_quit);
+        state.before_quit = listeners;
+        if !allowed || state.pending.is_some() {
+            return;
+        }
+
+        let windows = state.windows.clone();
+        let timer = match windows.first() {
+            Some(first) => *first,
+            None => return,
+        };
+        let id = state.next_id;
+        state.next_id += 1;
+        state.pending = Some(PendingQuit {
+            id,
+            timer,
+            waiting: windows.len(),
+        });
+        drop(state);
+
+        let _ = timer.dispatch(|webview| {
+            unsafe { SetTimer(webview.hwnd, QUIT_TIMER_ID, QUIT_TIMEOUT_MS, None) };
+            Ok(())
+        });
+        for handle in windows {
+            let s = self.clone();
+            let script = format!("window._taco_beforeQuit({})", id);
+            let dispatched = handle.dispatch(move |webview| {
+                // A page that can't run the script has nothing to flush.
+                if webview.eval(&script).is_err() {
+                    s.reply(id, true);
+                }
+                Ok(())
+            });
+            // Nor has a window that's gone.
+            if dispatched.is_err() {
+                self.reply(id, true);
+            }
+        }
+    }
+
+    fn reply(&self, id: u64, allowed: bool) {
+        let mut state = self.state();
+        let pending = match &mut state.pending {
+            Some(pending) if pending.id == id => pending,
+            _ => return,
+        };
+        let timer = pending.timer;
+        if !allowed {
+            state.pending = None;
+            drop(state);
+            let _ = timer.dispatch(|webview| {
+                unsafe { KillTimer(webview.hwnd, QUIT_TIMER_ID) };
+                Ok(())
+            });
+            return;
+        }
+        pending.waiting = pending.waiting.saturating_sub(1);
+        if pending.waiting == 0 {
+            drop(state);
+            // Outside of the binding call that is still resolving.
+            let s = self.clone();
+            let _ = timer.dispatch(move |_| {
+                s.finish();
+                Ok(())
+            });
+        }
+    }
+
+    // On the timer window's thread.
+    fn finish(&self) {
+        let (timer, windows) = {
+            let mut state = self.state();
+            let pending = match state.pending.take() {
+                Some(pending) => pending,
+                None => return,
+            };
+            (pending.timer, std::mem::take(&mut state.windows))
+        };
+        unsafe { KillTimer(timer.hwnd, QUIT_TIMER_ID) };
+
+        for handle in windows {
+            let _ = handle.dispatch(|webview| {
+                unsafe {
+                    let _ = webview.controller.Close();
+                    DestroyWindow(webview.hwnd);
+                }
+                Ok(())
+            });
+        }
+    }
+}
@@ -0,0 +1,47 @@
+({
+    // A selector that finds `element` again after a reload: its id, its name among the
+    // fields with that name, or its path from the body.
+    selector(element) {
+        if (element.id) return '#' + CSS.escape(element.id);
+        if (element.name) {
+            const named = document.getElementsByName(element.name);
+            const index = Array.prototype.indexOf.call(named, element);
+            return `[name="${CSS.escape(element.name)}"]` + (index > 0 ? `:nth-match(${index})` : '');
+        }
+        const path = [];
+        for (let e = element; e && e !== document.body; e = e.parentElement) {
+            const index = Array.prototype.indexOf.call(e.parentElement.children, e) + 1;
+            path.unshift(`${e.tagName.toLowerCase()}:nth-child(${index})`);
+        }
+        return 'body > ' + path.join(' > ');
+    },
+
+    find(selector) {
+        const match = /^(.*):nth-match\((\d+)\)$/.exec(selector);
+        if (!match) return document.querySelector(selector);
+        return document.querySelectorAll(match[1])[Number(match[2])] || null;
+    },
+
+    capture(form) {
+        const fields = !form ? [] : Array.from(document.querySelectorAll('input, textarea, select'))
+            .filter(e => !['password', 'file', 'hidden', 'button', 'submit', 'reset', 'image'].includes(e.type))
+            .map(e => ({ selector: this.selector(e), value: e.value, checked: !!e.checked }));
+        return { scrollX: window.scrollX, scrollY: window.scrollY, form: fields };
+    },
+
+    restore(state) {
+        for (const field of state.form) {
+            const element = this.find(field.selector);
+            if (!element) continue;
+            if (element.type === 'checkbox' || element.type === 'radio') {
+                element.checked = field.checked;
+            } else {
+                element.value = field.value;
+            }
+            // So frameworks holding the value in their own state pick it up too.
+            element.dispatchEvent(new Event('input', { bubbles: true }));
+            element.dispatchEvent(new Event('change', { bubbles: true }));
+        }
+        window.scrollTo(state.scrollX, state.scrollY);
+    },
+})
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use windows::Win32::Foundation::PWSTR;
+
+use webview2_com::*;
+
+use crate::{Result, WebView};
+
+// What's needed to bring a page back after the WebView or its renderer was recreated.
+// Serializable, so it can also be kept across restarts, e.g. for a runtime update.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateSnapshot {
+    pub url: String,
+    pub scroll_x: f64,
+    pub scroll_y: f64,
+    pub zoom: f64,
+    // Empty unless asked for. Password and file inputs are always left out.
+    pub form: Vec<FormField>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FormField {
+    pub selector: String,
+    pub value: String,
+    // For checkboxes and radio buttons.
+    pub checked: bool,
+}
+
+// The part of a snapshot that comes from the page.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PageState {
+    pub scroll_x: f64,
+    pub scroll_y: f64,
+    pub form: Vec<FormField>,
+}
+
+impl PageState {
+    pub(crate) fn into_snapshot(self, url: String, zoom: f64) -> StateSnapshot {
+        StateSnapshot {
+            url,
+            scroll_x: self.scroll_x,
+            scroll_y: self.scroll_y,
+            zoom,
+            form: self.form,
+        }
+    }
+}
+
+// An init script that reports the page state to `binding` whenever the user scrolls, or edits
+// a field if `form` is set. A crashed renderer can't be asked anymore, so `enable_auto_recovery`
+// keeps the last report instead. The fields are only scanned again after edits.
+pub(crate) fn reporter_script(binding: &str, form: bool) -> String {
+    format!(
+        r#"(function () {{
+    const state = {state};
+    let fields = [];
+    let edited = false;
+    let timer = null;
+    const report = () => {{
+        clearTimeout(timer);
+        timer = setTimeout(() => {{
+            if (edited) fields = state.capture(true).form;
+            edited = false;
+            window.{binding}({{ scrollX: window.scrollX, scrollY: window.scrollY, form: fields }});
+        }}, 300);
+    }};
+    window.addEventListener('scroll', report, true);
+    if ({form}) {{
+        const edit = () => {{
+            edited = true;
+            report();
+        }};
+        window.addEventListener('input', edit, true);
+        window.addEventListener('change', edit, true);
+    }}
+}})();"#,
+        state = include_str!("state.js"),
+        binding = binding,
+        form = form,
+    )
+}
+
+impl WebView {
+    // Captures the URL, scroll position and zoom, plus the values of the page's form fields if
+    // `form` is set, for `restore_state` on this or another WebView.
+    pub fn snapshot_state(&self, form: bool) -> Result<StateSnapshot> {
+        let mut url = PWSTR::default();
+        unsafe { self.core.Source(&mut url)? };
+        let page =
+            self.evaluate_in_page(&format!("({}).capture({})", include_str!("state.js"), form))?;
+        let page: PageState = serde_json::from_value(page)?;
+        Ok(page.into_snapshot(take_pwstr(url), self.zoom()?))
+    }
+
+    // Navigates to the snapshot's URL and, once it has loaded, fills in its form fields and
+    // scrolls to where it was. Content loaded later by the page can shift the position.
+    pub fn restore_state(&self, snapshot: &StateSnapshot) -> Result<&Self> {
+        if snapshot.zoom > 0. {
+            self.set_zoom(snapshot.zoom)?;
+        }
+        self.navigate(&snapshot.url)?;
+        let state = serde_json::json!({
+            "scrollX": snapshot.scroll_x,
+            "scrollY": snapshot.scroll_y,
+            "form": snapshot.form,
+        });
+        self.evaluate_in_page(&format!(
+            "({}).restore({})",
+            include_str!("state.js"),
+            state
+        ))?;
+        Ok(self)
+    }
+}
@@ -0,0 +1,90 @@
+use std::{cell::Cell, sync::mpsc};
+
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::BOOL,
+        UI::WindowsAndMessaging::{SIZE_MAXIMIZED, SIZE_MINIMIZED, SIZE_RESTORED, WM_SIZE},
+    },
+};
+
+use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
+
+use crate::{window::WindowRunner, Error, Result, WebView};
+
+impl WebView {
+    // Hides the WebView and freezes its page, so WebView2 can drop caches and other memory it
+    // can rebuild, until `resume`. Blocks until that's done; false if WebView2 declined, e.g. for
+    // a page playing audio.
+    pub fn suspend(&self) -> Result<bool> {
+        let core = self.core.cast::<ICoreWebView2_3>()?;
+        // TrySuspend fails for visible WebViews.
+        unsafe { self.controller.SetIsVisible(false)? };
+
+        let (tx, rx) = mpsc::channel();
+        TrySuspendCompletedHandler::wait_for_async_operation(
+            Box::new(move |handler| unsafe {
+                core.TrySuspend(handler)
+                    .map_err(webview2_com::Error::WindowsError)
+            }),
+            Box::new(move |error_code, suspended| {
+                error_code?;
+                tx.send(suspended).expect("send over mpsc channel");
+                Ok(())
+            }),
+        )?;
+        let suspended = rx
+            .recv()
+            .map_err(|_| Error::WebView2Error(webview2_com::Error::SendError))?;
+        if !suspended {
+            unsafe { self.controller.SetIsVisible(true)? };
+        }
+        Ok(suspended)
+    }
+
+    // Wakes a suspended WebView and shows it again. Scripts, input and navigation resume it
+    // by themselves, but it stays hidden until this is called.
+    pub fn resume(&self) -> Result<&Self> {
+        unsafe {
+            self.core.cast::<ICoreWebView2_3>()?.Resume()?;
+            self.controller.SetIsVisible(true)?;
+        }
+        Ok(self)
+    }
+
+    pub fn is_suspended(&self) -> Result<bool> {
+        let mut suspended = BOOL::default();
+        unsafe {
+            self.core
+                .cast::<ICoreWebView2_3>()?
+                .IsSuspended(&mut suspended)?
+        };
+        Ok(suspended.as_bool())
+    }
+
+    // Suspends the WebView while its window is minimized, for tray-style apps that spend most
+    // of their time there.
+    pub fn suspend_when_minimized<T: 'static>(&self, wrun: &mut WindowRunner<T>) -> Result<()> {
+        let core = self.core.cast::<ICoreWebView2_3>()?;
+        let controller = self.controller.clone();
+        // So that ordinary resizes leave the WebView alone.
+        let minimized = Cell::new(false);
+        wrun.add_event_listener(WM_SIZE, move |wparam, _, _| unsafe {
+            match wparam.0 as u32 {
+                SIZE_MINIMIZED => {
+                    minimized.set(true);
+                    // Not waited for, as `suspend` would, inside the window procedure.
+                    let _ = controller.SetIsVisible(false);
+                    let _ = core
+                        .TrySuspend(TrySuspendCompletedHandler::create(Box::new(|_, _| Ok(()))));
+                }
+                SIZE_RESTORED | SIZE_MAXIMIZED if minimized.replace(false) => {
+                    let _ = core.Resume();
+                    let _ = controller.SetIsVisible(true);
+                }
+                _ => {}
+            }
+        });
+        Ok(())
+    }
+}
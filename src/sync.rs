@@ -0,0 +1,260 @@
+use std::{
+    cell::{Cell, RefCell},
+    ffi::c_void,
+    mem::ManuallyDrop,
+    rc::Rc,
+};
+
+use serde_json::{json, Value};
+use windows::{
+    core::{IUnknown, Interface, GUID, HRESULT},
+    Win32::{
+        Foundation::{
+            BSTR, DISP_E_BADPARAMCOUNT, DISP_E_MEMBERNOTFOUND, DISP_E_TYPEMISMATCH,
+            DISP_E_UNKNOWNNAME, E_NOINTERFACE, E_NOTIMPL, PWSTR, S_OK,
+        },
+        System::{
+            Com::{
+                IDispatch, IDispatchVtbl, DISPPARAMS, EXCEPINFO, VARIANT, VARIANT_0, VARIANT_0_0,
+                VARIANT_0_0_0,
+            },
+            Ole::{VT_BSTR, VT_DISPATCH},
+        },
+    },
+};
+
+use crate::{BindingsMap, Result, WebView};
+
+// The page sees this as `chrome.webview.hostObjects.sync._taco`.
+const HOST_OBJECT: &str = "_taco";
+// The only member of the host object: `call(name, argsJson) -> resultJson`.
+const CALL_DISPID: i32 = 1;
+
+// A hand-rolled IDispatch; the `implement` macro of this windows version can't handle VARIANTs.
+// WebView2 only calls host objects on the UI thread, so the reference count needn't be atomic.
+#[repr(C)]
+struct SyncBindings {
+    vtable: *const IDispatchVtbl,
+    refs: Cell<u32>,
+    bindings: Rc<RefCell<BindingsMap>>,
+}
+
+static VTABLE: IDispatchVtbl = IDispatchVtbl(
+    query_interface,
+    add_ref,
+    release,
+    get_type_info_count,
+    get_type_info,
+    get_ids_of_names,
+    invoke,
+);
+
+impl SyncBindings {
+    fn create(bindings: Rc<RefCell<BindingsMap>>) -> IDispatch {
+        let object = Box::new(SyncBindings {
+            vtable: &VTABLE,
+            refs: Cell::new(1),
+            bindings,
+        });
+        unsafe { std::mem::transmute(Box::into_raw(object)) }
+    }
+}
+
+unsafe extern "system" fn query_interface(
+    this: *mut c_void,
+    iid: &GUID,
+    interface: *mut *mut c_void,
+) -> HRESULT {
+    if *iid == IUnknown::IID || *iid == IDispatch::IID {
+        add_ref(this);
+        *interface = this;
+        S_OK
+    } else {
+        *interface = std::ptr::null_mut();
+        E_NOINTERFACE
+    }
+}
+
+unsafe extern "system" fn add_ref(this: *mut c_void) -> u32 {
+    let refs = &(*(this as *const SyncBindings)).refs;
+    refs.set(refs.get() + 1);
+    refs.get()
+}
+
+unsafe extern "system" fn release(this: *mut c_void) -> u32 {
+    let refs = (*(this as *const SyncBindings)).refs.get() - 1;
+    (*(this as *const SyncBindings)).refs.set(refs);
+    if refs == 0 {
+        drop(Box::from_raw(this as *mut SyncBindings));
+    }
+    refs
+}
+
+unsafe extern "system" fn get_type_info_count(_this: *mut c_void, count: *mut u32) -> HRESULT {
+    *count = 0;
+    S_OK
+}
+
+unsafe extern "system" fn get_type_info(
+    _this: *mut c_void,
+    _itinfo: u32,
+    _lcid: u32,
+    _info: *mut *mut c_void,
+) -> HRESULT {
+    E_NOTIMPL
+}
+
+unsafe extern "system" fn get_ids_of_names(
+    _this: *mut c_void,
+    _riid: *const GUID,
+    names: *const PWSTR,
+    count: u32,
+    _lcid: u32,
+    dispids: *mut i32,
+) -> HRESULT {
+    let mut result = S_OK;
+    for i in 0..count as usize {
+        let dispid = if i == 0 && read_pwstr(*names.add(i)) == "call" {
+            CALL_DISPID
+        } else {
+            result = DISP_E_UNKNOWNNAME;
+            -1
+        };
+        *dispids.add(i) = dispid;
+    }
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe extern "system" fn invoke(
+    this: *mut c_void,
+    dispid: i32,
+    _riid: *const GUID,
+    _lcid: u32,
+    _flags: u16,
+    params: *const DISPPARAMS,
+    result: *mut VARIANT,
+    _excepinfo: *mut EXCEPINFO,
+    _argerr: *mut u32,
+) -> HRESULT {
+    if dispid != CALL_DISPID {
+        return DISP_E_MEMBERNOTFOUND;
+    }
+    let params = &*params;
+    if params.cArgs != 2 {
+        return DISP_E_BADPARAMCOUNT;
+    }
+    // Arguments arrive in reverse order.
+    let (name, args) = match (
+        read_bstr(&*params.rgvarg.add(1)),
+        read_bstr(&*params.rgvarg),
+    ) {
+        (Some(name), Some(args)) => (name, args),
+        _ => return DISP_E_TYPEMISMATCH,
+    };
+
+    // Cloned out, so that the binding can bind and unbind; panicking here would abort.
+    let binding = (*(this as *const SyncBindings))
+        .bindings
+        .borrow()
+        .get(&name)
+        .cloned();
+    let reply = match (serde_json::from_str::<Vec<Value>>(&args), binding) {
+        (Ok(args), Some(f)) => match f.try_borrow_mut() {
+            Ok(mut f) => match f(args) {
+                Ok(value) => json!({ "ok": value }),
+                Err(err) => json!({ "error": err }),
+            },
+            Err(_) => json!({ "error": format!("{} is still running", name) }),
+        },
+        (Ok(_), None) => json!({ "error": format!("{} is not bound", name) }),
+        (Err(err), _) => json!({ "error": err.to_string() }),
+    };
+
+    if !result.is_null() {
+        *result = bstr_variant(BSTR::from(reply.to_string()));
+    }
+    S_OK
+}
+
+// Unlike `take_pwstr`, leaves the caller's string alone.
+unsafe fn read_pwstr(s: PWSTR) -> String {
+    let mut len = 0;
+    while *s.0.add(len) != 0 {
+        len += 1;
+    }
+    String::from_utf16_lossy(std::slice::from_raw_parts(s.0, len))
+}
+
+unsafe fn read_bstr(variant: &VARIANT) -> Option<String> {
+    let variant = &variant.Anonymous.Anonymous;
+    (variant.vt == VT_BSTR as u16).then(|| variant.Anonymous.bstrVal.to_string())
+}
+
+fn bstr_variant(value: BSTR) -> VARIANT {
+    VARIANT {
+        Anonymous: VARIANT_0 {
+            Anonymous: ManuallyDrop::new(VARIANT_0_0 {
+                vt: VT_BSTR as u16,
+                wReserved1: 0,
+                wReserved2: 0,
+                wReserved3: 0,
+                Anonymous: VARIANT_0_0_0 {
+                    bstrVal: ManuallyDrop::new(value),
+                },
+            }),
+        },
+    }
+}
+
+fn dispatch_variant(value: IDispatch) -> VARIANT {
+    VARIANT {
+        Anonymous: VARIANT_0 {
+            Anonymous: ManuallyDrop::new(VARIANT_0_0 {
+                vt: VT_DISPATCH as u16,
+                wReserved1: 0,
+                wReserved2: 0,
+                wReserved3: 0,
+                Anonymous: VARIANT_0_0_0 {
+                    pdispVal: ManuallyDrop::new(Some(value)),
+                },
+            }),
+        },
+    }
+}
+
+impl WebView {
+    // Like `bind`, but `window[name](...)` returns the result (or throws) synchronously instead of
+    // returning a Promise. The page blocks until `f` returns, so keep it to cheap getters.
+    pub fn bind_sync<F>(&self, name: &str, f: F) -> Result<()>
+    where
+        F: FnMut(Vec<Value>) -> std::result::Result<Value, String> + 'static,
+    {
+        self._apartment.assert_thread("WebView::bind_sync");
+        self.forget_binding(name);
+        // Again after unbinding them all, which replaces the host object with a new one.
+        let first = self.sync_bindings.borrow().is_empty();
+        self.sync_bindings
+            .borrow_mut()
+            .insert(name.to_string(), Rc::new(RefCell::new(f)));
+
+        if first {
+            let mut variant = dispatch_variant(SyncBindings::create(self.sync_bindings.clone()));
+            unsafe {
+                self.core.AddHostObjectToScript(HOST_OBJECT, &mut variant)?;
+                ManuallyDrop::drop(&mut (*variant.Anonymous.Anonymous).Anonymous.pdispVal);
+            }
+        }
+
+        let js = format!(
+            r#"window[{name}] = (...args) => {{
+                const reply = JSON.parse(chrome.webview.hostObjects.sync.{object}.call({name}, JSON.stringify(args)));
+                if ('error' in reply) throw new Error(reply.error);
+                return reply.ok;
+            }};"#,
+            name = Value::String(name.to_string()),
+            object = HOST_OBJECT,
+        );
+        self.inject_binding(name, js)
+    }
+}
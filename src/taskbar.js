@@ -0,0 +1,11 @@
+(function () {
+    const taco = window.taco = window.taco || {};
+    taco.window = taco.window || {};
+
+    taco.window.setProgress = (value, state = 'normal') => _taco_setProgress(value, state);
+    taco.window.setBadge = count => _taco_setBadge(count === undefined ? null : count);
+    taco.window.clearBadge = () => _taco_setBadge(0);
+
+    navigator.setAppBadge = taco.window.setBadge;
+    navigator.clearAppBadge = taco.window.clearBadge;
+})();
@@ -0,0 +1,200 @@
+use serde_json::Value;
+use windows::Win32::{
+    Foundation::{HWND, RECT},
+    Graphics::Gdi::*,
+    System::Com::*,
+    UI::{Shell::*, WindowsAndMessaging::*},
+};
+
+use crate::{Result, WebView};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressState {
+    None,
+    Indeterminate,
+    Normal,
+    Error,
+    Paused,
+}
+
+// Same semantics as the Badging API: a flag is a plain dot without a number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Badge {
+    None,
+    Flag,
+    Count(u32),
+}
+
+impl ProgressState {
+    fn from_str(state: &str) -> Option<Self> {
+        match state {
+            "none" => Some(Self::None),
+            "indeterminate" => Some(Self::Indeterminate),
+            "normal" => Some(Self::Normal),
+            "error" => Some(Self::Error),
+            "paused" => Some(Self::Paused),
+            _ => None,
+        }
+    }
+
+    fn flag(&self) -> TBPFLAG {
+        match self {
+            Self::None => TBPF_NOPROGRESS,
+            Self::Indeterminate => TBPF_INDETERMINATE,
+            Self::Normal => TBPF_NORMAL,
+            Self::Error => TBPF_ERROR,
+            Self::Paused => TBPF_PAUSED,
+        }
+    }
+}
+
+fn taskbar_list() -> Result<ITaskbarList3> {
+    unsafe {
+        let taskbar: ITaskbarList3 = CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER)?;
+        taskbar.HrInit()?;
+        Ok(taskbar)
+    }
+}
+
+// Draws a red badge (with the count, if any) as an overlay icon.
+fn badge_icon(text: &str) -> HICON {
+    unsafe {
+        let size = GetSystemMetrics(SM_CXSMICON);
+        let rect = RECT {
+            left: 0,
+            top: 0,
+            right: size,
+            bottom: size,
+        };
+
+        let screen = GetDC(None);
+        let hdc = CreateCompatibleDC(screen);
+        let color = CreateCompatibleBitmap(screen, size, size);
+        let mask = CreateBitmap(size, size, 1, 1, std::ptr::null());
+
+        // Black in the color bitmap and white in the mask are transparent.
+        let old = SelectObject(hdc, color);
+        FillRect(hdc, &rect, HBRUSH(GetStockObject(BLACK_BRUSH).0));
+        let red = CreateSolidBrush(0x002020E0);
+        let old_brush = SelectObject(hdc, red);
+        let old_pen = SelectObject(hdc, GetStockObject(NULL_PEN));
+        Ellipse(hdc, 0, 0, size + 1, size + 1);
+        if !text.is_empty() {
+            let mut text_rect = rect;
+            SetBkMode(hdc, TRANSPARENT);
+            SetTextColor(hdc, 0x00FFFFFF);
+            DrawTextW(
+                hdc,
+                text,
+                -1,
+                &mut text_rect,
+                DT_CENTER | DT_VCENTER | DT_SINGLELINE,
+            );
+        }
+
+        SelectObject(hdc, mask);
+        FillRect(hdc, &rect, HBRUSH(GetStockObject(WHITE_BRUSH).0));
+        SelectObject(hdc, GetStockObject(BLACK_BRUSH));
+        Ellipse(hdc, 0, 0, size + 1, size + 1);
+
+        SelectObject(hdc, old_pen);
+        SelectObject(hdc, old_brush);
+        SelectObject(hdc, old);
+        DeleteObject(red);
+        DeleteDC(hdc);
+        ReleaseDC(None, screen);
+
+        let info = ICONINFO {
+            fIcon: true.into(),
+            hbmMask: mask,
+            hbmColor: color,
+            ..Default::default()
+        };
+        let icon = CreateIconIndirect(&info);
+        DeleteObject(mask);
+        DeleteObject(color);
+        icon
+    }
+}
+
+pub fn set_progress(hwnd: HWND, state: ProgressState, value: f64) -> Result<()> {
+    let taskbar = taskbar_list()?;
+    unsafe {
+        taskbar.SetProgressState(hwnd, state.flag())?;
+        if state != ProgressState::None && state != ProgressState::Indeterminate {
+            let completed = (value.clamp(0., 1.) * 1000.) as u64;
+            taskbar.SetProgressValue(hwnd, completed, 1000)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn set_badge(hwnd: HWND, badge: Badge) -> Result<()> {
+    let taskbar = taskbar_list()?;
+    unsafe {
+        match badge {
+            Badge::None => taskbar.SetOverlayIcon(hwnd, None, "")?,
+            Badge::Flag => {
+                let icon = badge_icon("");
+                taskbar.SetOverlayIcon(hwnd, icon, "")?;
+                DestroyIcon(icon);
+            }
+            Badge::Count(count) => {
+                let text = if count > 99 {
+                    String::from("99+")
+                } else {
+                    count.to_string()
+                };
+                let icon = badge_icon(&text);
+                taskbar.SetOverlayIcon(hwnd, icon, text)?;
+                DestroyIcon(icon);
+            }
+        }
+    }
+    Ok(())
+}
+
+impl WebView {
+    // `value` ranges from 0.0 to 1.0 and is ignored for `None` and `Indeterminate`.
+    pub fn set_progress(&self, state: ProgressState, value: f64) -> Result<&Self> {
+        set_progress(self.hwnd, state, value)?;
+        Ok(self)
+    }
+
+    pub fn set_badge(&self, badge: Badge) -> Result<&Self> {
+        set_badge(self.hwnd, badge)?;
+        Ok(self)
+    }
+}
+
+// Exposes the above as `taco.window.setProgress/setBadge/clearBadge` and `navigator.setAppBadge`.
+pub(crate) fn inject(webview: &WebView) -> Result<()> {
+    let hwnd = webview.hwnd;
+    webview.bind_unsafe("_taco_setProgress", move |params| {
+        if let [Value::Number(value), Value::String(state)] = &params[..] {
+            if let Some(state) = ProgressState::from_str(state) {
+                let value = value.as_f64().unwrap_or_default();
+                set_progress(hwnd, state, value).map_err(|err| err.to_string())?;
+                return Ok(Value::Null);
+            }
+        }
+        Err(r#"Usage: taco.window.setProgress(value, "none" | "indeterminate" | "normal" | "error" | "paused")"#.into())
+    });
+
+    webview.bind_unsafe("_taco_setBadge", move |params| {
+        let badge = match &params[..] {
+            [Value::Null] => Badge::Flag,
+            [Value::Number(count)] => match count.as_u64() {
+                Some(0) => Badge::None,
+                Some(count) => Badge::Count(count as u32),
+                None => return Err("The badge count must be a non-negative integer".into()),
+            },
+            _ => return Err("Usage: taco.window.setBadge(count?)".into()),
+        };
+        set_badge(hwnd, badge).map_err(|err| err.to_string())?;
+        Ok(Value::Null)
+    });
+
+    webview.init(include_str!("taskbar.js"))?;
+    Ok(())
+}
@@ -0,0 +1,38 @@
+(function () {
+    // Added again by every `on_text_input_focus` call.
+    if (window._taco_textInputListening) return;
+    window._taco_textInputListening = true;
+
+    // Text fields, as opposed to checkboxes, buttons and the like.
+    const TEXT_TYPES = ['text', 'search', 'email', 'url', 'tel', 'password', 'number', 'date',
+        'datetime-local', 'month', 'time', 'week'];
+    const kind = element => {
+        if (!(element instanceof Element)) return null;
+        if (element instanceof HTMLTextAreaElement) return element.readOnly ? null : 'textarea';
+        if (element instanceof HTMLInputElement) {
+            return TEXT_TYPES.includes(element.type) && !element.readOnly ? element.type : null;
+        }
+        return element.isContentEditable ? 'contenteditable' : null;
+    };
+    const report = (focused, element) => {
+        const rect = element.getBoundingClientRect();
+        const scale = window.devicePixelRatio;
+        window._taco_textInputFocus({
+            focused,
+            kind: kind(element),
+            inputMode: element.getAttribute('inputmode') || '',
+            x: Math.round(rect.left * scale),
+            y: Math.round(rect.top * scale),
+            width: Math.round(rect.width * scale),
+            height: Math.round(rect.height * scale),
+        });
+    };
+
+    document.addEventListener('focusin', e => {
+        if (kind(e.target)) report(true, e.target);
+    }, true);
+    document.addEventListener('focusout', e => {
+        // Moving straight to another field only reports the new one.
+        if (kind(e.target) && !kind(e.relatedTarget)) report(false, e.target);
+    }, true);
+})();
@@ -0,0 +1,44 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{event::Subscription, Result, WebView};
+
+// An editable element of the top-level document gained or lost focus.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextInputFocus {
+    pub focused: bool,
+    // The input type (`text`, `email`, `number`, ...), `textarea` or `contenteditable`.
+    pub kind: String,
+    // The `inputmode` attribute, e.g. `numeric`, or empty.
+    pub input_mode: String,
+    // The element's bounds in physical pixels, relative to the WebView.
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl WebView {
+    // Calls `f` when a text field, textarea or contenteditable element gains or loses focus,
+    // e.g. to show a custom on-screen keyboard next to it. Fields inside iframes aren't seen.
+    // Call it before navigating. A later call replaces `f`.
+    pub fn on_text_input_focus(
+        &self,
+        mut f: impl FnMut(&TextInputFocus) + 'static,
+    ) -> Result<Subscription> {
+        self.bind_unsafe("_taco_textInputFocus", move |params| {
+            let focus = params
+                .into_iter()
+                .next()
+                .map(serde_json::from_value::<TextInputFocus>)
+                .ok_or("Usage: _taco_textInputFocus(focus)")?
+                .map_err(|err| err.to_string())?;
+            f(&focus);
+            Ok(Value::Null)
+        });
+        let binding = self.track_binding("_taco_textInputFocus");
+        let script = self.track_init(include_str!("text_input.js"))?;
+        Ok(self.merge(&[binding, script]))
+    }
+}
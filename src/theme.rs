@@ -0,0 +1,99 @@
+use windows::Win32::{
+    Foundation::{BOOL, HWND, PSTR},
+    Graphics::Dwm::DwmSetWindowAttribute,
+    System::LibraryLoader::{GetProcAddress, LoadLibraryA},
+    UI::Controls::SetWindowTheme,
+};
+
+// For the native parts taco creates: the title bar, menus and tray menus. Web content follows
+// its own CSS. Message boxes have no dark variant and stay light.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Theme {
+    // Follows the "app mode" in the Windows settings.
+    System,
+    Light,
+    Dark,
+}
+
+// Win32 has no public dark mode for menus, so this goes through the undocumented uxtheme
+// exports Explorer uses, by ordinal. They've been stable since Windows 10 1903; on older
+// systems they're missing and nothing changes.
+const SHOULD_APPS_USE_DARK_MODE: usize = 132;
+const ALLOW_DARK_MODE_FOR_WINDOW: usize = 133;
+const SET_PREFERRED_APP_MODE: usize = 135;
+const FLUSH_MENU_THEMES: usize = 136;
+
+// Not in this SDK's DWMWINDOWATTRIBUTE yet.
+const DWMWA_USE_IMMERSIVE_DARK_MODE: i32 = 20;
+
+// PreferredAppMode
+const APP_MODE_ALLOW_DARK: i32 = 1;
+const APP_MODE_FORCE_DARK: i32 = 2;
+const APP_MODE_FORCE_LIGHT: i32 = 3;
+
+unsafe fn uxtheme(ordinal: usize) -> Option<unsafe extern "system" fn() -> isize> {
+    let module = LoadLibraryA("uxtheme.dll");
+    if module.is_invalid() {
+        return None;
+    }
+    GetProcAddress(module, PSTR(ordinal as *mut u8))
+}
+
+fn is_dark(theme: Theme) -> bool {
+    match theme {
+        Theme::Dark => true,
+        Theme::Light => false,
+        Theme::System => unsafe {
+            uxtheme(SHOULD_APPS_USE_DARK_MODE).is_some_and(|f| {
+                let f: unsafe extern "system" fn() -> bool = std::mem::transmute(f);
+                f()
+            })
+        },
+    }
+}
+
+// Process-wide, for menus created from now on. Call before building windows.
+pub fn set_app_theme(theme: Theme) {
+    let mode = match theme {
+        Theme::System => APP_MODE_ALLOW_DARK,
+        Theme::Light => APP_MODE_FORCE_LIGHT,
+        Theme::Dark => APP_MODE_FORCE_DARK,
+    };
+    unsafe {
+        if let Some(f) = uxtheme(SET_PREFERRED_APP_MODE) {
+            let f: unsafe extern "system" fn(i32) -> i32 = std::mem::transmute(f);
+            f(mode);
+        }
+        if let Some(f) = uxtheme(FLUSH_MENU_THEMES) {
+            let f: unsafe extern "system" fn() = std::mem::transmute(f);
+            f();
+        }
+    }
+}
+
+// The title bar and scrollbars of `hwnd`, and the menus it owns.
+pub fn set_window_theme(hwnd: HWND, theme: Theme) {
+    let dark = is_dark(theme);
+    unsafe {
+        if let Some(f) = uxtheme(ALLOW_DARK_MODE_FOR_WINDOW) {
+            let f: unsafe extern "system" fn(HWND, bool) -> bool = std::mem::transmute(f);
+            f(hwnd, dark);
+        }
+        let _ = SetWindowTheme(
+            hwnd,
+            if dark {
+                "DarkMode_Explorer"
+            } else {
+                "Explorer"
+            },
+            None,
+        );
+        let value = BOOL::from(dark);
+        let _ = DwmSetWindowAttribute(
+            hwnd,
+            DWMWA_USE_IMMERSIVE_DARK_MODE,
+            &value as *const _ as _,
+            std::mem::size_of::<BOOL>() as _,
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use windows::Win32::{
+    Foundation::{HWND, LPARAM, WPARAM},
+    UI::WindowsAndMessaging::{PostMessageA, WM_APP},
+};
+
+use serde_json::Value;
+
+use crate::{
+    resolve,
+    window::{UserMsg, WindowHandle, WindowRunner},
+    Result, WebView,
+};
+
+// Carries closures from `WebViewHandle::dispatch` to the window's thread.
+pub(crate) const WM_WEBVIEW_DISPATCH: u32 = WM_APP + 2;
+
+type WebViewTask = Box<dyn FnOnce(&WebView) -> Result<()>>;
+
+// The `Send` way to reach a WebView: `WebView` itself wraps COM objects of its thread's
+// apartment and can't leave it, so other threads post closures through this instead. They run
+// on the window's thread while it's pumping messages, and are dropped if the window is gone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WebViewHandle {
+    pub hwnd: HWND,
+}
+
+impl WebViewHandle {
+    pub fn dispatch(&self, f: impl FnOnce(&WebView) -> Result<()> + Send + 'static) -> Result<()> {
+        let p = Box::into_raw(Box::new(Box::new(f) as WebViewTask));
+        unsafe {
+            if !PostMessageA(self.hwnd, WM_WEBVIEW_DISPATCH, WPARAM(0), LPARAM(p as _)).as_bool() {
+                drop(Box::from_raw(p));
+                return Err(windows::core::Error::from_win32().into());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn eval(&self, js: impl Into<String>) -> Result<()> {
+        let js = js.into();
+        self.dispatch(move |webview| webview.eval(&js).map(|_| ()))
+    }
+}
+
+// Settles one call of a `WebView::bind_async` binding, from any thread. Dropping it without
+// settling rejects the call, so the page's promise doesn't hang forever.
+#[derive(Debug)]
+pub struct Responder {
+    handle: WebViewHandle,
+    id: u64,
+    settled: bool,
+}
+
+impl Responder {
+    pub(crate) fn new(webview: &WebView, id: u64) -> Self {
+        Self {
+            handle: webview.handle(),
+            id,
+            settled: false,
+        }
+    }
+
+    pub fn resolve(self, value: Value) -> Result<()> {
+        self.respond(Ok(value))
+    }
+
+    pub fn reject(self, error: impl Into<String>) -> Result<()> {
+        self.respond(Err(error.into()))
+    }
+
+    // Fails only if the window is gone.
+    pub fn respond(mut self, result: std::result::Result<Value, String>) -> Result<()> {
+        self.settled = true;
+        settle(self.handle, self.id, result)
+    }
+}
+
+impl Drop for Responder {
+    fn drop(&mut self) {
+        if !self.settled {
+            let _ = settle(
+                self.handle,
+                self.id,
+                Err("Dropped without a response".into()),
+            );
+        }
+    }
+}
+
+fn settle(
+    handle: WebViewHandle,
+    id: u64,
+    result: std::result::Result<Value, String>,
+) -> Result<()> {
+    let (status, value) = match result {
+        Ok(value) => (0, value),
+        Err(err) => (1, Value::String(err)),
+    };
+    handle.dispatch(move |webview| resolve(webview, id, status, value))
+}
+
+impl WebView {
+    pub fn handle(&self) -> WebViewHandle {
+        WebViewHandle { hwnd: self.hwnd }
+    }
+}
+
+pub(crate) fn listen<T: 'static>(webview: &WebView, wrun: &mut WindowRunner<T>) {
+    let w = webview.clone();
+    wrun.add_event_listener(WM_WEBVIEW_DISPATCH, move |_, lparam, _| {
+        let f = unsafe { Box::from_raw(lparam.0 as *mut WebViewTask) };
+        // Panicking here would abort, as this runs in the window procedure; an eval failing
+        // because the page navigated away meanwhile is nothing to bring the app down for.
+        let _ = f(&w);
+    });
+}
+
+// Everything meant to cross threads, checked at compile time.
+#[allow(dead_code)]
+fn assert_send() {
+    fn send<S: Send>() {}
+    send::<WebViewHandle>();
+    send::<Responder>();
+    send::<WindowHandle<()>>();
+    send::<UserMsg<()>>();
+    send::<crate::shutdown::Shutdown>();
+    send::<crate::Error>();
+}
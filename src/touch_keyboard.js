@@ -0,0 +1,20 @@
+(function () {
+    // Only focus that follows a touch opens the keyboard; mouse and pen users have one already.
+    let touched = false;
+    document.addEventListener('pointerdown', e => touched = e.pointerType === 'touch', true);
+
+    const editable = element => element instanceof HTMLTextAreaElement
+        || (element instanceof HTMLInputElement
+            && !['button', 'checkbox', 'color', 'file', 'hidden', 'image', 'radio', 'range',
+                'reset', 'submit'].includes(element.type))
+        || (element instanceof Element && element.isContentEditable);
+
+    document.addEventListener('focusin', e => {
+        if (touched && editable(e.target) && !e.target.readOnly) window._taco_touchKeyboard(true);
+    }, true);
+    document.addEventListener('focusout', e => {
+        if (touched && editable(e.target) && !editable(e.relatedTarget)) {
+            window._taco_touchKeyboard(false);
+        }
+    }, true);
+})();
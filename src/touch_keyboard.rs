@@ -0,0 +1,114 @@
+use std::ffi::c_void;
+
+use serde_json::Value;
+use windows::{
+    core::{IUnknown, IUnknownVtbl, Interface, GUID, HRESULT},
+    Win32::{
+        Foundation::{HWND, PWSTR, REGDB_E_CLASSNOTREG},
+        System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER, CLSCTX_LOCAL_SERVER},
+        UI::{Shell::ShellExecuteW, WindowsAndMessaging::*},
+    },
+};
+
+use crate::{event::Subscription, GetWindowLong, Result, WebView};
+
+// UIHostNoLaunch, the touch keyboard's COM server. Only registered while TabTip.exe runs.
+const CLSID_UI_HOST_NO_LAUNCH: GUID = GUID::from_u128(0x4ce576fa_83dc_4f88_951c_9d0782b4e376);
+
+// ITipInvocation, which isn't in the SDK.
+#[repr(transparent)]
+struct ITipInvocation(IUnknown);
+
+#[repr(C)]
+struct ITipInvocationVtbl {
+    unknown: IUnknownVtbl,
+    toggle: unsafe extern "system" fn(this: *mut c_void, hwnd: HWND) -> HRESULT,
+}
+
+unsafe impl Interface for ITipInvocation {
+    type Vtable = ITipInvocationVtbl;
+    const IID: GUID = GUID::from_u128(0x37c994e7_432b_4834_a2f7_dce1f13b834b);
+}
+
+fn is_visible() -> bool {
+    unsafe {
+        let hwnd = FindWindowW("IPTip_Main_Window", PWSTR::default());
+        hwnd.0 != 0
+            && IsWindowVisible(hwnd).as_bool()
+            && GetWindowLong(hwnd, GWL_STYLE) & WS_DISABLED as isize == 0
+    }
+}
+
+// TabTip.exe registers UIHostNoLaunch, and shows the keyboard, when it starts.
+fn launch_tabtip() -> Result<()> {
+    // The 64-bit Common Files, also from 32-bit processes.
+    let common = std::env::var("CommonProgramW6432")
+        .or_else(|_| std::env::var("CommonProgramFiles"))
+        .unwrap_or_else(|_| r"C:\Program Files\Common Files".to_string());
+    let tabtip = format!(r"{}\microsoft shared\ink\TabTip.exe", common);
+    let result = unsafe {
+        ShellExecuteW(
+            HWND::default(),
+            "open",
+            tabtip.as_str(),
+            PWSTR::default(),
+            PWSTR::default(),
+            SW_SHOWNORMAL as i32,
+        )
+    };
+    if result.0 <= 32 {
+        return Err(windows::core::Error::from_win32().into());
+    }
+    Ok(())
+}
+
+fn toggle() -> Result<()> {
+    unsafe {
+        let tip: ITipInvocation = match CoCreateInstance(
+            &CLSID_UI_HOST_NO_LAUNCH,
+            None,
+            CLSCTX_INPROC_SERVER | CLSCTX_LOCAL_SERVER,
+        ) {
+            Ok(tip) => tip,
+            Err(err) if err.code() == REGDB_E_CLASSNOTREG => return launch_tabtip(),
+            Err(err) => return Err(err.into()),
+        };
+        (tip.vtable().toggle)(std::mem::transmute_copy(&tip), GetDesktopWindow()).ok()?;
+    }
+    Ok(())
+}
+
+impl WebView {
+    // Opens the Windows touch keyboard, as tapping a text field does in tablet mode. The
+    // keyboard only has a toggle, so this does nothing if it's already open.
+    pub fn show_touch_keyboard(&self) -> Result<()> {
+        match is_visible() {
+            true => Ok(()),
+            false => toggle(),
+        }
+    }
+
+    pub fn hide_touch_keyboard(&self) -> Result<()> {
+        match is_visible() {
+            true => toggle(),
+            false => Ok(()),
+        }
+    }
+
+    // Shows the touch keyboard when a text field is tapped, and hides it again when the focus
+    // leaves for something that isn't one. Call it before navigating.
+    pub fn auto_touch_keyboard(&self) -> Result<Subscription> {
+        let w = self.clone();
+        self.bind_unsafe("_taco_touchKeyboard", move |params| {
+            let result = match &params[..] {
+                [Value::Bool(true)] => w.show_touch_keyboard(),
+                [Value::Bool(false)] => w.hide_touch_keyboard(),
+                _ => return Err("Usage: _taco_touchKeyboard(show)".into()),
+            };
+            result.map(|_| Value::Null).map_err(|err| err.to_string())
+        });
+        let binding = self.track_binding("_taco_touchKeyboard");
+        let script = self.track_init(include_str!("touch_keyboard.js"))?;
+        Ok(self.merge(&[binding, script]))
+    }
+}
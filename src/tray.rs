@@ -0,0 +1,99 @@
+use windows::Win32::{
+    Foundation::HWND,
+    UI::{Shell::*, WindowsAndMessaging::*},
+};
+
+use crate::Result;
+
+// The callback message of every tray icon. WPARAM is the icon id and LPARAM the mouse
+// message (WM_LBUTTONUP, WM_RBUTTONUP, ...) or balloon notification (NIN_BALLOONUSERCLICK, ...).
+pub const WM_TRAYICON: u32 = WM_APP + 1;
+
+pub struct TrayIcon {
+    hwnd: HWND,
+    id: u32,
+}
+
+pub(crate) fn copy_wide(dst: &mut [u16], src: &str) {
+    // Truncate so that the last element stays as the null terminator.
+    let len = dst.len() - 1;
+    for (d, c) in dst[..len].iter_mut().zip(src.encode_utf16()) {
+        *d = c;
+    }
+}
+
+// Loads an .ico file, falling back to the default application icon.
+pub fn load_icon(path: Option<&str>) -> HICON {
+    unsafe {
+        if let Some(path) = path {
+            let icon = LoadImageW(
+                None,
+                path,
+                IMAGE_ICON,
+                0,
+                0,
+                LR_LOADFROMFILE | LR_DEFAULTSIZE,
+            );
+            if icon.0 != 0 {
+                return HICON(icon.0);
+            }
+        }
+        LoadIconW(None, IDI_APPLICATION)
+    }
+}
+
+impl TrayIcon {
+    pub fn new(hwnd: HWND, id: u32, icon: HICON, tooltip: &str) -> Result<Self> {
+        let tray = Self { hwnd, id };
+        let mut data = tray.data();
+        data.uFlags = NIF_MESSAGE | NIF_ICON | NIF_TIP;
+        data.uCallbackMessage = WM_TRAYICON;
+        data.hIcon = icon;
+        copy_wide(&mut data.szTip, tooltip);
+        if !unsafe { Shell_NotifyIconW(NIM_ADD, &data) }.as_bool() {
+            return Err(windows::core::Error::from_win32().into());
+        }
+        Ok(tray)
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn data(&self) -> NOTIFYICONDATAW {
+        NOTIFYICONDATAW {
+            cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as _,
+            hWnd: self.hwnd,
+            uID: self.id,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_tooltip(&self, tooltip: &str) -> Result<()> {
+        let mut data = self.data();
+        data.uFlags = NIF_TIP;
+        copy_wide(&mut data.szTip, tooltip);
+        if !unsafe { Shell_NotifyIconW(NIM_MODIFY, &data) }.as_bool() {
+            return Err(windows::core::Error::from_win32().into());
+        }
+        Ok(())
+    }
+
+    pub fn show_balloon(&self, title: &str, body: &str) -> Result<()> {
+        let mut data = self.data();
+        data.uFlags = NIF_INFO;
+        data.dwInfoFlags = NIIF_INFO;
+        copy_wide(&mut data.szInfoTitle, title);
+        copy_wide(&mut data.szInfo, body);
+        if !unsafe { Shell_NotifyIconW(NIM_MODIFY, &data) }.as_bool() {
+            return Err(windows::core::Error::from_win32().into());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TrayIcon {
+    fn drop(&mut self) {
+        unsafe { Shell_NotifyIconW(NIM_DELETE, &self.data()) };
+    }
+}
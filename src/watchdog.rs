@@ -0,0 +1,376 @@
+use std::{
+    cell::RefCell,
+    ffi::{c_void, CStr},
+    fmt, ptr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use windows::Win32::{
+    Foundation::{CloseHandle, BOOL, HANDLE, HWND},
+    System::{
+        Diagnostics::Debug::*,
+        Memory::{VirtualQuery, MEMORY_BASIC_INFORMATION},
+        Threading::{
+            GetCurrentProcess, GetCurrentThreadId, OpenThread, ResumeThread, SuspendThread,
+            WaitForSingleObject, THREAD_GET_CONTEXT, THREAD_QUERY_INFORMATION,
+            THREAD_SUSPEND_RESUME, THREAD_SYNCHRONIZE, WAIT_OBJECT_0,
+        },
+    },
+    UI::WindowsAndMessaging::SetTimer,
+};
+
+use crate::Result;
+
+const MAX_FRAMES: usize = 64;
+const MAX_SYMBOL_NAME: usize = 256;
+// Of the stack above the stack pointer, copied while the thread is suspended.
+const MAX_STACK_COPY: usize = 1 << 20;
+
+// DbgHelp is single-threaded, so watchdogs take turns. True once SymInitialize has run.
+static DBGHELP: Mutex<bool> = Mutex::new(false);
+
+// When the UI thread's heartbeat timer last ran, in milliseconds since `origin`.
+struct Heartbeat {
+    origin: Instant,
+    last: AtomicU64,
+}
+
+impl Heartbeat {
+    fn beat(&self) {
+        let now = self.origin.elapsed().as_millis() as u64;
+        self.last.store(now, Ordering::Relaxed);
+    }
+
+    fn since_last(&self) -> Duration {
+        let last = Duration::from_millis(self.last.load(Ordering::Relaxed));
+        self.origin.elapsed().saturating_sub(last)
+    }
+}
+
+thread_local! {
+    // With the id and period of the thread's timer, which runs as often as the shortest
+    // threshold asks for.
+    static HEARTBEAT: RefCell<Option<(Arc<Heartbeat>, usize, Duration)>> =
+        const { RefCell::new(None) };
+    // On the watchdog's thread while it walks a stack: where the copy starts, and the copy.
+    static STACK_COPY: RefCell<(u64, Vec<u8>)> = const { RefCell::new((0, Vec::new())) };
+}
+
+unsafe extern "system" fn beat(_hwnd: HWND, _msg: u32, _id: usize, _time: u32) {
+    HEARTBEAT.with(|heartbeat| {
+        if let Some((heartbeat, _, _)) = &*heartbeat.borrow() {
+            heartbeat.beat();
+        }
+    });
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct StackFrame {
+    pub address: u64,
+    // Only resolved when DbgHelp finds symbols, i.e. for MSVC builds with their PDBs nearby.
+    pub symbol: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+// The UI thread hadn't pumped messages for `duration` when `backtrace` was taken.
+#[derive(Clone, Debug)]
+pub struct Stall {
+    pub duration: Duration,
+    pub backtrace: Vec<StackFrame>,
+}
+
+impl fmt::Display for Stall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "UI thread stalled for {:?}", self.duration)?;
+        for (i, frame) in self.backtrace.iter().enumerate() {
+            write!(f, "{:>4}: {:#018x}", i, frame.address)?;
+            if let Some(symbol) = &frame.symbol {
+                write!(f, " {}", symbol)?;
+            }
+            if let (Some(file), Some(line)) = (&frame.file, frame.line) {
+                write!(f, " at {}:{}", file, line)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// Where StackWalk64 starts from, given the suspended thread's registers.
+#[cfg(target_arch = "x86_64")]
+fn first_frame(context: &CONTEXT) -> (u32, STACKFRAME64) {
+    let address = |offset| ADDRESS64 {
+        Offset: offset,
+        Segment: 0,
+        Mode: AddrModeFlat,
+    };
+    let frame = STACKFRAME64 {
+        AddrPC: address(context.Rip),
+        AddrFrame: address(context.Rbp),
+        AddrStack: address(context.Rsp),
+        ..Default::default()
+    };
+    (IMAGE_FILE_MACHINE_AMD64 as u32, frame)
+}
+
+#[cfg(target_arch = "x86")]
+fn first_frame(context: &CONTEXT) -> (u32, STACKFRAME64) {
+    let address = |offset: u32| ADDRESS64 {
+        Offset: offset as u64,
+        Segment: 0,
+        Mode: AddrModeFlat,
+    };
+    let frame = STACKFRAME64 {
+        AddrPC: address(context.Eip),
+        AddrFrame: address(context.Ebp),
+        AddrStack: address(context.Esp),
+        ..Default::default()
+    };
+    (IMAGE_FILE_MACHINE_I386 as u32, frame)
+}
+
+// CONTEXT_FULL for the architecture.
+#[cfg(target_arch = "x86_64")]
+const CONTEXT_FULL: u32 = 0x0010_000B;
+#[cfg(target_arch = "x86")]
+const CONTEXT_FULL: u32 = 0x0001_0007;
+
+// GetThreadContext wants a 16-byte aligned CONTEXT on x64.
+#[repr(C, align(16))]
+struct AlignedContext(CONTEXT);
+
+#[repr(C)]
+struct SymbolBuffer {
+    info: SYMBOL_INFO,
+    name: [u8; MAX_SYMBOL_NAME],
+}
+
+unsafe fn resolve(process: HANDLE, address: u64) -> StackFrame {
+    let mut frame = StackFrame {
+        address,
+        ..Default::default()
+    };
+
+    let mut buffer: SymbolBuffer = std::mem::zeroed();
+    buffer.info.SizeOfStruct = std::mem::size_of::<SYMBOL_INFO>() as u32;
+    buffer.info.MaxNameLen = MAX_SYMBOL_NAME as u32;
+    let mut displacement = 0;
+    if SymFromAddr(process, address, &mut displacement, &mut buffer.info).as_bool() {
+        let name = std::slice::from_raw_parts(
+            buffer.info.Name.as_ptr() as *const u8,
+            buffer.info.NameLen as usize,
+        );
+        frame.symbol = Some(String::from_utf8_lossy(name).into_owned());
+    }
+
+    let mut line = IMAGEHLP_LINE64 {
+        SizeOfStruct: std::mem::size_of::<IMAGEHLP_LINE64>() as u32,
+        ..Default::default()
+    };
+    let mut displacement = 0;
+    if SymGetLineFromAddr64(process, address, &mut displacement, &mut line).as_bool() {
+        frame.file = Some(
+            CStr::from_ptr(line.FileName.0 as *const _)
+                .to_string_lossy()
+                .into_owned(),
+        );
+        frame.line = Some(line.LineNumber);
+    }
+    frame
+}
+
+// StackWalk64 wants `extern "system"` callbacks.
+unsafe extern "system" fn function_table_access(process: HANDLE, base: u64) -> *mut c_void {
+    SymFunctionTableAccess64(process, base)
+}
+
+unsafe extern "system" fn module_base(process: HANDLE, address: u64) -> u64 {
+    SymGetModuleBase64(process, address)
+}
+
+// Serves the stack from the copy, as the thread has moved on since; code and unwind data
+// don't change, so the rest is read live.
+unsafe extern "system" fn read_memory(
+    process: HANDLE,
+    address: u64,
+    buffer: *mut c_void,
+    size: u32,
+    read: *mut u32,
+) -> BOOL {
+    let copied = STACK_COPY.with(|copy| {
+        let (start, bytes) = &*copy.borrow();
+        let offset = address.checked_sub(*start)? as usize;
+        let source = bytes.get(offset..offset.checked_add(size as usize)?)?;
+        ptr::copy_nonoverlapping(source.as_ptr(), buffer as *mut u8, source.len());
+        Some(())
+    });
+    let mut count = size as usize;
+    let ok = copied.is_some()
+        || ReadProcessMemory(
+            process,
+            address as *const c_void,
+            buffer,
+            size as usize,
+            &mut count,
+        )
+        .as_bool();
+    if !read.is_null() {
+        *read = count as u32;
+    }
+    ok.into()
+}
+
+// Suspends `thread` only to take its registers and a copy of its stack, which involves no
+// locks: walking and symbolizing take the loader and heap locks, which the thread may hold.
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+unsafe fn capture(thread: HANDLE) -> Vec<StackFrame> {
+    let process = GetCurrentProcess();
+    let mut initialized = DBGHELP.lock().unwrap_or_else(|err| err.into_inner());
+    if !*initialized {
+        SymSetOptions(SYMOPT_UNDNAME | SYMOPT_DEFERRED_LOADS | SYMOPT_LOAD_LINES);
+        SymInitialize(process, None, true);
+        *initialized = true;
+    }
+
+    // Allocated up front, for the same reason.
+    let mut stack = vec![0u8; MAX_STACK_COPY];
+    if SuspendThread(thread) == u32::MAX {
+        return Vec::new();
+    }
+    let mut context: AlignedContext = std::mem::zeroed();
+    context.0.ContextFlags = CONTEXT_FULL;
+    let captured = GetThreadContext(thread, &mut context.0).as_bool();
+    let (machine, mut frame) = first_frame(&context.0);
+    let start = frame.AddrStack.Offset;
+    let mut region = MEMORY_BASIC_INFORMATION::default();
+    let size = std::mem::size_of::<MEMORY_BASIC_INFORMATION>();
+    // The committed stack from the stack pointer up to the stack's base.
+    let len = if captured && VirtualQuery(start as *const c_void, &mut region, size) != 0 {
+        let end = region.BaseAddress as u64 + region.RegionSize as u64;
+        (end.saturating_sub(start) as usize).min(MAX_STACK_COPY)
+    } else {
+        0
+    };
+    if len > 0 {
+        ptr::copy_nonoverlapping(start as *const u8, stack.as_mut_ptr(), len);
+    }
+    ResumeThread(thread);
+    if !captured {
+        return Vec::new();
+    }
+
+    stack.truncate(len);
+    STACK_COPY.with(|copy| *copy.borrow_mut() = (start, stack));
+    let mut addresses = Vec::with_capacity(MAX_FRAMES);
+    while addresses.len() < MAX_FRAMES
+        && StackWalk64(
+            machine,
+            process,
+            thread,
+            &mut frame,
+            &mut context.0 as *mut CONTEXT as *mut _,
+            Some(read_memory),
+            Some(function_table_access),
+            Some(module_base),
+            None,
+        )
+        .as_bool()
+        && frame.AddrPC.Offset != 0
+    {
+        addresses.push(frame.AddrPC.Offset);
+    }
+    STACK_COPY.with(|copy| *copy.borrow_mut() = (0, Vec::new()));
+
+    addresses
+        .into_iter()
+        .map(|address| resolve(process, address))
+        .collect()
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+unsafe fn capture(_thread: HANDLE) -> Vec<StackFrame> {
+    Vec::new()
+}
+
+// Calls `f` (on a thread of its own) whenever the calling thread goes `threshold` without
+// pumping messages, with the stack it's stuck in, e.g. a binding that blocks on I/O. Each stall
+// is reported once, while it's still going on. Call it from the thread that runs the window;
+// watching it again with a shorter threshold makes the heartbeat run more often.
+pub fn watch_event_loop(
+    threshold: Duration,
+    mut f: impl FnMut(&Stall) + Send + 'static,
+) -> Result<()> {
+    // How often the heartbeat runs, and how often the watchdog looks at it.
+    let period = (threshold / 4).max(Duration::from_millis(10));
+
+    let heartbeat = HEARTBEAT.with(|heartbeat| {
+        let mut heartbeat = heartbeat.borrow_mut();
+        match &mut *heartbeat {
+            Some((heartbeat, timer, current)) => {
+                if period < *current {
+                    // Replaces the timer with the same id.
+                    unsafe {
+                        SetTimer(
+                            HWND::default(),
+                            *timer,
+                            period.as_millis() as u32,
+                            Some(beat),
+                        )
+                    };
+                    *current = period;
+                }
+                heartbeat.clone()
+            }
+            None => {
+                let timer =
+                    unsafe { SetTimer(HWND::default(), 0, period.as_millis() as u32, Some(beat)) };
+                let beats = Arc::new(Heartbeat {
+                    origin: Instant::now(),
+                    last: AtomicU64::new(0),
+                });
+                *heartbeat = Some((beats.clone(), timer, period));
+                beats
+            }
+        }
+    });
+
+    let thread = unsafe {
+        OpenThread(
+            THREAD_SUSPEND_RESUME
+                | THREAD_GET_CONTEXT
+                | THREAD_QUERY_INFORMATION
+                | THREAD_SYNCHRONIZE,
+            false,
+            GetCurrentThreadId(),
+        )
+    };
+    if thread.is_invalid() {
+        return Err(windows::core::Error::from_win32().into());
+    }
+
+    thread::spawn(move || {
+        let mut reported = false;
+        // Stops once the watched thread has exited.
+        while unsafe { WaitForSingleObject(thread, period.as_millis() as u32) } != WAIT_OBJECT_0 {
+            let stalled = heartbeat.since_last();
+            if stalled < threshold {
+                reported = false;
+            } else if !reported {
+                reported = true;
+                let backtrace = unsafe { capture(thread) };
+                f(&Stall {
+                    duration: stalled,
+                    backtrace,
+                });
+            }
+        }
+        unsafe { CloseHandle(thread) };
+    });
+    Ok(())
+}
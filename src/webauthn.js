@@ -0,0 +1,19 @@
+// Called with the name of the binding that is told about each WebAuthn ceremony.
+(function (binding) {
+    const credentials = navigator.credentials;
+    if (!credentials) return;
+
+    const hook = (method, kind) => {
+        const original = credentials[method].bind(credentials);
+        credentials[method] = options => {
+            if (options && options.publicKey) {
+                const publicKey = options.publicKey;
+                const rpId = kind === 'create' ? publicKey.rp && publicKey.rp.id : publicKey.rpId;
+                window[binding](kind, location.origin, rpId || null);
+            }
+            return original(options);
+        };
+    };
+    hook('create', 'create');
+    hook('get', 'get');
+})
@@ -0,0 +1,64 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde_json::Value;
+
+use crate::{event::Subscription, Result, WebView};
+
+static NEXT_BINDING: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebAuthnKind {
+    // navigator.credentials.create(), e.g. registering a passkey.
+    Create,
+    // navigator.credentials.get(), i.e. signing in.
+    Get,
+}
+
+#[derive(Clone, Debug)]
+pub struct WebAuthnCeremony {
+    pub kind: WebAuthnKind,
+    pub origin: String,
+    // None if the page leaves it to default to the origin's host.
+    pub rp_id: Option<String>,
+}
+
+impl WebView {
+    // Called when the page starts a WebAuthn ceremony, before Windows Hello prompts the user.
+    // It only observes: the page's `navigator.credentials.create`/`get` are wrapped to report
+    // their options, in the top document only, and the ceremony itself is left to WebView2.
+    //
+    // There is nothing to configure for it either. WebView2 has no settings or environment
+    // options for WebAuthn, and passes it through to Windows itself; it only needs a secure
+    // context, so serve login pages over https (or `map_host`/`register_protocol`, which are
+    // https too) and keep the relying party id a suffix of the page's host.
+    pub fn on_webauthn(
+        &self,
+        mut f: impl FnMut(&WebAuthnCeremony) + 'static,
+    ) -> Result<Subscription> {
+        let name = format!(
+            "_taco_webauthn{}",
+            NEXT_BINDING.fetch_add(1, Ordering::Relaxed)
+        );
+        self.bind_unsafe(&name, move |params| {
+            if let [Value::String(kind), Value::String(origin), rp_id] = &params[..] {
+                f(&WebAuthnCeremony {
+                    kind: match kind.as_str() {
+                        "create" => WebAuthnKind::Create,
+                        _ => WebAuthnKind::Get,
+                    },
+                    origin: origin.clone(),
+                    rp_id: rp_id.as_str().map(String::from),
+                });
+                return Ok(Value::Null);
+            }
+            Err("Usage: _taco_webauthn(kind, origin, rpId)".into())
+        });
+        let binding = self.track_binding(&name);
+        let script = self.track_init(&format!(
+            "{}({});",
+            include_str!("webauthn.js"),
+            Value::String(name)
+        ))?;
+        Ok(self.merge(&[binding, script]))
+    }
+}
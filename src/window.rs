@@ -1,42 +1,204 @@
-use crate::Result;
-use crate::{GetWindowLong, SetWindowLong};
+use crate::{com, Result};
+use crate::{GetWindowLong, SetWindowLong, WebView};
 
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::ffi::CString;
 use std::marker::PhantomData;
 use std::ptr::null;
+use std::rc::Rc;
+use std::time::Instant;
 
 use windows::Win32::{
-    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, PSTR, RECT, WPARAM},
-    System::LibraryLoader::GetModuleHandleA,
+    Foundation::{HANDLE, HINSTANCE, HWND, LPARAM, LRESULT, PSTR, RECT, WPARAM},
+    Graphics::{Dwm, Gdi},
+    System::{LibraryLoader::GetModuleHandleA, Shutdown, Threading::GetCurrentThreadId},
     UI::HiDpi,
     UI::WindowsAndMessaging::*,
 };
 
-type WndProcs = HashMap<u32, Vec<Box<dyn FnMut(WPARAM, LPARAM)>>>;
+type WndProcs<T> = HashMap<u32, Vec<Box<dyn FnMut(WPARAM, LPARAM, &T)>>>;
+// What GWLP_USERDATA points to while running; returns whether the message had listeners.
+type Dispatcher = Box<dyn FnMut(u32, WPARAM, LPARAM) -> bool>;
+type EventListeners = Rc<RefCell<Vec<Box<dyn FnMut(WindowEvent)>>>>;
+
+// WM_APP..USER_MESSAGE_BASE is reserved for taco (dispatch, tray icons, ...);
+// `register_user_message` hands out ids above it.
+pub const USER_MESSAGE_BASE: u32 = WM_APP + 0x100;
+const USER_MESSAGE_LAST: u32 = 0xBFFF;
+
+// Set on windows started with `run_detached`.
+const DETACHED_PROP: &str = "taco.detached";
+
+thread_local! {
+    // What the message being dispatched is answered with, if not 0; see `set_message_result`.
+    static MESSAGE_RESULT: Cell<Option<LRESULT>> = const { Cell::new(None) };
+    // How many `wndproc` calls are on the stack. A detached dispatcher whose window is
+    // destroyed from one of its own listeners is only freed once they have all returned.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    static DESTROYED: RefCell<Vec<*mut Dispatcher>> = const { RefCell::new(Vec::new()) };
+}
+
+// Answers the message a listener is handling with `result` instead of 0, e.g. TRUE for
+// WM_SETCURSOR.
+pub fn set_message_result(result: LRESULT) {
+    MESSAGE_RESULT.with(|r| r.set(Some(result)));
+}
+
+const OCCLUSION_TIMER_ID: usize = 1;
+const OCCLUSION_POLL_MS: u32 = 500;
+
+const DPI_TIMER_ID: usize = 5;
+const DPI_ANIMATION_MS: f64 = 150.;
+const DPI_FRAME_MS: u32 = 15;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindowEvent {
+    // The window became fully covered (or minimized / cloaked) and is no longer visible.
+    Occluded(bool),
+    // Windows is about to log off, restart or shut down. Call `block_shutdown` to ask the user
+    // to wait while state is saved.
+    QueryEndSession { logoff: bool, critical: bool },
+    // Follows QueryEndSession. `ending` is false if the shutdown was cancelled; if true, the
+    // process may be terminated as soon as the listeners return.
+    EndSession { ending: bool },
+    // The window moved to a monitor with another scale factor (old, new), e.g. 1.0 -> 1.5.
+    // Sent once the window got its new size, or started animating towards it.
+    ScaleFactorChanged(f64, f64),
+}
 
 pub struct WindowRunner<T> {
     hwnd: HWND,
-    wndprocs: WndProcs,
+    // The thread that created the window, which is the only one receiving its messages.
+    thread: u32,
+    wndprocs: WndProcs<T>,
+    event_listeners: EventListeners,
+    next_user_message: u32,
+    animate_dpi_changes: Rc<Cell<bool>>,
+    quit_on_destroy: Rc<Cell<bool>>,
     luggage_type: PhantomData<fn() -> T>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+// A window message carrying a `P`, posted with `WindowHandle::post`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UserMsg<P> {
+    pub id: u32,
+    payload_type: PhantomData<fn() -> P>,
+}
+
+impl<P> Clone for UserMsg<P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P> Copy for UserMsg<P> {}
+
+#[derive(Debug, PartialEq, Eq)]
 pub struct WindowHandle<T> {
     pub hwnd: HWND,
     pub hinstance: HINSTANCE,
     luggage_type: PhantomData<fn() -> T>,
 }
 
+// Not derived, so that handles can be cloned whatever the luggage type is.
+impl<T> Clone for WindowHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            hwnd: self.hwnd,
+            hinstance: self.hinstance,
+            luggage_type: PhantomData,
+        }
+    }
+}
+
 impl<T: Clone> Copy for WindowHandle<T> {}
 
+// What `WebViewBuilder::build` returns. The webview only works while `run` is pumping
+// messages, so keeping the runner here makes it hard to drop by accident.
+pub struct Window<T> {
+    webview: WebView,
+    runner: WindowRunner<T>,
+    handle: WindowHandle<T>,
+}
+
+impl<T: 'static> Window<T> {
+    pub(crate) fn new(webview: WebView, runner: WindowRunner<T>, handle: WindowHandle<T>) -> Self {
+        Self {
+            webview,
+            runner,
+            handle,
+        }
+    }
+
+    pub fn webview(&self) -> &WebView {
+        &self.webview
+    }
+
+    // Can be sent to other threads to dispatch closures onto this window.
+    pub fn handle(&self) -> WindowHandle<T> {
+        self.handle.clone()
+    }
+
+    pub fn runner(&mut self) -> &mut WindowRunner<T> {
+        &mut self.runner
+    }
+
+    pub fn add_event_listener(&mut self, msg: u32, f: impl FnMut(WPARAM, LPARAM, &T) + 'static) {
+        self.runner.add_event_listener(msg, f);
+    }
+
+    pub fn on_window_event(&mut self, f: impl FnMut(WindowEvent) + 'static) {
+        self.runner.on_window_event(f);
+    }
+
+    pub fn run(self, luggage: T) -> Result<()> {
+        self.runner.run(luggage)
+    }
+
+    // See `WindowRunner::run_detached`.
+    pub fn run_detached(self, luggage: T) -> WebView {
+        self.runner.run_detached(luggage);
+        self.webview
+    }
+
+    pub fn into_parts(self) -> (WebView, WindowRunner<T>, WindowHandle<T>) {
+        (self.webview, self.runner, self.handle)
+    }
+}
+
 extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    DEPTH.with(|d| d.set(d.get() + 1));
+    let result = dispatch(hwnd, msg, wparam, lparam);
+    if DEPTH.with(|d| d.replace(d.get() - 1)) == 1 {
+        for p in DESTROYED.with(|d| std::mem::take(&mut *d.borrow_mut())) {
+            drop(unsafe { Box::from_raw(p) });
+        }
+    }
+    result
+}
+
+fn dispatch(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
     unsafe {
-        let p = GetWindowLong(hwnd, GWLP_USERDATA) as *mut WndProcs;
-        if let Some(wndprocs) = p.as_mut() {
-            if let Some(fs) = wndprocs.get_mut(&msg) {
-                for f in fs.iter_mut() {
-                    f(wparam, lparam);
+        let p = GetWindowLong(hwnd, GWLP_USERDATA) as *mut Dispatcher;
+        if let Some(dispatch) = p.as_mut() {
+            MESSAGE_RESULT.with(|r| r.set(None));
+            let handled = dispatch(msg, wparam, lparam);
+            let result = MESSAGE_RESULT.with(|r| r.take());
+            // The last message; a detached dispatcher is owned by the window. It may be
+            // destroyed by one of the listeners, which are still running further up the stack.
+            if msg == WM_NCDESTROY && RemovePropA(hwnd, DETACHED_PROP).0 != 0 {
+                SetWindowLong(hwnd, GWLP_USERDATA, 0);
+                DESTROYED.with(|d| d.borrow_mut().push(p));
+            }
+            if handled {
+                if let Some(result) = result {
+                    return result;
+                }
+                // Returning 0 here would veto the shutdown; blocking is done with
+                // ShutdownBlockReasonCreate instead.
+                if msg == WM_QUERYENDSESSION {
+                    return LRESULT(1);
                 }
                 return LRESULT::default();
             }
@@ -47,52 +209,216 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM)
 
 impl<T: 'static> WindowRunner<T> {
     pub fn run(mut self, luggage: T) -> Result<()> {
-        self.add_event_listener(WM_APP, move |_, lparam| unsafe {
-            let p = lparam.0 as *mut Box<dyn FnOnce(&T) -> Result<()>>;
-            let f = Box::from_raw(p);
-            f(&luggage).unwrap();
-        });
-        let p = &mut self.wndprocs as *mut WndProcs;
+        com::assert_thread(self.thread, "WindowRunner::run");
+        let mut dispatch = self.dispatcher(luggage);
+        let p = &mut dispatch as *mut Dispatcher;
         unsafe { SetWindowLong(self.hwnd, GWLP_USERDATA, p as _) };
 
         let mut msg = MSG::default();
 
-        loop {
+        let result = loop {
             unsafe {
                 let result = GetMessageA(&mut msg, None, 0, 0).0;
 
                 match result {
                     -1 => break Err(windows::core::Error::from_win32().into()),
                     0 => break Ok(()),
-                    _ => match msg.message {
-                        _ => {
-                            TranslateMessage(&msg);
-                            DispatchMessageA(&msg);
-                        }
-                    },
+                    _ => {
+                        TranslateMessage(&msg);
+                        DispatchMessageA(&msg);
+                    }
                 }
             }
-        }
+        };
+
+        unsafe { SetWindowLong(self.hwnd, GWLP_USERDATA, 0) };
+        result
     }
 
-    pub fn add_event_listener(&mut self, msg: u32, f: impl FnMut(WPARAM, LPARAM) + 'static) {
-        if !self.wndprocs.contains_key(&msg) {
-            self.wndprocs.insert(msg, Vec::new());
+    // Starts handling the window's messages without a loop of its own, for further windows on a
+    // thread where another window's `run` already pumps them. Destroying the window then
+    // doesn't quit that loop, and the listeners and luggage are dropped along with the window.
+    pub fn run_detached(mut self, luggage: T) {
+        com::assert_thread(self.thread, "WindowRunner::run_detached");
+        self.quit_on_destroy.set(false);
+        let dispatch = Box::into_raw(Box::new(self.dispatcher(luggage)));
+        unsafe {
+            SetPropA(self.hwnd, DETACHED_PROP, HANDLE(1));
+            SetWindowLong(self.hwnd, GWLP_USERDATA, dispatch as _);
         }
-        let fs = self.wndprocs.get_mut(&msg).unwrap();
+    }
+
+    fn dispatcher(&mut self, luggage: T) -> Dispatcher {
+        self.add_event_listener(WM_APP, move |_, lparam, luggage| unsafe {
+            let p = lparam.0 as *mut Box<dyn FnOnce(&T) -> Result<()>>;
+            let f = Box::from_raw(p);
+            #[cfg(feature = "diagnostics")]
+            crate::diagnostics::dispatch_ran();
+            f(luggage).unwrap();
+        });
+
+        // Listeners and dispatched closures share the luggage, which lives in the dispatcher.
+        let mut wndprocs = std::mem::take(&mut self.wndprocs);
+        Box::new(move |msg, wparam, lparam| match wndprocs.get_mut(&msg) {
+            Some(fs) => {
+                for f in fs.iter_mut() {
+                    f(wparam, lparam, &luggage);
+                }
+                true
+            }
+            None => false,
+        })
+    }
+
+    // `f` also gets the luggage passed to `run`, and is only called while running.
+    pub fn add_event_listener(&mut self, msg: u32, f: impl FnMut(WPARAM, LPARAM, &T) + 'static) {
+        com::assert_thread(self.thread, "WindowRunner::add_event_listener");
+        let fs = self.wndprocs.entry(msg).or_default();
         let f = Box::new(f) as _;
         fs.push(f);
     }
 
+    pub fn register_user_message<P: Send + 'static>(&mut self) -> UserMsg<P> {
+        let id = self.next_user_message;
+        assert!(id <= USER_MESSAGE_LAST, "out of user message ids");
+        self.next_user_message += 1;
+        UserMsg {
+            id,
+            payload_type: PhantomData,
+        }
+    }
+
+    // Payloads posted while no listener is registered are leaked.
+    pub fn on_user_message<P: 'static>(
+        &mut self,
+        msg: UserMsg<P>,
+        mut f: impl FnMut(P, &T) + 'static,
+    ) {
+        self.add_event_listener(msg.id, move |_, lparam, luggage| {
+            let payload = unsafe { Box::from_raw(lparam.0 as *mut P) };
+            f(*payload, luggage);
+        });
+    }
+
     pub fn reset_event_listeners(&mut self, msg: u32) {
         self.wndprocs.remove(&msg);
     }
+
+    pub fn hwnd(&self) -> HWND {
+        self.hwnd
+    }
+
+    // Resizes the window smoothly when it moves to a monitor with another scale factor,
+    // instead of jumping to the new size.
+    pub fn set_animate_dpi_changes(&mut self, animate: bool) {
+        self.animate_dpi_changes.set(animate);
+    }
+
+    pub fn on_window_event(&mut self, f: impl FnMut(WindowEvent) + 'static) {
+        com::assert_thread(self.thread, "WindowRunner::on_window_event");
+        let mut listeners = self.event_listeners.borrow_mut();
+        // Nobody to tell about occlusion before the first listener.
+        if listeners.is_empty() {
+            unsafe { SetTimer(self.hwnd, OCCLUSION_TIMER_ID, OCCLUSION_POLL_MS, None) };
+        }
+        listeners.push(Box::new(f));
+    }
+}
+
+fn set_window_rect(hwnd: HWND, rect: RECT) {
+    let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+    unsafe {
+        SetWindowPos(
+            hwnd,
+            None,
+            rect.left,
+            rect.top,
+            width,
+            height,
+            SWP_NOZORDER | SWP_NOACTIVATE,
+        )
+    };
+}
+
+fn emit_window_event(listeners: &EventListeners, event: WindowEvent) {
+    for f in listeners.borrow_mut().iter_mut() {
+        f(event);
+    }
+}
+
+// Shows `reason` on the shutdown screen and keeps Windows from ending the session until
+// `unblock_shutdown` is called (or the user forces it).
+pub fn block_shutdown(hwnd: HWND, reason: &str) -> Result<()> {
+    unsafe { Shutdown::ShutdownBlockReasonCreate(hwnd, reason).ok()? };
+    Ok(())
+}
+
+pub fn unblock_shutdown(hwnd: HWND) -> Result<()> {
+    unsafe { Shutdown::ShutdownBlockReasonDestroy(hwnd).ok()? };
+    Ok(())
+}
+
+fn is_cloaked(hwnd: HWND) -> bool {
+    let mut cloaked = 0u32;
+    unsafe {
+        Dwm::DwmGetWindowAttribute(
+            hwnd,
+            Dwm::DWMWA_CLOAKED,
+            &mut cloaked as *mut _ as _,
+            std::mem::size_of::<u32>() as _,
+        )
+        .is_ok()
+            && cloaked != 0
+    }
+}
+
+// There is no occlusion notification for plain Win32 windows, so subtract the rects of
+// every visible window above ours in the z-order and see if anything is left.
+pub fn is_occluded(hwnd: HWND) -> bool {
+    unsafe {
+        if IsIconic(hwnd).as_bool() || !IsWindowVisible(hwnd).as_bool() || is_cloaked(hwnd) {
+            return true;
+        }
+
+        let mut rect = RECT::default();
+        GetWindowRect(hwnd, &mut rect);
+        let remaining = Gdi::CreateRectRgnIndirect(&rect);
+
+        let mut occluded = false;
+        let mut above = GetWindow(hwnd, GW_HWNDPREV);
+        while above.0 != 0 {
+            let exstyle = GetWindowLong(above, GWL_EXSTYLE) as u32;
+            // Layered and click-through windows are usually (partly) see-through.
+            let translucent = exstyle & (WS_EX_LAYERED | WS_EX_TRANSPARENT) != 0;
+            if IsWindowVisible(above).as_bool()
+                && !IsIconic(above).as_bool()
+                && !translucent
+                && !is_cloaked(above)
+            {
+                let mut rect = RECT::default();
+                GetWindowRect(above, &mut rect);
+                let hrgn = Gdi::CreateRectRgnIndirect(&rect);
+                let result = Gdi::CombineRgn(remaining, remaining, hrgn, Gdi::RGN_DIFF);
+                Gdi::DeleteObject(hrgn);
+                if result == Gdi::NULLREGION as i32 {
+                    occluded = true;
+                    break;
+                }
+            }
+            above = GetWindow(above, GW_HWNDPREV);
+        }
+
+        Gdi::DeleteObject(remaining);
+        occluded
+    }
 }
 
 pub fn dispatch_unsafe<T>(hwnd: HWND, f: impl FnOnce(&T) -> Result<()>) {
     let f = Box::new(f) as Box<dyn FnOnce(&T) -> Result<()>>;
     let f = Box::new(f);
     let p = Box::into_raw(f);
+    #[cfg(feature = "diagnostics")]
+    crate::diagnostics::dispatch_posted();
     unsafe { PostMessageA(hwnd, WM_APP, WPARAM(0), LPARAM(p as _)) };
 }
 
@@ -100,8 +426,20 @@ impl<T> WindowHandle<T> {
     pub fn dispatch(&self, f: impl FnOnce(&T) -> Result<()> + Send + 'static) {
         dispatch_unsafe(self.hwnd, f)
     }
+
+    pub fn post<P: Send + 'static>(&self, msg: UserMsg<P>, payload: P) -> Result<()> {
+        let p = Box::into_raw(Box::new(payload));
+        unsafe {
+            if !PostMessageA(self.hwnd, msg.id, WPARAM(0), LPARAM(p as _)).as_bool() {
+                drop(Box::from_raw(p));
+                return Err(windows::core::Error::from_win32().into());
+            }
+        }
+        Ok(())
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_window<T: 'static>(
     style: WINDOW_STYLE,
     exstyle: WINDOW_EX_STYLE,
@@ -151,26 +489,113 @@ pub fn create_window<T: 'static>(
 
     let mut wrun = WindowRunner {
         hwnd,
+        thread: unsafe { GetCurrentThreadId() },
         wndprocs: HashMap::new(),
+        event_listeners: Rc::new(RefCell::new(Vec::new())),
+        next_user_message: USER_MESSAGE_BASE,
+        animate_dpi_changes: Rc::new(Cell::new(false)),
+        quit_on_destroy: Rc::new(Cell::new(true)),
         luggage_type: PhantomData,
     };
 
-    // wrun.add_event_listener(msg, f)
-    wrun.add_event_listener(WM_DPICHANGED, move |_, lparam| unsafe {
-        let rect = *(lparam.0 as *mut RECT);
-        let x = rect.left;
-        let y = rect.top;
-        let w = rect.right - x;
-        let h = rect.bottom - y;
-        SetWindowPos(hwnd, None, x, y, w, h, Default::default());
+    // Windows suggests a rect that keeps the window the same size in logical pixels.
+    let dpi = Rc::new(Cell::new(unsafe { HiDpi::GetDpiForWindow(hwnd) }));
+    let animation = Rc::new(Cell::new(None::<(RECT, RECT, Instant)>));
+    let listeners = wrun.event_listeners.clone();
+    let animate = wrun.animate_dpi_changes.clone();
+    let a = animation.clone();
+    wrun.add_event_listener(WM_DPICHANGED, move |wparam, lparam, _| unsafe {
+        let target = *(lparam.0 as *mut RECT);
+        if animate.get() {
+            let mut current = RECT::default();
+            GetWindowRect(hwnd, &mut current);
+            a.set(Some((current, target, Instant::now())));
+            SetTimer(hwnd, DPI_TIMER_ID, DPI_FRAME_MS, None);
+        } else {
+            set_window_rect(hwnd, target);
+        }
+        let new = (wparam.0 >> 16) as u32 & 0xffff;
+        let old = dpi.replace(new);
+        let event = WindowEvent::ScaleFactorChanged(old as f64 / 96., new as f64 / 96.);
+        emit_window_event(&listeners, event);
+    });
+    wrun.add_event_listener(WM_TIMER, move |wparam, _, _| {
+        if wparam.0 != DPI_TIMER_ID {
+            return;
+        }
+        let (from, to, started) = match animation.get() {
+            Some(animation) => animation,
+            None => return,
+        };
+        let t = (started.elapsed().as_secs_f64() * 1000. / DPI_ANIMATION_MS).min(1.);
+        // Ease out.
+        let t = 1. - (1. - t).powi(3);
+        let lerp = |a: i32, b: i32| a + ((b - a) as f64 * t).round() as i32;
+        let rect = RECT {
+            left: lerp(from.left, to.left),
+            top: lerp(from.top, to.top),
+            right: lerp(from.right, to.right),
+            bottom: lerp(from.bottom, to.bottom),
+        };
+        set_window_rect(hwnd, rect);
+        if t >= 1. {
+            animation.set(None);
+            unsafe { KillTimer(hwnd, DPI_TIMER_ID) };
+        }
     });
 
-    wrun.add_event_listener(WM_CLOSE, move |_, _| unsafe {
+    // Occlusion is polled since other windows moving over ours don't notify us. The timer is
+    // started by `on_window_event`.
+    let occluded = Rc::new(RefCell::new(false));
+    let check_occlusion = {
+        let listeners = wrun.event_listeners.clone();
+        move || {
+            if listeners.borrow().is_empty() {
+                return;
+            }
+            let now = is_occluded(hwnd);
+            if now != occluded.replace(now) {
+                emit_window_event(&listeners, WindowEvent::Occluded(now));
+            }
+        }
+    };
+    let check = check_occlusion.clone();
+    wrun.add_event_listener(WM_TIMER, move |wparam, _, _| {
+        if wparam.0 == OCCLUSION_TIMER_ID {
+            check();
+        }
+    });
+    let check = check_occlusion;
+    wrun.add_event_listener(WM_SIZE, move |_, _, _| check());
+
+    let listeners = wrun.event_listeners.clone();
+    wrun.add_event_listener(WM_QUERYENDSESSION, move |_, lparam, _| {
+        let flags = lparam.0 as u32;
+        let event = WindowEvent::QueryEndSession {
+            logoff: flags & ENDSESSION_LOGOFF != 0,
+            critical: flags & ENDSESSION_CRITICAL != 0,
+        };
+        emit_window_event(&listeners, event);
+    });
+    let listeners = wrun.event_listeners.clone();
+    wrun.add_event_listener(WM_ENDSESSION, move |wparam, _, _| {
+        let event = WindowEvent::EndSession {
+            ending: wparam.0 != 0,
+        };
+        emit_window_event(&listeners, event);
+    });
+
+    wrun.add_event_listener(WM_CLOSE, move |_, _, _| unsafe {
         DestroyWindow(hwnd);
     });
 
-    wrun.add_event_listener(WM_DESTROY, |_, _| unsafe {
-        PostQuitMessage(0);
+    let quit = wrun.quit_on_destroy.clone();
+    wrun.add_event_listener(WM_DESTROY, move |_, _, _| unsafe {
+        KillTimer(hwnd, OCCLUSION_TIMER_ID);
+        KillTimer(hwnd, DPI_TIMER_ID);
+        if quit.get() {
+            PostQuitMessage(0);
+        }
     });
 
     let whandle = WindowHandle {
@@ -0,0 +1,10 @@
+(function () {
+    const taco = window.taco = window.taco || {};
+
+    taco.windows = {
+        // Resolves once the message is queued for the window with that label.
+        postMessage: (label, data) => window._taco_postWindowMessage(label, data === undefined ? null : data),
+        // The callback gets the data and the label of the sending window.
+        onMessage: callback => taco.on('window-message', message => callback(message.data, message.from)),
+    };
+})();